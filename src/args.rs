@@ -0,0 +1,51 @@
+use crate::error::Error;
+
+// Splits the commandline arguments that come after a command into positional arguments and
+// "--flag value" / "--switch" options
+pub struct Args {
+    pub positional: Vec<String>,
+    options: Vec<(String, String)>, // (flag name without the dashes, value)
+    switches: Vec<String>
+}
+
+impl Args {
+    // takes_value lists the flags that are followed by a value, switches lists the ones that
+    // aren't. Anything else starting with "--" is rejected.
+    pub fn parse(raw: &[String], takes_value: &[&str], switches: &[&str]) -> Result<Args, Error> {
+        let mut parsed = Args {
+            positional: vec!(),
+            options: vec!(),
+            switches: vec!()
+        };
+        let mut iter = raw.iter();
+        while let Some(arg) = iter.next() {
+            let name = match arg.strip_prefix("--") {
+                Some(x) => x,
+                None => {
+                    parsed.positional.push(arg.clone());
+                    continue;
+                }
+            };
+            if takes_value.contains(&name) {
+                match iter.next() {
+                    Some(x) => parsed.options.push((name.to_string(), x.clone())),
+                    None => return Err(Error::Usage(format!("The option --{} needs a value after it.\nRun 'purchase_tracker --help' for more information", name)))
+                }
+            } else if switches.contains(&name) {
+                parsed.switches.push(name.to_string());
+            } else {
+                return Err(Error::Usage(format!("Unknown option --{}.\nRun 'purchase_tracker --help' for more information", name)));
+            }
+        }
+        Ok(parsed)
+    }
+
+    // The value given to a flag. If the flag was given more than once, the last one wins.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(flag, _)| flag == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn switch(&self, name: &str) -> bool {
+        self.switches.iter().any(|x| x == name)
+    }
+}
@@ -0,0 +1,420 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::args::Args;
+use crate::error::Error;
+use crate::{read_line, storage, Date};
+
+/*
+ * Before a ledger gets overwritten, a byte-for-byte copy of it is put in a backups directory next to
+ * it, named "backup_{ledger file name}_{unix timestamp}". If two snapshots of the same ledger land
+ * in the same second, the later ones get a ".1", ".2", ... suffix.
+ *
+ * Snapshots are thinned out after every write according to the ledger's retention policy, which is
+ * stored in the same directory in "retention_{ledger file name}".
+ */
+const BACKUP_DIR: &str = ".purchase_tracker_backups";
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+pub struct Retention {
+    pub keep_last: u64, // Always keep this many of the newest snapshots
+    pub keep_daily: u64, // Keep the newest snapshot of each of this many most recent days that have one
+    pub keep_weekly: u64 // Same thing, but for weeks (starting on Monday)
+}
+// A rule set to 0 is disabled. The newest snapshot is always kept no matter what.
+
+impl Default for Retention {
+    fn default() -> Retention {
+        Retention {
+            keep_last: 10,
+            keep_daily: 7,
+            keep_weekly: 4
+        }
+    }
+}
+
+impl ToString for Retention {
+    fn to_string(&self) -> String {
+        format!("keep_last = {}\nkeep_daily = {}\nkeep_weekly = {}\n", self.keep_last, self.keep_daily, self.keep_weekly)
+    }
+}
+
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub timestamp: u64, // Seconds since the unix epoch
+    sequence: u64 // Tiebreaker for snapshots taken in the same second
+}
+
+pub fn backup_dir(ledger: &Path) -> PathBuf {
+    match ledger.parent() {
+        Some(x) if !x.as_os_str().is_empty() => x.join(BACKUP_DIR),
+        _ => PathBuf::from(BACKUP_DIR)
+    }
+}
+
+fn ledger_name(ledger: &Path) -> Result<String, Error> {
+    match ledger.file_name() {
+        Some(x) => Ok(x.to_string_lossy().to_string()),
+        None => Err(Error::validation(&ledger.display().to_string(), "that doesn't name a file, so it can't be backed up"))
+    }
+}
+
+fn retention_path(ledger: &Path) -> Result<PathBuf, Error> {
+    Ok(backup_dir(ledger).join(format!("retention_{}", ledger_name(ledger)?)))
+}
+
+// Copies ledger into the backups directory. Does nothing if the ledger doesn't exist yet.
+pub fn snapshot(ledger: &Path) -> Result<Option<PathBuf>, Error> {
+    match fs::exists(ledger) {
+        Ok(true) => (),
+        Ok(false) => return Ok(None),
+        Err(x) => return Err(Error::io("checking whether this file exists:", ledger, x))
+    }
+    let dir = backup_dir(ledger);
+    if let Err(x) = fs::create_dir_all(&dir) {
+        return Err(Error::io("creating the backups directory", &dir, x));
+    }
+
+    let unix_timestamp = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(x) => x.as_secs(),
+        Err(x) => return Err(Error::io("backing up", ledger, io::Error::other(format!("Failed to fetch system time. You should check your system's clock\nIf you're wondering why I need the system clock, it's for giving the backup file a unique name\nIf it helps, the error in question was: {}", x))))
+    };
+    let base_name = format!("backup_{}_{}", ledger_name(ledger)?, unix_timestamp);
+    // Older snapshots from this same second may have been pruned already, so count up from the
+    // newest surviving one rather than reusing a freed name that would sort before it
+    let mut sequence = match list(ledger)?.first() {
+        Some(x) if x.timestamp == unix_timestamp => x.sequence + 1,
+        _ => 0
+    };
+    loop {
+        let backup_path = match sequence {
+            0 => dir.join(&base_name),
+            x => dir.join(format!("{}.{}", base_name, x))
+        };
+        // Claim the name first so that two runs at the same time can't write the same backup
+        match fs::File::create_new(&backup_path) {
+            Ok(_) => {
+                if let Err(x) = fs::copy(ledger, &backup_path) {
+                    return Err(Error::io(&format!("copying {} to", ledger.display()), &backup_path, x));
+                }
+                return Ok(Some(backup_path));
+            },
+            Err(x) if x.kind() == io::ErrorKind::AlreadyExists => sequence += 1,
+            Err(x) => return Err(Error::io("creating the backup file", &backup_path, x))
+        }
+    }
+}
+
+// All snapshots of ledger, newest first
+pub fn list(ledger: &Path) -> Result<Vec<Snapshot>, Error> {
+    let dir = backup_dir(ledger);
+    let prefix = format!("backup_{}_", ledger_name(ledger)?);
+    let entries = match fs::read_dir(&dir) {
+        Ok(x) => x,
+        Err(x) if x.kind() == io::ErrorKind::NotFound => return Ok(vec!()),
+        Err(x) => return Err(Error::io("looking through the backups directory", &dir, x))
+    };
+    let mut snapshots: Vec<Snapshot> = vec!();
+    for entry in entries {
+        let entry = match entry {
+            Ok(x) => x,
+            Err(x) => return Err(Error::io("looking through the backups directory", &dir, x))
+        };
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let rest = match file_name.strip_prefix(&prefix) {
+            Some(x) => x,
+            None => continue
+        };
+        // Anything that doesn't look like "{timestamp}" or "{timestamp}.{sequence}" belongs to a
+        // different ledger whose name happens to start with this one's
+        let (timestamp, sequence) = match rest.split_once('.') {
+            Some((x, y)) => (x.parse::<u64>(), y.parse::<u64>()),
+            None => (rest.parse::<u64>(), Ok(0))
+        };
+        if let (Ok(timestamp), Ok(sequence)) = (timestamp, sequence) {
+            snapshots.push(Snapshot {
+                path: entry.path(),
+                timestamp,
+                sequence
+            });
+        }
+    }
+    snapshots.sort_by_key(|x| Reverse((x.timestamp, x.sequence)));
+    Ok(snapshots)
+}
+
+pub fn load_retention(ledger: &Path) -> Result<Retention, Error> {
+    let path = retention_path(ledger)?;
+    let contents = match fs::read_to_string(&path) {
+        Ok(x) => x,
+        Err(x) if x.kind() == io::ErrorKind::NotFound => return Ok(Retention::default()),
+        Err(x) => return Err(Error::io("reading the backup retention policy from", &path, x))
+    };
+    let field = format!("the retention policy in {}", path.display());
+    let mut retention = Retention::default();
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((x, y)) => (x.trim(), y.trim()),
+            None => return Err(Error::parse(&field, line, "Every line should look like \"keep_last = 10\""))
+        };
+        let value = match value.parse::<u64>() {
+            Ok(x) => x,
+            Err(x) => return Err(Error::parse(&field, line, format!("The value should be an unsigned integer. Here's the reported error, if it helps: {}", x)))
+        };
+        match key {
+            "keep_last" => retention.keep_last = value,
+            "keep_daily" => retention.keep_daily = value,
+            "keep_weekly" => retention.keep_weekly = value,
+            x => return Err(Error::validation(&field, format!("there's no setting called \"{}\"", x)))
+        }
+    }
+    Ok(retention)
+}
+
+pub fn save_retention(ledger: &Path, retention: &Retention) -> Result<(), Error> {
+    let path = retention_path(ledger)?;
+    match fs::create_dir_all(backup_dir(ledger)).and_then(|_| fs::write(&path, retention.to_string())) {
+        Ok(_) => Ok(()),
+        Err(x) => Err(Error::io("saving the retention policy to", &path, x))
+    }
+}
+
+// Decides which of snapshots (newest first) survive the retention policy
+fn to_keep(snapshots: &[Snapshot], retention: &Retention) -> Vec<bool> {
+    let mut keep = vec![false; snapshots.len()];
+    for x in keep.iter_mut().take(retention.keep_last as usize) {
+        *x = true;
+    }
+    keep_newest_per_bucket(snapshots, &mut keep, retention.keep_daily, |x| x.timestamp / SECONDS_PER_DAY);
+    // The unix epoch was a Thursday, so shifting by 3 days makes weeks start on Monday
+    keep_newest_per_bucket(snapshots, &mut keep, retention.keep_weekly, |x| (x.timestamp / SECONDS_PER_DAY + 3) / 7);
+    if let Some(x) = keep.first_mut() {
+        *x = true;
+    }
+    keep
+}
+
+fn keep_newest_per_bucket(snapshots: &[Snapshot], keep: &mut [bool], buckets: u64, bucket_of: impl Fn(&Snapshot) -> u64) {
+    let mut last_bucket: Option<u64> = None;
+    let mut kept = 0;
+    for (index, snapshot) in snapshots.iter().enumerate() {
+        if kept >= buckets {
+            break;
+        }
+        let bucket = bucket_of(snapshot);
+        if last_bucket != Some(bucket) {
+            // Snapshots are newest first, so the first one we see in a bucket is its newest
+            keep[index] = true;
+            kept += 1;
+            last_bucket = Some(bucket);
+        }
+    }
+}
+
+// Deletes the snapshots of ledger that fall outside of its retention policy and returns them
+pub fn prune(ledger: &Path) -> Result<Vec<PathBuf>, Error> {
+    let snapshots = list(ledger)?;
+    let keep = to_keep(&snapshots, &load_retention(ledger)?);
+    let mut removed: Vec<PathBuf> = vec!();
+    for (snapshot, keep) in snapshots.into_iter().zip(keep) {
+        if !keep {
+            if let Err(x) = fs::remove_file(&snapshot.path) {
+                return Err(Error::io("removing the old backup", &snapshot.path, x));
+            }
+            removed.push(snapshot.path);
+        }
+    }
+    Ok(removed)
+}
+
+// Formats a unix timestamp as "YYYY-MM-DD HH:MM:SS UTC"
+pub fn format_timestamp(timestamp: u64) -> String {
+    let date = Date::from_days_since_epoch((timestamp / SECONDS_PER_DAY) as i64);
+    let seconds = timestamp % SECONDS_PER_DAY;
+    format!("{} {:02}:{:02}:{:02} UTC", date.to_iso_string(), seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+// purchase_tracker restore [FILE] [SNAPSHOT]
+pub fn restore_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &[], &[])?;
+    let (ledger, snapshot) = match args.positional.as_slice() {
+        [x] => (Path::new(x), None),
+        [x, y] => (Path::new(x), Some(y)),
+        _ => return Err(Error::usage("purchase_tracker restore [FILE] [SNAPSHOT]"))
+    };
+    let snapshots = list(ledger)?;
+
+    let chosen = match snapshot {
+        None => {
+            if snapshots.is_empty() {
+                println!("There are no backups of {} yet.", ledger.display());
+                return Ok(());
+            }
+            println!("Backups of {} (newest first):", ledger.display());
+            for (index, snapshot) in snapshots.iter().enumerate() {
+                let orders = match storage::try_load(&snapshot.path) {
+                    Some(x) => format!("{} orders", x.orders.len()),
+                    None => "unreadable".to_string()
+                };
+                println!("{}. {} ({}) {}", index, format_timestamp(snapshot.timestamp), orders, snapshot.path.display());
+            }
+            println!("Run 'purchase_tracker restore {} [SNAPSHOT]' with one of the numbers above to restore that backup.", ledger.display());
+            return Ok(());
+        },
+        Some(x) => match x.parse::<usize>() {
+            Ok(y) if y < snapshots.len() => &snapshots[y],
+            _ => return Err(Error::validation("SNAPSHOT", format!("\"{}\" isn't one of the backups of {}. Run 'purchase_tracker restore {}' to see the list of backups.", x, ledger.display(), ledger.display())))
+        }
+    };
+
+    let restored = storage::load(&chosen.path)?;
+    let current = storage::try_load(ledger).map(|x| x.orders);
+    println!("Backup from {}: {} orders", format_timestamp(chosen.timestamp), restored.orders.len());
+    match &current {
+        Some(x) => {
+            println!("Currently in {}: {} orders", ledger.display(), x.len());
+            if restored.orders.len() >= x.len() {
+                println!("Restoring will bring back {} orders.", restored.orders.len() - x.len());
+            } else {
+                println!("Restoring will drop {} orders.", x.len() - restored.orders.len());
+            }
+        },
+        None => println!("{} is currently missing or unreadable.", ledger.display())
+    }
+
+    let stdin = io::stdin();
+    println!("Restore this backup? The current contents of {} will be backed up first. (Type \"Yes\" or \"No\")", ledger.display());
+    loop {
+        let temp = read_line(&stdin)?;
+        if temp.trim() == "Yes" {
+            break;
+        }
+        if temp.trim() == "No" {
+            println!("Left {} as it was.", ledger.display());
+            return Ok(());
+        }
+        println!("Please type \"Yes\" or \"No\"");
+    }
+
+    storage::save(ledger, &restored)?;
+    println!("Success! {} now holds the backup from {}.", ledger.display(), format_timestamp(chosen.timestamp));
+    Ok(())
+}
+
+// purchase_tracker retention [FILE] [--keep-last N] [--keep-daily N] [--keep-weekly N]
+pub fn retention_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["keep-last", "keep-daily", "keep-weekly"], &[])?;
+    let ledger = match args.positional.as_slice() {
+        [x] => Path::new(x),
+        _ => return Err(Error::usage("purchase_tracker retention [FILE] [--keep-last N] [--keep-daily N] [--keep-weekly N]"))
+    };
+    let mut retention = load_retention(ledger)?;
+    let mut changed = false;
+    for (flag, setting) in [("keep-last", &mut retention.keep_last), ("keep-daily", &mut retention.keep_daily), ("keep-weekly", &mut retention.keep_weekly)] {
+        if let Some(x) = args.value(flag) {
+            *setting = match x.parse::<u64>() {
+                Ok(y) => y,
+                Err(y) => return Err(Error::parse(&format!("--{}", flag), x, format!("It should be an unsigned integer. Here's the reported error, if it helps: {}", y)))
+            };
+            changed = true;
+        }
+    }
+
+    if changed {
+        save_retention(ledger, &retention)?;
+        let removed = prune(ledger)?;
+        println!("Saved the new retention policy and removed {} backups that fell outside of it.", removed.len());
+    }
+    println!("Retention policy for backups of {}:\n{}", ledger.display(), retention.to_string());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    // 2024-01-01, a Monday
+    const MONDAY: i64 = 19723;
+
+    // Snapshots taken at (days after MONDAY, hour, minute, sequence), given newest first like list
+    fn snapshots(times: &[(i64, u64, u64, u64)]) -> Vec<Snapshot> {
+        times.iter().map(|&(day, hour, minute, sequence)| Snapshot {
+            path: PathBuf::new(),
+            timestamp: (MONDAY + day) as u64 * SECONDS_PER_DAY + hour * 3600 + minute * 60,
+            sequence
+        }).collect()
+    }
+
+    fn kept(snapshots: &[Snapshot], keep_last: u64, keep_daily: u64, keep_weekly: u64) -> Vec<usize> {
+        let keep = to_keep(snapshots, &Retention { keep_last, keep_daily, keep_weekly });
+        keep.iter().enumerate().filter(|(_, x)| **x).map(|(x, _)| x).collect()
+    }
+
+    #[test]
+    fn daily_keeps_the_newest_of_each_day() {
+        let taken = snapshots(&[(2, 18, 0, 0), (2, 9, 0, 1), (2, 9, 0, 0), (1, 12, 0, 0), (1, 8, 0, 0), (0, 23, 59, 0), (-1, 10, 0, 0)]);
+        assert_eq!(kept(&taken, 0, 3, 0), vec!(0, 3, 5));
+        assert_eq!(kept(&taken, 0, 10, 0), vec!(0, 3, 5, 6));
+        // Two in the same second are told apart by their sequence, the later one is newer
+        let same_second = snapshots(&[(2, 9, 0, 1), (2, 9, 0, 0), (1, 9, 0, 0)]);
+        assert_eq!(kept(&same_second, 0, 2, 0), vec!(0, 2));
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // Tuesday, the very start of Monday, the very end of the Sunday before it, that Sunday's
+        // morning, the Monday of that week and the Sunday before that
+        let mut snapshots = snapshots(&[(1, 10, 0, 0), (0, 0, 0, 0), (-1, 23, 59, 0), (-1, 10, 0, 0), (-7, 8, 0, 0), (-8, 8, 0, 0)]);
+        snapshots[2].timestamp += 59;
+        assert_eq!(kept(&snapshots, 0, 0, 2), vec!(0, 2));
+        assert_eq!(kept(&snapshots, 0, 0, 3), vec!(0, 2, 5));
+        assert_eq!(kept(&snapshots, 0, 2, 2), vec!(0, 1, 2));
+    }
+
+    #[test]
+    fn the_newest_snapshot_is_always_kept() {
+        let snapshots = snapshots(&[(3, 12, 0, 0), (3, 11, 0, 0), (2, 12, 0, 0), (-10, 12, 0, 0)]);
+        assert_eq!(kept(&snapshots, 0, 0, 0), vec!(0));
+        assert_eq!(kept(&snapshots, 2, 0, 0), vec!(0, 1));
+        assert_eq!(kept(&snapshots, 10, 0, 0), vec!(0, 1, 2, 3));
+        // The rules add up rather than each having to agree
+        assert_eq!(kept(&snapshots, 1, 1, 2), vec!(0, 3));
+        assert_eq!(kept(&snapshots, 2, 2, 0), vec!(0, 1, 2));
+        assert!(kept(&[], 0, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn prune_removes_what_the_policy_doesnt_keep() {
+        let dir = env::temp_dir().join(format!("purchase_tracker_backup_{}", process::id()));
+        let ledger = dir.join("orders.ptl");
+        let backups = backup_dir(&ledger);
+        fs::create_dir_all(&backups).unwrap();
+        let day = MONDAY as u64 * SECONDS_PER_DAY;
+        let names = [format!("{}", day + 3600), format!("{}.1", day), format!("{}", day), format!("{}", day - 3600), format!("{}", day - 2 * SECONDS_PER_DAY)];
+        for x in &names {
+            fs::write(backups.join(format!("backup_orders.ptl_{}", x)), "").unwrap();
+        }
+        // Backups of other ledgers aren't touched
+        fs::write(backups.join(format!("backup_orders.ptl.old_{}", day)), "").unwrap();
+        fs::write(backups.join("backup_orders.ptl_notes"), "").unwrap();
+
+        save_retention(&ledger, &Retention { keep_last: 1, keep_daily: 2, keep_weekly: 0 }).unwrap();
+        let removed = prune(&ledger);
+        let left = list(&ledger).unwrap().iter().map(|x| x.path.clone()).collect::<Vec<_>>();
+        let others = (fs::exists(backups.join(format!("backup_orders.ptl.old_{}", day))).unwrap(), fs::exists(backups.join("backup_orders.ptl_notes")).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let path = |x: usize| backups.join(format!("backup_orders.ptl_{}", names[x]));
+        assert_eq!(removed.unwrap(), vec!(path(1), path(2), path(4)));
+        assert_eq!(left, vec!(path(0), path(3)));
+        assert_eq!(others, (true, true));
+    }
+}
@@ -0,0 +1,241 @@
+use std::path::Path;
+
+use crate::args::Args;
+use crate::category::{tags_to_string, Filter};
+use crate::error::Error;
+use crate::money::{Currency, Money};
+use crate::payment;
+use crate::vendor::{vendor_name, Vendor};
+use crate::{parse_order_index, rates, storage, AddOn, Ledger, Order, OrderLine, Product};
+
+// One line summing up an order, for listings
+pub fn summary_line(index: usize, order: &Order, vendors: &[Vendor]) -> String {
+    let products = match order.products.iter().fold(0u64, |total, x| total.saturating_add(x.quantity)) {
+        1 => "1 product".to_string(),
+        x => format!("{} products", x)
+    };
+    let vendor = match order.vendor.and_then(|x| vendors.get(x)) {
+        Some(x) => format!("  {}", x.name),
+        None => "".to_string()
+    };
+    let refunded = match order.refunds.is_empty() {
+        true => "".to_string(),
+        false => format!("  ({} after refunds)", order.net_total().to_string_in(order.currency))
+    };
+    format!("{}. {}{}  {}{}  {}  {}", index, order.date_placed.to_string(), vendor, order.total.to_string_in(order.currency), refunded, products, order.status().name())
+}
+
+// What orders cost after refunds added up in one currency, converting each one at the rate from
+// the day it was placed
+fn spent_in(ledger: &Ledger, orders: &[&Order], currency: Currency) -> Result<Money, Error> {
+    let mut totals: Vec<Money> = vec!();
+    for order in orders {
+        totals.push(rates::convert(ledger, order.net_total(), order.currency, currency, order.date_placed)?);
+    }
+    Money::sum(totals, "the totals of the orders")
+}
+
+// What orders cost after refunds added up separately for each currency, for when they can't be converted
+// into one
+fn spent_per_currency(orders: &[&Order]) -> Result<Vec<(Currency, Money)>, Error> {
+    let mut currencies: Vec<Currency> = orders.iter().map(|x| x.currency).collect();
+    currencies.sort();
+    currencies.dedup();
+    let mut spent: Vec<(Currency, Money)> = vec!();
+    for currency in currencies {
+        let totals = orders.iter().filter(|x| x.currency == currency).map(|x| x.net_total());
+        spent.push((currency, Money::sum(totals, &format!("the totals of the orders in {}", currency.code()))?));
+    }
+    Ok(spent)
+}
+
+// Prints how much orders cost altogether, e.g. "$1204.50 spent on 3 orders."
+fn print_spent(ledger: &Ledger, orders: &[&Order], currency: Currency, indent: &str) -> Result<(), Error> {
+    match spent_in(ledger, orders, currency) {
+        Ok(x) => println!("{}{} spent on {} orders.", indent, x.to_string_in(currency), orders.len()),
+        Err(x) => {
+            let spent: Vec<String> = spent_per_currency(orders)?.into_iter().map(|(y, z)| z.to_string_in(y)).collect();
+            println!("{}{} spent on {} orders.", indent, spent.join(" + "), orders.len());
+            println!("{}(Couldn't add that up in {}. {})", indent, currency.code(), x.to_string());
+        }
+    }
+    Ok(())
+}
+
+// The orders at indices split up by vendor, as (vendor, indices of its orders). Vendors come in
+// the order they were added, and orders without a vendor come last.
+fn by_vendor(ledger: &Ledger, indices: &[usize]) -> Vec<(Option<usize>, Vec<usize>)> {
+    let mut groups: Vec<(Option<usize>, Vec<usize>)> = vec!();
+    for vendor in (0..ledger.vendors.len()).map(Some).chain([None]) {
+        let orders: Vec<usize> = indices.iter().copied()
+            .filter(|x| ledger.orders[*x].vendor.filter(|y| *y < ledger.vendors.len()) == vendor)
+            .collect();
+        if !orders.is_empty() {
+            groups.push((vendor, orders));
+        }
+    }
+    groups
+}
+
+// purchase_tracker list [FILE] [--in CURRENCY] [--by-vendor] [--category CATEGORY] [--tag TAG]
+pub fn list_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["in", "category", "tag"], &["by-vendor"])?;
+    let path = match args.positional.as_slice() {
+        [x] => Path::new(x),
+        _ => return Err(Error::usage("purchase_tracker list [FILE] [--in CURRENCY] [--by-vendor] [--category CATEGORY] [--tag TAG]"))
+    };
+    let filter = Filter::from_args(&args);
+    let ledger = storage::load(path)?;
+    let currency = match args.value("in") {
+        Some(x) => Currency::parse(x, "--in")?,
+        None => ledger.home_currency
+    };
+    if ledger.orders.is_empty() {
+        println!("{} doesn't have any orders yet.", path.display());
+        return Ok(());
+    }
+    let shown: Vec<usize> = (0..ledger.orders.len()).filter(|x| filter.matches(&ledger.orders[*x])).collect();
+    if shown.is_empty() {
+        println!("{} doesn't have any orders{}.", path.display(), filter.describe());
+        return Ok(());
+    }
+    if args.switch("by-vendor") {
+        for (vendor, indices) in by_vendor(&ledger, &shown) {
+            println!("{}:", group_name(&ledger, vendor));
+            for index in &indices {
+                println!("  {}", summary_line(*index, &ledger.orders[*index], &ledger.vendors));
+            }
+            let orders: Vec<&Order> = indices.iter().map(|x| &ledger.orders[*x]).collect();
+            print_spent(&ledger, &orders, currency, "  ")?;
+        }
+    } else {
+        for index in &shown {
+            println!("{}", summary_line(*index, &ledger.orders[*index], &ledger.vendors));
+        }
+    }
+    let orders: Vec<&Order> = shown.iter().map(|x| &ledger.orders[*x]).collect();
+    print_spent(&ledger, &orders, currency, "")
+}
+
+fn group_name(ledger: &Ledger, vendor: Option<usize>) -> &str {
+    match vendor {
+        Some(x) => &ledger.vendors[x].name,
+        None => "(no vendor)"
+    }
+}
+
+// purchase_tracker show [FILE] [ORDER] [--category CATEGORY] [--tag TAG]
+pub fn show_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["category", "tag"], &[])?;
+    let (path, index) = match args.positional.as_slice() {
+        [x, y] => (Path::new(x), y),
+        _ => return Err(Error::usage("purchase_tracker show [FILE] [ORDER] [--category CATEGORY] [--tag TAG]"))
+    };
+    let filter = Filter::from_args(&args);
+    let ledger = storage::load(path)?;
+    let index = parse_order_index(index, &ledger.orders, path)?;
+    let order = &ledger.orders[index];
+    println!("{}.\n{}", index, order.to_string());
+    println!("Vendor: {}", vendor_name(order, &ledger.vendors));
+    println!("Paid with: {}", payment::payments_to_string(order, &ledger.payment_methods));
+    // With a filter, also point out which of its products are like that
+    if !filter.is_empty() {
+        let lines: Vec<(usize, &OrderLine)> = order.products.iter().enumerate().filter(|(_, x)| filter.matches_product(&x.product, ("", &order.tags))).collect();
+        match lines.is_empty() {
+            true => println!("It has no products{}.", filter.describe()),
+            false => println!("Products{}:", filter.describe())
+        }
+        for (line, x) in lines {
+            println!("  products[{}]: {}", line, x.product.name.trim());
+        }
+    }
+    Ok(())
+}
+
+// Everywhere text (already lowercased) shows up in an order, as (where, the text that matched)
+fn search_order(order: &Order, vendors: &[Vendor], text: &str) -> Vec<(String, String)> {
+    let mut matches: Vec<(String, String)> = vec!();
+    if let Some(x) = order.vendor.and_then(|x| vendors.get(x)) {
+        check_field(&x.name, text, "vendor", &mut matches);
+    }
+    for (index, line) in order.products.iter().enumerate() {
+        search_product(&line.product, text, &format!("products[{}]", index), &mut matches);
+        check_field(&line.discount_reason, text, &format!("products[{}].discount_reason", index), &mut matches);
+        check_field(&line.notes, text, &format!("products[{}].notes", index), &mut matches);
+    }
+    for (index, refund) in order.refunds.iter().enumerate() {
+        check_field(&refund.reason, text, &format!("refunds[{}].reason", index), &mut matches);
+    }
+    check_field(&tags_to_string(&order.tags), text, "tags", &mut matches);
+    check_field(&order.notes, text, "notes", &mut matches);
+    matches
+}
+
+fn search_product(product: &Product, text: &str, location: &str, matches: &mut Vec<(String, String)>) {
+    check_field(&product.name, text, &format!("{}.name", location), matches);
+    check_field(&product.desc, text, &format!("{}.desc", location), matches);
+    check_field(&product.category, text, &format!("{}.category", location), matches);
+    check_field(&tags_to_string(&product.tags), text, &format!("{}.tags", location), matches);
+    if let Some(items) = &product.items {
+        for (index, item) in items.iter().enumerate() {
+            search_product(item, text, &format!("{}.items[{}]", location, index), matches);
+        }
+    }
+    if let Some(add_ons) = &product.add_ons {
+        for (index, add_on) in add_ons.iter().enumerate() {
+            search_add_on(add_on, text, &format!("{}.add_ons[{}]", location, index), matches);
+        }
+    }
+}
+
+fn search_add_on(add_on: &AddOn, text: &str, location: &str, matches: &mut Vec<(String, String)>) {
+    check_field(&add_on.name, text, &format!("{}.name", location), matches);
+    check_field(&add_on.category, text, &format!("{}.category", location), matches);
+    check_field(&tags_to_string(&add_on.tags), text, &format!("{}.tags", location), matches);
+    if let Some(x) = &add_on.assoc_product {
+        search_product(x, text, &format!("{}.assoc_product", location), matches);
+    }
+}
+
+fn check_field(field: &str, text: &str, location: &str, matches: &mut Vec<(String, String)>) {
+    if field.to_lowercase().contains(text) {
+        matches.push((location.to_string(), field.trim().to_string()));
+    }
+}
+
+// purchase_tracker search [FILE] [TEXT] [--by-vendor] [--category CATEGORY] [--tag TAG]
+pub fn search_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["category", "tag"], &["by-vendor"])?;
+    let (path, text) = match args.positional.as_slice() {
+        [x, y] => (Path::new(x), y.to_lowercase()),
+        _ => return Err(Error::usage("purchase_tracker search [FILE] [TEXT] [--by-vendor] [--category CATEGORY] [--tag TAG]"))
+    };
+    let filter = Filter::from_args(&args);
+    let ledger = storage::load(path)?;
+    let found: Vec<(usize, Vec<(String, String)>)> = ledger.orders.iter().enumerate()
+        .filter(|(_, order)| filter.matches(order))
+        .map(|(index, order)| (index, search_order(order, &ledger.vendors, &text)))
+        .filter(|(_, matches)| !matches.is_empty())
+        .collect();
+    let print_match = |index: usize, matches: &[(String, String)], indent: &str| {
+        println!("{}{}", indent, summary_line(index, &ledger.orders[index], &ledger.vendors));
+        for (location, field) in matches {
+            println!("{}    {}: {}", indent, location, field);
+        }
+    };
+    if args.switch("by-vendor") {
+        let indices: Vec<usize> = found.iter().map(|(x, _)| *x).collect();
+        for (vendor, group) in by_vendor(&ledger, &indices) {
+            println!("{} ({} orders matched):", group_name(&ledger, vendor), group.len());
+            for (index, matches) in found.iter().filter(|(x, _)| group.contains(x)) {
+                print_match(*index, matches, "  ");
+            }
+        }
+    } else {
+        for (index, matches) in &found {
+            print_match(*index, matches, "");
+        }
+    }
+    println!("{} of {} orders matched.", found.len(), ledger.orders.len());
+    Ok(())
+}
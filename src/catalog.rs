@@ -0,0 +1,257 @@
+use bincode::{Decode, Encode};
+use std::io;
+use std::path::Path;
+
+use crate::args::Args;
+use crate::category::Filter;
+use crate::edit::edit_product;
+use crate::error::Error;
+use crate::money::Currency;
+use crate::vendor::fuzzy_matches;
+use crate::{ask, ask_yes_no, product_cli, read_answer, storage, Product, ABORT};
+
+/*
+ * The catalog keeps products that get bought more than once, sub-products and add-ons included,
+ * so the order creator (and product files, see spec.rs) can copy one into an order instead of
+ * having all of it typed in again. Orders get their own copy: changing a catalog entry later
+ * doesn't touch the orders that used it.
+ *
+ * Entries are numbered like orders, but never removed. Retiring one hides it from the order
+ * creator and from 'purchase_tracker catalog FILE' (unless --all is given).
+ */
+#[derive(Encode, Decode, Debug)]
+pub struct CatalogEntry {
+    product: Product,
+    currency: Currency, // What the prices of product are in
+    retired: bool
+}
+
+impl CatalogEntry {
+    // For format upgrades, which build entries out of their frozen copies
+    pub fn upgraded(product: Product, currency: Currency, retired: bool) -> CatalogEntry {
+        CatalogEntry {
+            product,
+            currency,
+            retired
+        }
+    }
+
+    pub fn product(&self) -> &Product {
+        &self.product
+    }
+
+    pub fn product_mut(&mut self) -> &mut Product {
+        &mut self.product
+    }
+
+    fn name(&self) -> &str {
+        self.product.name.trim()
+    }
+
+    // One line summing up the entry, for listings
+    fn summary_line(&self, index: usize) -> String {
+        let mut temp = format!("{}. {}  {}", index, self.name(), self.product.sticker_price.to_string_in(self.currency));
+        if let Some(x) = &self.product.items {
+            temp.push_str(&format!("  {} sub-products", x.len()));
+        }
+        if let Some(x) = &self.product.add_ons {
+            temp.push_str(&format!("  {} add-ons", x.len()));
+        }
+        if self.retired {
+            temp.push_str("  (retired)");
+        }
+        temp
+    }
+}
+
+// The entry called name, ignoring case. Retired entries count too, so names stay unique.
+fn find(catalog: &[CatalogEntry], name: &str) -> Option<usize> {
+    catalog.iter().position(|x| x.name().eq_ignore_ascii_case(name.trim()))
+}
+
+// A copy of the product called name and the currency its prices are in, for product files.
+// field says where name came from, for the error.
+pub fn lookup(catalog: &[CatalogEntry], name: &str, field: &str) -> Result<(Product, Currency), Error> {
+    match find(catalog, name) {
+        Some(x) if catalog[x].retired => Err(Error::validation(field, format!("\"{}\" is retired from the catalog. Run 'purchase_tracker catalog unretire' to use it again", catalog[x].name()))),
+        Some(x) => Ok((catalog[x].product.clone(), catalog[x].currency)),
+        None => Err(Error::validation(field, format!("there's nothing called \"{}\" in the catalog", name.trim())))
+    }
+}
+
+// Offers to copy a product from the catalog into an order in currency, letting anything that
+// was different this time be changed. None if the catalog is empty or the product is a new one.
+pub fn pick_product_cli(stdin: &io::Stdin, catalog: &[CatalogEntry], currency: Currency) -> Result<Option<Product>, Error> {
+    let active: Vec<(usize, &str)> = catalog.iter().enumerate().filter(|(_, x)| !x.retired).map(|(index, x)| (index, x.name())).collect();
+    if active.is_empty() {
+        return Ok(None);
+    }
+    loop {
+        println!("Is this something from your catalog? (Type part of its name, or leave it blank to type in a new product)");
+        let query = read_answer(stdin)?.trim().to_string();
+        if query.is_empty() {
+            return Ok(None);
+        }
+        let matches = fuzzy_matches(active.iter().copied(), &query);
+        if matches.is_empty() {
+            println!("Nothing in the catalog matches \"{}\".", query);
+            continue;
+        }
+        for (number, index) in matches.iter().enumerate() {
+            println!("  {}. {}  {}", number, catalog[*index].name(), catalog[*index].product.sticker_price.to_string_in(catalog[*index].currency));
+        }
+        println!("Type one of the numbers above, or leave it blank to search again.");
+        let picked = ask(stdin, |x| match x.trim() {
+            "" => Ok(None),
+            y => match y.parse::<usize>() {
+                Ok(z) if z < matches.len() => Ok(Some(matches[z])),
+                _ => Err(Error::validation("the catalog entry", format!("pick a number from 0 to {}", matches.len() - 1)))
+            }
+        })?;
+        let entry = match picked {
+            Some(x) => &catalog[x],
+            None => continue
+        };
+        let mut product = entry.product.clone();
+        if entry.currency != currency {
+            println!("The catalog has the prices of {} in {}, but this order is in {}. They were copied over as the same numbers, so change them below.", entry.name(), entry.currency.code(), currency.code());
+        }
+        println!("Was anything about {} different this time, like its price? (Type \"Yes\" to change it for this order only, or \"No\")", entry.name());
+        if ask_yes_no(stdin)? {
+            edit_product(stdin, currency, &mut product, entry.name())?;
+        }
+        return Ok(Some(product));
+    }
+}
+
+// Offers to save a product that was just typed in for an order in currency, unless the catalog
+// already has something by that name
+pub fn offer_to_save_cli(stdin: &io::Stdin, catalog: &mut Vec<CatalogEntry>, product: &Product, currency: Currency) -> Result<(), Error> {
+    if product.name.trim().is_empty() || find(catalog, &product.name).is_some() {
+        return Ok(());
+    }
+    println!("Save {} to the catalog so it doesn't have to be typed in again next time? (Type \"Yes\" or \"No\")", product.name.trim());
+    if ask_yes_no(stdin)? {
+        catalog.push(CatalogEntry {
+            product: product.clone(),
+            currency,
+            retired: false
+        });
+    }
+    Ok(())
+}
+
+// Turns the ENTRY argument of a command (its number or its name) into an index into catalog
+fn parse_entry(input: &str, catalog: &[CatalogEntry], path: &Path) -> Result<usize, Error> {
+    if let Ok(x) = input.trim().parse::<usize>() {
+        return match x < catalog.len() {
+            true => Ok(x),
+            false => Err(Error::validation("ENTRY", format!("the catalog of {} only has {} entries, numbered starting from 0, so there's no entry {}", path.display(), catalog.len(), x)))
+        };
+    }
+    match find(catalog, input) {
+        Some(x) => Ok(x),
+        None => Err(Error::validation("ENTRY", format!("the catalog of {} doesn't have anything called \"{}\". Run 'purchase_tracker catalog {} --all' to see what it has", path.display(), input.trim(), path.display())))
+    }
+}
+
+// Renaming an entry to the name of another one would make find ambiguous
+fn check_unique(catalog: &[CatalogEntry], index: usize) -> Result<(), Error> {
+    match catalog.iter().enumerate().find(|(other, x)| *other != index && x.name().eq_ignore_ascii_case(catalog[index].name())) {
+        Some((other, x)) => Err(Error::validation("the name", format!("the catalog already has an entry called {} (number {})", x.name(), other))),
+        None => Ok(())
+    }
+}
+
+// purchase_tracker catalog [FILE] [--all] [--category CATEGORY] [--tag TAG] | catalog show [FILE] [ENTRY] | catalog add [FILE] [--currency CODE]
+// | catalog edit [FILE] [ENTRY] | catalog retire [FILE] [ENTRY] | catalog unretire [FILE] [ENTRY]
+pub fn catalog_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["currency", "category", "tag"], &["all"])?;
+    let usage = "purchase_tracker catalog [FILE] [--all] [--category CATEGORY] [--tag TAG] | purchase_tracker catalog show [FILE] [ENTRY] | purchase_tracker catalog add [FILE] [--currency CODE] | purchase_tracker catalog edit [FILE] [ENTRY] | purchase_tracker catalog retire [FILE] [ENTRY] | purchase_tracker catalog unretire [FILE] [ENTRY]";
+    let stdin = io::stdin();
+    match args.positional.iter().map(|x| x.as_str()).collect::<Vec<&str>>().as_slice() {
+        ["show", path, entry] => {
+            let path = Path::new(path);
+            let ledger = storage::load(path)?;
+            let index = parse_entry(entry, &ledger.catalog, path)?;
+            let entry = &ledger.catalog[index];
+            println!("{}.\n{}Currency: {}", index, entry.product.to_string_in(entry.currency), entry.currency.code());
+            if entry.retired {
+                println!("Retired");
+            }
+            Ok(())
+        },
+        ["add", path] => {
+            let path = Path::new(path);
+            let mut ledger = storage::load(path)?;
+            let currency = match args.value("currency") {
+                Some(x) => Currency::parse(x, "--currency")?,
+                None => ledger.home_currency
+            };
+            println!("(Type \"{}\" in answer to any question to stop without saving anything.)", ABORT);
+            let product = product_cli(currency)?;
+            ledger.catalog.push(CatalogEntry {
+                product,
+                currency,
+                retired: false
+            });
+            check_unique(&ledger.catalog, ledger.catalog.len() - 1)?;
+            storage::save(path, &ledger)?;
+            println!("Added {}", ledger.catalog[ledger.catalog.len() - 1].summary_line(ledger.catalog.len() - 1));
+            Ok(())
+        },
+        ["edit", path, entry] => {
+            let path = Path::new(path);
+            let mut ledger = storage::load(path)?;
+            let index = parse_entry(entry, &ledger.catalog, path)?;
+            let before = ledger.catalog[index].summary_line(index);
+            println!("(Type \"{}\" in answer to any question to throw away your changes without saving anything.)", ABORT);
+            let entry = &mut ledger.catalog[index];
+            let location = format!("catalog entry {}", index);
+            edit_product(&stdin, entry.currency, &mut entry.product, &location)?;
+            check_unique(&ledger.catalog, index)?;
+            storage::save(path, &ledger)?;
+            println!("Before: {}", before);
+            println!("After:  {}", ledger.catalog[index].summary_line(index));
+            Ok(())
+        },
+        [action @ ("retire" | "unretire"), path, entry] => {
+            let path = Path::new(path);
+            let mut ledger = storage::load(path)?;
+            let index = parse_entry(entry, &ledger.catalog, path)?;
+            let retired = *action == "retire";
+            if ledger.catalog[index].retired == retired {
+                println!("{} is already {}, nothing was changed.", ledger.catalog[index].name(), match retired {
+                    true => "retired",
+                    false => "in use"
+                });
+                return Ok(());
+            }
+            ledger.catalog[index].retired = retired;
+            storage::save(path, &ledger)?;
+            println!("{}", ledger.catalog[index].summary_line(index));
+            Ok(())
+        },
+        [path] => {
+            let ledger = storage::load(Path::new(path))?;
+            let filter = Filter::from_args(&args);
+            let matching: Vec<(usize, &CatalogEntry)> = ledger.catalog.iter().enumerate().filter(|(_, x)| filter.matches_product(&x.product, ("", &[]))).collect();
+            let shown: Vec<&(usize, &CatalogEntry)> = matching.iter().filter(|(_, x)| args.switch("all") || !x.retired).collect();
+            if shown.is_empty() {
+                match filter.is_empty() {
+                    true => println!("The catalog of {} is empty.", path),
+                    false => println!("The catalog of {} doesn't have anything{}.", path, filter.describe())
+                }
+            }
+            for (index, entry) in shown {
+                println!("{}", entry.summary_line(*index));
+            }
+            let retired = matching.iter().filter(|(_, x)| x.retired).count();
+            if retired > 0 && !args.switch("all") {
+                println!("({} retired entries not shown, pass --all to see them)", retired);
+            }
+            Ok(())
+        },
+        _ => Err(Error::usage(usage))
+    }
+}
@@ -0,0 +1,410 @@
+use std::path::Path;
+
+use crate::args::Args;
+use crate::error::Error;
+use crate::{storage, AddOn, Ledger, Order, Product};
+
+/*
+ * Categories and tags, for slicing up spending. Every product and add-on can have a category,
+ * written from the most general part to the most specific like "Hobby > Fencing > Blades", and any
+ * number of free-form tags. Orders can have tags too.
+ *
+ * Both are inherited: a sub-product, add-on or associated product without a category of its own is
+ * in the category of whatever it's part of, and one without tags of its own has the tags of
+ * whatever it's part of. The top level products of an order get the order's tags. Giving one a
+ * category or tags of its own overrides what it would have inherited, for it and everything in it.
+ */
+
+// "hobby>fencing >  Blades" -> "hobby > fencing > Blades", "" stays "" (no category)
+pub fn parse_category(input: &str) -> String {
+    input.split('>').map(|x| x.trim()).filter(|x| !x.is_empty()).collect::<Vec<&str>>().join(" > ")
+}
+
+// "sale, gift ,, Sale" -> ["sale", "gift"]. Tags are compared ignoring case, the first spelling wins.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = vec!();
+    for tag in input.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+        if !tags.iter().any(|x| x.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+pub fn tags_to_string(tags: &[String]) -> String {
+    tags.join(", ")
+}
+
+// Whether category is wanted or somewhere under it, ignoring case
+fn in_category(category: &str, wanted: &str) -> bool {
+    let category = category.to_lowercase();
+    let wanted = wanted.to_lowercase();
+    category == wanted || category.starts_with(&format!("{} > ", wanted))
+}
+
+// What something with its own category and tags ends up with, given what it's part of
+pub fn inherit<'a>(category: &'a str, tags: &'a [String], parent: (&'a str, &'a [String])) -> (&'a str, &'a [String]) {
+    (
+        match category.is_empty() {
+            true => parent.0,
+            false => category
+        },
+        match tags.is_empty() {
+            true => parent.1,
+            false => tags
+        }
+    )
+}
+
+// The category and tags every product and add-on in product ends up with, product first
+fn collect_product<'a>(product: &'a Product, parent: (&'a str, &'a [String]), found: &mut Vec<(&'a str, &'a [String])>) {
+    let mine = inherit(&product.category, &product.tags, parent);
+    found.push(mine);
+    for item in product.items.iter().flatten() {
+        collect_product(item, mine, found);
+    }
+    for add_on in product.add_ons.iter().flatten() {
+        collect_add_on(add_on, mine, found);
+    }
+}
+
+fn collect_add_on<'a>(add_on: &'a AddOn, parent: (&'a str, &'a [String]), found: &mut Vec<(&'a str, &'a [String])>) {
+    let mine = inherit(&add_on.category, &add_on.tags, parent);
+    found.push(mine);
+    if let Some(x) = &add_on.assoc_product {
+        collect_product(x, mine, found);
+    }
+}
+
+// Picks out orders (or products) with --category and --tag, for every command that lists or adds
+// up orders, and the catalog
+pub struct Filter {
+    category: Option<String>,
+    tag: Option<String>
+}
+
+impl Filter {
+    // args has to have been parsed with "category" and "tag" as flags that take a value
+    pub fn from_args(args: &Args) -> Filter {
+        Filter {
+            category: args.value("category").map(parse_category).filter(|x| !x.is_empty()),
+            tag: args.value("tag").map(|x| x.trim().to_string()).filter(|x| !x.is_empty())
+        }
+    }
+
+    // An order matches when anything in it is in the category (or under it), and when it or
+    // anything in it has the tag
+    pub fn matches(&self, order: &Order) -> bool {
+        let mut found: Vec<(&str, &[String])> = vec!();
+        for line in &order.products {
+            collect_product(&line.product, ("", &order.tags), &mut found);
+        }
+        self.matches_found(&found, &order.tags)
+    }
+
+    // The same for a product on its own (a catalog entry, or one line of an order), where parent is
+    // what it inherits from, e.g. ("", &order.tags)
+    pub fn matches_product(&self, product: &Product, parent: (&str, &[String])) -> bool {
+        let mut found: Vec<(&str, &[String])> = vec!();
+        collect_product(product, parent, &mut found);
+        self.matches_found(&found, parent.1)
+    }
+
+    fn matches_found(&self, found: &[(&str, &[String])], tags: &[String]) -> bool {
+        let category_ok = self.category.as_ref().is_none_or(|x| found.iter().any(|(y, _)| in_category(y, x)));
+        let tag_ok = self.tag.as_ref().is_none_or(|x| tags.iter().chain(found.iter().flat_map(|(_, y)| y.iter())).any(|y| y.eq_ignore_ascii_case(x)));
+        category_ok && tag_ok
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.category.is_none() && self.tag.is_none()
+    }
+
+    // Like " in Hobby > Fencing tagged gift", "" when nothing is being filtered
+    pub fn describe(&self) -> String {
+        let mut temp = "".to_string();
+        if let Some(x) = &self.category {
+            temp.push_str(&format!(" in {}", x));
+        }
+        if let Some(x) = &self.tag {
+            temp.push_str(&format!(" tagged {}", x));
+        }
+        temp
+    }
+}
+
+// The category and tags of top level product number line of order, for reports that split orders
+// up by product
+pub fn line_category(order: &Order, line: usize) -> (&str, &[String]) {
+    let product = &order.products[line].product;
+    inherit(&product.category, &product.tags, ("", &order.tags))
+}
+
+// Calls change on the category of product and of everything in it
+fn each_category(product: &mut Product, change: &mut impl FnMut(&mut String)) {
+    change(&mut product.category);
+    for item in product.items.iter_mut().flatten() {
+        each_category(item, change);
+    }
+    for add_on in product.add_ons.iter_mut().flatten() {
+        change(&mut add_on.category);
+        if let Some(x) = &mut add_on.assoc_product {
+            each_category(x, change);
+        }
+    }
+}
+
+// Renames old (and everything under it) to new throughout the orders and the catalog. Returns how
+// many products and add-ons moved, and whether new already had anything in it, which makes this
+// a merge.
+fn rename(ledger: &mut Ledger, old: &str, new: &str) -> (usize, bool) {
+    let mut renamed = 0;
+    let mut merged = false;
+    let depth = old.split(" > ").count();
+    let mut change = |category: &mut String| {
+        if in_category(category, new) && !in_category(category, old) {
+            merged = true;
+        }
+        if !category.is_empty() && in_category(category, old) {
+            let rest: Vec<&str> = category.split(" > ").skip(depth).collect();
+            *category = match rest.is_empty() {
+                true => new.to_string(),
+                false => format!("{} > {}", new, rest.join(" > "))
+            };
+            renamed += 1;
+        }
+    };
+    for order in &mut ledger.orders {
+        for line in &mut order.products {
+            each_category(&mut line.product, &mut change);
+        }
+    }
+    for entry in &mut ledger.catalog {
+        each_category(entry.product_mut(), &mut change);
+    }
+    (renamed, merged)
+}
+
+// purchase_tracker categories [FILE] | categories rename [FILE] [OLD] [NEW]
+pub fn categories_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &[], &[])?;
+    let usage = "purchase_tracker categories [FILE] | purchase_tracker categories rename [FILE] [OLD] [NEW]";
+    match args.positional.iter().map(|x| x.as_str()).collect::<Vec<&str>>().as_slice() {
+        ["rename", path, old, new] => {
+            let path = Path::new(path);
+            let mut ledger = storage::load(path)?;
+            let (old, new) = (parse_category(old), parse_category(new));
+            if old.is_empty() || new.is_empty() {
+                return Err(Error::validation("the categories", "both the old and the new category need a name"));
+            }
+            let (renamed, merged) = rename(&mut ledger, &old, &new);
+            if renamed == 0 {
+                return Err(Error::validation("OLD", format!("nothing in {} is in {}. Run 'purchase_tracker categories {}' to see the categories it has", path.display(), old, path.display())));
+            }
+            storage::save(path, &ledger)?;
+            match merged {
+                true => println!("Merged {} into {}, which moved {} products and add-ons.", old, new, renamed),
+                false => println!("Renamed {} to {}, which moved {} products and add-ons.", old, new, renamed)
+            }
+            Ok(())
+        },
+        [path] => {
+            let ledger = storage::load(Path::new(path))?;
+            // Counting what was inherited too, so a bundle in "Hobby" with three sub-products
+            // counts four times
+            let mut found: Vec<(&str, &[String])> = vec!();
+            for order in &ledger.orders {
+                for line in &order.products {
+                    collect_product(&line.product, ("", &[]), &mut found);
+                }
+            }
+            for entry in &ledger.catalog {
+                collect_product(entry.product(), ("", &[]), &mut found);
+            }
+            let mut categories: Vec<&str> = found.into_iter().map(|(x, _)| x).filter(|x| !x.is_empty()).collect();
+            if categories.is_empty() {
+                println!("Nothing in {} has a category yet.", path);
+            }
+            categories.sort_by_key(|x| x.to_lowercase());
+            let mut shown: Vec<(&str, usize)> = vec!();
+            for category in categories {
+                match shown.last_mut() {
+                    Some((x, count)) if x.eq_ignore_ascii_case(category) => *count += 1,
+                    _ => shown.push((category, 1))
+                }
+            }
+            for (category, count) in shown {
+                println!("{} ({} products and add-ons)", category, count);
+            }
+            Ok(())
+        },
+        _ => Err(Error::usage(usage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::CatalogEntry;
+    use crate::money::{Money, USD};
+    use crate::{Date, OrderLine};
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    fn tags(input: &str) -> Vec<String> {
+        parse_tags(input)
+    }
+
+    fn product(category: &str, tags: &[String]) -> Product {
+        Product {
+            name: "Sword".to_string(),
+            desc: "".to_string(),
+            base_price: Money::default(),
+            sticker_price: Money::default(),
+            category: category.to_string(),
+            tags: tags.to_vec(),
+            items: None,
+            add_ons: None
+        }
+    }
+
+    fn add_on(category: &str, tags: &[String], assoc_product: Option<Product>) -> AddOn {
+        AddOn {
+            name: "Grip".to_string(),
+            desc: "".to_string(),
+            sticker_price: Money::default(),
+            actual_price: Money::default(),
+            category: category.to_string(),
+            tags: tags.to_vec(),
+            assoc_product
+        }
+    }
+
+    fn order(products: Vec<Product>, tags: &[String]) -> Order {
+        Order {
+            date_placed: Date::new(2026, 10, 1).unwrap(),
+            vendor: None,
+            status_history: vec!(),
+            currency: USD,
+            subtotal: Money::default(),
+            breakdown: None,
+            total: Money::default(),
+            products: products.into_iter().map(|x| OrderLine {
+                product: x,
+                quantity: 1,
+                unit_price: Money::default(),
+                paid: Money::default(),
+                discount_reason: "".to_string(),
+                notes: "".to_string()
+            }).collect(),
+            discounts: vec!(),
+            refunds: vec!(),
+            payments: vec!(),
+            tags: tags.to_vec(),
+            notes: "".to_string()
+        }
+    }
+
+    // The category and tags of everything in product, as owned strings
+    fn collected(product: &Product, parent: (&str, &[String])) -> Vec<(String, Vec<String>)> {
+        let mut found: Vec<(&str, &[String])> = vec!();
+        collect_product(product, parent, &mut found);
+        found.into_iter().map(|(x, y)| (x.to_string(), y.to_vec())).collect()
+    }
+
+    fn categories(ledger: &Ledger) -> Vec<String> {
+        let mut found: Vec<(&str, &[String])> = vec!();
+        for order in &ledger.orders {
+            for line in &order.products {
+                collect_product(&line.product, ("", &[]), &mut found);
+            }
+        }
+        for entry in &ledger.catalog {
+            collect_product(entry.product(), ("", &[]), &mut found);
+        }
+        found.into_iter().map(|(x, _)| x.to_string()).collect()
+    }
+
+    #[test]
+    fn own_categories_and_tags_win_over_inherited_ones() {
+        let gift = tags("gift");
+        let sale = tags("sale, clearance");
+        assert_eq!(inherit("Hobby", &sale, ("Sport", &gift)), ("Hobby", sale.as_slice()));
+        assert_eq!(inherit("", &[], ("Sport", &gift)), ("Sport", gift.as_slice()));
+        // Category and tags are inherited separately
+        assert_eq!(inherit("Hobby", &[], ("Sport", &gift)), ("Hobby", gift.as_slice()));
+        assert_eq!(inherit("", &sale, ("Sport", &gift)), ("Sport", sale.as_slice()));
+    }
+
+    #[test]
+    fn everything_inherits_from_whatever_its_part_of() {
+        let gift = tags("gift");
+        let sale = tags("sale");
+        // A bundle in Hobby with a sub-product of its own category, one without, and an add-on
+        // without one whose product has tags of its own
+        let mut bundle = product("Hobby", &[]);
+        bundle.items = Some(vec!(product("Hobby > Blades", &[]), product("", &[])));
+        bundle.add_ons = Some(vec!(add_on("", &[], Some(product("", &sale)))));
+        let owned = |x: &str, y: &[String]| (x.to_string(), y.to_vec());
+        assert_eq!(collected(&bundle, ("", &gift)), vec!(owned("Hobby", &gift), owned("Hobby > Blades", &gift), owned("Hobby", &gift), owned("Hobby", &gift), owned("Hobby", &sale)));
+
+        // Top level products get the order's tags, but never a category from it
+        let order = order(vec!(product("", &[]), product("Sport", &sale)), &gift);
+        assert_eq!(line_category(&order, 0), ("", gift.as_slice()));
+        assert_eq!(line_category(&order, 1), ("Sport", sale.as_slice()));
+    }
+
+    #[test]
+    fn filters_go_by_what_was_inherited() {
+        let mut bundle = product("Hobby > Fencing", &[]);
+        bundle.items = Some(vec!(product("", &tags("blade"))));
+        let order = order(vec!(bundle), &tags("Gift"));
+        let filter = |category: Option<&str>, tag: Option<&str>| Filter { category: category.map(|x| x.to_string()), tag: tag.map(|x| x.to_string()) };
+        assert!(filter(Some("hobby"), None).matches(&order));
+        assert!(filter(Some("Hobby > Fencing"), Some("gift")).matches(&order));
+        assert!(filter(None, Some("BLADE")).matches(&order));
+        assert!(!filter(Some("Hobby > Fence"), None).matches(&order));
+        assert!(!filter(Some("Hobby > Fencing > Blades"), None).matches(&order));
+        assert!(!filter(Some("Hobby"), Some("sale")).matches(&order));
+    }
+
+    #[test]
+    fn renaming_moves_everything_under_a_category() {
+        let mut ledger = Ledger::new();
+        let mut sword = product("Hobby > Fencing", &[]);
+        sword.items = Some(vec!(product("hobby > fencing > Blades", &[]), product("", &[])));
+        sword.add_ons = Some(vec!(add_on("Hobby > Fencing Gear", &[], Some(product("Hobby > Fencing", &[])))));
+        ledger.orders.push(order(vec!(sword, product("Hobby", &[])), &[]));
+        ledger.catalog.push(CatalogEntry::upgraded(product("Hobby > Fencing > Masks", &[]), USD, false));
+        assert_eq!(rename(&mut ledger, "Hobby > Fencing", "Sport > Fencing"), (4, false));
+        // Only whole parts of a category count, and whatever had no category of its own still
+        // inherits the new name
+        assert_eq!(categories(&ledger), vec!("Sport > Fencing", "Sport > Fencing > Blades", "Sport > Fencing", "Hobby > Fencing Gear", "Sport > Fencing", "Hobby", "Sport > Fencing > Masks"));
+        assert_eq!(rename(&mut ledger, "Cooking", "Food"), (0, false));
+    }
+
+    #[test]
+    fn renaming_into_a_category_that_exists_merges_them() {
+        let dir = env::temp_dir().join(format!("purchase_tracker_category_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("orders.ptl");
+        let mut ledger = Ledger::new();
+        ledger.orders.push(order(vec!(product("Sport > Fencing > Blades", &[]), product("Sport", &[]), product("Hobby > Fencing", &[])), &[]));
+        ledger.catalog.push(CatalogEntry::upgraded(product("Hobby > Fencing > Masks", &[]), USD, false));
+        storage::save(&path, &ledger).unwrap();
+        let args = |x: &[&str]| x.iter().map(|y| y.to_string()).collect::<Vec<String>>();
+        let merged = categories_cli(&args(&["rename", &path.display().to_string(), "hobby>fencing", "Sport > Fencing"]));
+        let missing = categories_cli(&args(&["rename", &path.display().to_string(), "Hobby", "Sport"]));
+        let after = storage::load(&path).map(|x| categories(&x));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(merged.is_ok());
+        // Hobby had nothing left in it after the merge
+        assert!(matches!(missing, Err(Error::Validation { field, .. }) if field == "OLD"));
+        assert_eq!(after.unwrap(), vec!("Sport > Fencing > Blades", "Sport", "Sport > Fencing", "Sport > Fencing > Masks"));
+        let mut ledger = Ledger::new();
+        ledger.orders.push(order(vec!(product("Sport > Fencing", &[]), product("Hobby", &[])), &[]));
+        assert_eq!(rename(&mut ledger, "Hobby", "Sport"), (1, true));
+    }
+}
@@ -0,0 +1,250 @@
+use bincode::{Decode, Encode};
+use std::io;
+
+use crate::error::Error;
+use crate::money::{Currency, Money};
+use crate::{ask, format_percent, parse_count, parse_percent, read_answer, Order, OrderLine};
+
+/*
+ * Coupons, loyalty rewards, price matches and other discounts that came off an order after its
+ * products were priced. Order.subtotal already has them taken off (see the note under Order),
+ * these just record where that money went. A discount is either a percentage or a fixed amount,
+ * and is either for the whole order or only for some of its products.
+ */
+#[derive(Encode, Decode, Debug, Clone)]
+pub struct Discount {
+    pub source: Source,
+    pub code: String, // The coupon code, "" if there wasn't one
+    pub amount: Amount,
+    pub lines: Option<Vec<usize>> // Indices into Order.products it was for, None for the whole order
+}
+
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Promo, // Coupons and sales codes
+    Loyalty,
+    PriceMatch,
+    Other
+}
+
+impl Source {
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Promo => "promo",
+            Source::Loyalty => "loyalty",
+            Source::PriceMatch => "price match",
+            Source::Other => "other"
+        }
+    }
+}
+
+pub fn parse_source(input: &str, field: &str) -> Result<Source, Error> {
+    match input.trim().to_lowercase().as_str() {
+        "promo" | "coupon" => Ok(Source::Promo),
+        "loyalty" => Ok(Source::Loyalty),
+        "price match" | "price-match" | "pricematch" => Ok(Source::PriceMatch),
+        "other" => Ok(Source::Other),
+        _ => Err(Error::parse(field, input, "A discount comes from \"promo\", \"loyalty\", \"price match\" or \"other\"."))
+    }
+}
+
+#[derive(Encode, Decode, Debug, Clone, Copy)]
+pub enum Amount {
+    Percent(u32), // In hundredths of a percent, like Breakdown.tax_rate
+    Fixed(Money)
+}
+
+impl Amount {
+    // Like "15%" or "$5.00"
+    pub fn to_string_in(self, currency: Currency) -> String {
+        match self {
+            Amount::Percent(x) => format_percent(x),
+            Amount::Fixed(x) => x.to_string_in(currency)
+        }
+    }
+}
+
+// "15%" is a percentage and anything else is an amount of money
+pub fn parse_amount(input: &str, currency: Currency, field: &str) -> Result<Amount, Error> {
+    match input.trim().ends_with('%') {
+        true => parse_percent(input, field).map(Amount::Percent),
+        false => Money::parse(input, currency, field).map(Amount::Fixed)
+    }
+}
+
+// "0, 2" -> Some([0, 2]), "" -> None (the whole order). count is how many products the order has.
+pub fn parse_lines(input: &str, count: usize, field: &str) -> Result<Option<Vec<usize>>, Error> {
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+    let mut lines: Vec<usize> = vec!();
+    for number in input.split(',') {
+        match number.trim().parse::<usize>() {
+            Ok(x) if x < count => lines.push(x),
+            Ok(x) => return Err(Error::validation(field, format!("there's no product {}, they go from 0 to {}", x, count as i64 - 1))),
+            Err(x) => return Err(Error::parse(field, number, x))
+        }
+    }
+    lines.sort();
+    lines.dedup();
+    Ok(Some(lines))
+}
+
+impl Discount {
+    fn applies_to(&self, line: usize) -> bool {
+        self.lines.as_ref().is_none_or(|x| x.contains(&line))
+    }
+
+    // How much this discount took off an order with these products. Percentages are of what was
+    // paid for the products they're for.
+    pub fn saved(&self, products: &[OrderLine]) -> Money {
+        match self.amount {
+            Amount::Fixed(x) => x,
+            Amount::Percent(x) => {
+                let base: u128 = products.iter().enumerate()
+                    .filter(|(index, _)| self.applies_to(*index))
+                    .map(|(_, line)| line.paid.minor() as u128)
+                    .sum();
+                Money::from_minor(u64::try_from((base * x as u128 + 5000) / 10000).unwrap_or(u64::MAX))
+            }
+        }
+    }
+
+    // Like "promo SAVE10: 10% off products 0, 2 ($3.50)"
+    pub fn to_string_in(&self, currency: Currency, products: &[OrderLine]) -> String {
+        let mut temp = self.source.name().to_string();
+        if !self.code.is_empty() {
+            temp.push(' ');
+            temp.push_str(&self.code);
+        }
+        temp.push_str(": ");
+        temp.push_str(&self.amount.to_string_in(currency));
+        match &self.lines {
+            None => temp.push_str(" off the whole order"),
+            Some(x) => temp.push_str(&format!(" off products {}", x.iter().map(|y| y.to_string()).collect::<Vec<String>>().join(", ")))
+        }
+        if let Amount::Percent(_) = self.amount {
+            temp.push_str(&format!(" ({})", self.saved(products).to_string_in(currency)));
+        }
+        temp
+    }
+}
+
+// Discounts can only be for products the order has
+pub fn check(order: &Order) -> Result<(), Error> {
+    for (index, discount) in order.discounts.iter().enumerate() {
+        if let Some(x) = &discount.lines
+            && let Some(y) = x.iter().find(|y| **y >= order.products.len()) {
+            return Err(Error::validation(&format!("discounts[{}]", index), format!("it's for product {}, but the order only has {} products", y, order.products.len())));
+        }
+    }
+    Ok(())
+}
+
+// Product number line of an order can only be removed when none of its discounts are for it, since
+// there'd be no telling how much of a discount went to what's left
+pub fn check_line_removal(discounts: &[Discount], line: usize) -> Result<(), Error> {
+    match discounts.iter().position(|x| x.lines.as_ref().is_some_and(|y| y.contains(&line))) {
+        Some(x) => Err(Error::validation(&format!("products[{}]", line), format!("discount {} is for it, so change or remove that discount first", x))),
+        None => Ok(())
+    }
+}
+
+// Once product number line is gone, the products after it move up by one, and so do the numbers of
+// the discounts for them
+pub fn line_removed(discounts: &mut [Discount], line: usize) {
+    for lines in discounts.iter_mut().filter_map(|x| x.lines.as_mut()) {
+        for x in lines.iter_mut().filter(|x| **x > line) {
+            *x -= 1;
+        }
+    }
+}
+
+// How much an order saved, as (off the sticker prices of its products, through its discounts)
+pub fn savings(order: &Order) -> Result<(Money, Money), Error> {
+    let mut sales: Vec<Money> = vec!();
+    for line in &order.products {
+        let usual = match line.product.sticker_price.checked_mul(line.quantity) {
+            Some(x) => x,
+            None => return Err(Error::validation(&format!("the sticker price of {}", line.product.name.trim()), "times its quantity, it's more money than purchase_tracker can count"))
+        };
+        sales.push(usual.checked_sub(line.paid).unwrap_or_default());
+    }
+    let sales = Money::sum(sales, "the sales on the products")?;
+    let discounts = Money::sum(order.discounts.iter().map(|x| x.saved(&order.products)), "the discounts")?;
+    Ok((sales, discounts))
+}
+
+// Asks about one discount on an order with these products
+pub fn discount_cli(stdin: &io::Stdin, currency: Currency, products: &[OrderLine]) -> Result<Discount, Error> {
+    println!("Where did the discount come from? (Type \"promo\" for coupons and sales codes, \"loyalty\", \"price match\" or \"other\")");
+    let source = ask(stdin, |x| parse_source(x, "where the discount came from"))?;
+    println!("What was the coupon code? (Leave it blank if there wasn't one)");
+    let code = read_answer(stdin)?.trim().to_string();
+    println!("How much did it take off? (Type a percentage like \"15%\" or an amount like \"{}\")", Money::from_minor(500).to_string_in(currency));
+    let amount = ask(stdin, |x| parse_amount(x, currency, "the discount"))?;
+    let mut lines: Option<Vec<usize>> = None;
+    if !products.is_empty() {
+        println!("Which products was it for? (Type their numbers separated by commas, like \"0, 2\", or leave it blank if it was for the whole order)");
+        for (index, line) in products.iter().enumerate() {
+            println!("  {}. {}", index, line.product.name.trim());
+        }
+        lines = ask(stdin, |x| parse_lines(x, products.len(), "the products the discount was for"))?;
+    }
+    Ok(Discount {
+        source,
+        code,
+        amount,
+        lines
+    })
+}
+
+// Asks about every discount on an order with these products
+pub fn discounts_cli(currency: Currency, products: &[OrderLine]) -> Result<Vec<Discount>, Error> {
+    let stdin = io::stdin();
+    println!("How many coupons or other discounts did you use on this order? (Leave it blank if there weren't any)");
+    let count = ask(&stdin, |x| match x.trim() {
+        "" => Ok(0),
+        y => parse_count(y, "the number of discounts")
+    })?;
+    let mut discounts: Vec<Discount> = vec!();
+    for i in 0..count {
+        println!("Discount {}", i);
+        discounts.push(discount_cli(&stdin, currency, products)?);
+    }
+    Ok(discounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discount(lines: Option<Vec<usize>>) -> Discount {
+        Discount {
+            source: Source::Promo,
+            code: "".to_string(),
+            amount: Amount::Fixed(Money::from_minor(100)),
+            lines
+        }
+    }
+
+    #[test]
+    fn later_lines_move_up_when_a_line_is_removed() {
+        let mut discounts = vec!(discount(Some(vec!(0, 3))), discount(None), discount(Some(vec!(1, 4))));
+        assert!(check_line_removal(&discounts, 2).is_ok());
+        line_removed(&mut discounts, 2);
+        assert_eq!(discounts[0].lines, Some(vec!(0, 2)));
+        assert_eq!(discounts[1].lines, None);
+        assert_eq!(discounts[2].lines, Some(vec!(1, 3)));
+    }
+
+    #[test]
+    fn lines_with_a_discount_cant_be_removed() {
+        let discounts = vec!(discount(None), discount(Some(vec!(0, 2))));
+        assert!(check_line_removal(&discounts, 2).is_err());
+        assert!(check_line_removal(&discounts, 0).is_err());
+        assert!(check_line_removal(&discounts, 1).is_ok());
+        // A discount for the whole order isn't for any one product
+        assert!(check_line_removal(&[discount(None)], 0).is_ok());
+    }
+}
@@ -0,0 +1,550 @@
+use std::io;
+use std::path::Path;
+
+use crate::args::Args;
+use crate::browse::summary_line;
+use crate::catalog::CatalogEntry;
+use crate::category::{parse_category, parse_tags, tags_to_string};
+use crate::discount::{self, discount_cli, parse_amount, parse_lines, parse_source, Discount};
+use crate::error::Error;
+use crate::money::{Currency, Money};
+use crate::payment::{self, PaymentMethod};
+use crate::refund::{self, new_refund_cli, Refund};
+use crate::vendor::{self, Vendor};
+use crate::{add_on_cli, ask, ask_yes_no, format_percent, order_line_cli, parse_date, parse_count, parse_order_index, parse_quantity, parse_percent, product_cli, read_answer, status_cli, storage, AddOn, Breakdown, Order, OrderLine, Product, ABORT};
+
+/*
+ * The editor shows a numbered menu of the fields of whatever is being edited (the order, one of
+ * its products, an add-on...). Typing a number edits that field, and typing "done" goes back up a
+ * level. Lists (products, sub-products, add-ons) also take "add" and "remove N". Nothing is saved
+ * until "done" is typed at the top level, and "abort" throws every change away like it does in the
+ * order creator.
+ */
+
+// What was typed at a menu
+enum Choice {
+    Done,
+    Add,
+    Remove(usize),
+    Edit(usize)
+}
+
+// count is how many entries the menu has, list says whether "add" and "remove N" make sense
+fn parse_choice(input: &str, count: usize, list: bool) -> Result<Choice, Error> {
+    let input = input.trim();
+    if input == "done" {
+        return Ok(Choice::Done);
+    }
+    if list && input == "add" {
+        return Ok(Choice::Add);
+    }
+    let (index, remove) = match input.strip_prefix("remove") {
+        Some(x) if list => (x.trim(), true),
+        _ => (input, false)
+    };
+    let index = match index.parse::<usize>() {
+        Ok(x) if x < count => x,
+        Ok(x) => return Err(Error::validation("the menu", format!("there's no entry {}, they go from 0 to {}", x, count as i64 - 1))),
+        Err(x) => return Err(Error::parse("a menu entry", input, x))
+    };
+    match remove {
+        true => Ok(Choice::Remove(index)),
+        false => Ok(Choice::Edit(index))
+    }
+}
+
+fn menu(stdin: &io::Stdin, entries: &[String]) -> Result<Choice, Error> {
+    for (index, entry) in entries.iter().enumerate() {
+        println!("  {}. {}", index, entry);
+    }
+    println!("What do you want to change? (Type its number, or \"done\" when you're finished)");
+    ask(stdin, |x| parse_choice(x, entries.len(), false))
+}
+
+// Asks for a new value of a text field. A blank answer keeps the old one.
+fn edit_text(stdin: &io::Stdin, field: &str, current: &mut String) -> Result<(), Error> {
+    println!("New {}? (Currently \"{}\", leave it blank to keep that)", field, current.trim());
+    let answer = read_answer(stdin)?;
+    if !answer.trim().is_empty() {
+        *current = answer.trim().to_string();
+    }
+    Ok(())
+}
+
+fn edit_money(stdin: &io::Stdin, currency: Currency, field: &str, current: &mut Money) -> Result<(), Error> {
+    println!("New {}? (Currently {}, leave it blank to keep that)", field, current.to_string_in(currency));
+    let answer = ask(stdin, |x| match x.trim() {
+        "" => Ok(None),
+        y => Money::parse(y, currency, field).map(Some)
+    })?;
+    if let Some(x) = answer {
+        *current = x;
+    }
+    Ok(())
+}
+
+// Categories and tags can be taken away with "none", since a blank answer keeps them
+fn edit_category(stdin: &io::Stdin, category: &mut String) -> Result<(), Error> {
+    println!("New category? (Currently \"{}\", like \"Hobby > Fencing > Blades\". Leave it blank to keep that, or type \"none\" to use the one of what it's part of)", category);
+    let answer = read_answer(stdin)?;
+    match answer.trim() {
+        "" => (),
+        "none" => *category = "".to_string(),
+        x => *category = parse_category(x)
+    }
+    Ok(())
+}
+
+fn edit_tags(stdin: &io::Stdin, tags: &mut Vec<String>) -> Result<(), Error> {
+    println!("New tags? (Currently \"{}\", separated by commas. Leave it blank to keep them, or type \"none\" to remove them)", tags_to_string(tags));
+    let answer = read_answer(stdin)?;
+    match answer.trim() {
+        "" => (),
+        "none" => tags.clear(),
+        x => *tags = parse_tags(x)
+    }
+    Ok(())
+}
+
+// Edits a list of things in place. label describes one entry in the menu, add asks for a brand new
+// entry and edit changes an existing one (getting its index).
+fn edit_list<T>(stdin: &io::Stdin, what: &str, list: &mut Vec<T>, label: impl Fn(&T) -> String, add: impl Fn() -> Result<T, Error>, edit: impl Fn(&mut T, usize) -> Result<(), Error>) -> Result<(), Error> {
+    edit_list_removing(stdin, what, list, label, add, edit, |_| Ok(()))
+}
+
+// Like edit_list, for lists that other things point into by index. remove is told the index of an
+// entry before it's removed, and either fixes up whatever points past it or refuses the removal.
+fn edit_list_removing<T>(stdin: &io::Stdin, what: &str, list: &mut Vec<T>, label: impl Fn(&T) -> String, add: impl Fn() -> Result<T, Error>, edit: impl Fn(&mut T, usize) -> Result<(), Error>, mut remove: impl FnMut(usize) -> Result<(), Error>) -> Result<(), Error> {
+    loop {
+        println!("{}:", what);
+        if list.is_empty() {
+            println!("  (none)");
+        }
+        for (index, entry) in list.iter().enumerate() {
+            println!("  {}. {}", index, label(entry));
+        }
+        println!("Type a number to change that entry, \"add\" to add one, \"remove\" followed by a number to remove that entry, or \"done\" when you're finished.");
+        match ask(stdin, |x| parse_choice(x, list.len(), true))? {
+            Choice::Done => return Ok(()),
+            Choice::Add => list.push(add()?),
+            Choice::Remove(x) => match remove(x) {
+                Ok(_) => {
+                    list.remove(x);
+                    println!("Removed entry {}. The entries after it moved up by one.", x);
+                },
+                Err(y) => println!("{}", y.to_string())
+            },
+            Choice::Edit(x) => edit(&mut list[x], x)?
+        }
+    }
+}
+
+// Lists that were left empty are stored as None, like the order creator does when it's told "No"
+fn edit_optional_list<T>(stdin: &io::Stdin, what: &str, list: &mut Option<Vec<T>>, label: impl Fn(&T) -> String, add: impl Fn() -> Result<T, Error>, edit: impl Fn(&mut T, usize) -> Result<(), Error>) -> Result<(), Error> {
+    let mut temp = list.take().unwrap_or_default();
+    let result = edit_list(stdin, what, &mut temp, label, add, edit);
+    if !temp.is_empty() {
+        *list = Some(temp);
+    }
+    result
+}
+
+// currency is the one the order is in
+pub fn edit_product(stdin: &io::Stdin, currency: Currency, product: &mut Product, location: &str) -> Result<(), Error> {
+    loop {
+        println!("Editing {} ({})", location, product.name.trim());
+        let entries = vec!(
+            format!("Name: {}", product.name.trim()),
+            format!("Description: {}", product.desc.trim()),
+            format!("Base price: {}", product.base_price.to_string_in(currency)),
+            format!("Sticker price: {}", product.sticker_price.to_string_in(currency)),
+            format!("Category: {}", product.category),
+            format!("Tags: {}", tags_to_string(&product.tags)),
+            format!("Sub-products: {}", product.items.as_ref().map_or(0, |x| x.len())),
+            format!("Add-ons: {}", product.add_ons.as_ref().map_or(0, |x| x.len()))
+        );
+        match menu(stdin, &entries)? {
+            Choice::Edit(0) => edit_text(stdin, "name", &mut product.name)?,
+            Choice::Edit(1) => edit_text(stdin, "description", &mut product.desc)?,
+            Choice::Edit(2) => edit_money(stdin, currency, "base price", &mut product.base_price)?,
+            Choice::Edit(3) => edit_money(stdin, currency, "sticker price", &mut product.sticker_price)?,
+            Choice::Edit(4) => edit_category(stdin, &mut product.category)?,
+            Choice::Edit(5) => edit_tags(stdin, &mut product.tags)?,
+            Choice::Edit(6) => edit_optional_list(stdin, &format!("Sub-products of {}", product.name.trim()), &mut product.items,
+                |x| x.name.trim().to_string(),
+                || product_cli(currency),
+                |x, index| edit_product(stdin, currency, x, &format!("{}.items[{}]", location, index)))?,
+            Choice::Edit(_) => edit_optional_list(stdin, &format!("Add-ons of {}", product.name.trim()), &mut product.add_ons,
+                |x| x.name.trim().to_string(),
+                || add_on_cli(currency),
+                |x, index| edit_add_on(stdin, currency, x, &format!("{}.add_ons[{}]", location, index)))?,
+            _ => return Ok(())
+        }
+    }
+}
+
+// One of the top level products of an order, with what was bought of it and what it cost
+fn edit_line(stdin: &io::Stdin, currency: Currency, line: &mut OrderLine, location: &str) -> Result<(), Error> {
+    loop {
+        println!("Editing {} ({})", location, line.product.name.trim());
+        let entries = vec!(
+            format!("Product: {} (its name, prices, sub-products and add-ons)", line.product.name.trim()),
+            format!("Quantity: {}", line.quantity),
+            format!("Unit price: {}", line.unit_price.to_string_in(currency)),
+            format!("Paid for all of them: {}", line.paid.to_string_in(currency)),
+            format!("Discount reason: {}", line.discount_reason),
+            format!("Notes: {}", line.notes)
+        );
+        match menu(stdin, &entries)? {
+            Choice::Edit(0) => edit_product(stdin, currency, &mut line.product, location)?,
+            Choice::Edit(1) => {
+                println!("New quantity? (Currently {}, leave it blank to keep that) The amount paid doesn't change with it, so change that too if needed.", line.quantity);
+                let current = line.quantity;
+                line.quantity = ask(stdin, |x| match x.trim() {
+                    "" => Ok(current),
+                    y => parse_quantity(y, "the quantity")
+                })?;
+            },
+            Choice::Edit(2) => edit_money(stdin, currency, "unit price (the cost of one, accounting for all add-ons and sales)", &mut line.unit_price)?,
+            Choice::Edit(3) => edit_money(stdin, currency, "amount paid for all of them", &mut line.paid)?,
+            Choice::Edit(4) => edit_text(stdin, "discount reason", &mut line.discount_reason)?,
+            Choice::Edit(_) => edit_text(stdin, "notes about this product", &mut line.notes)?,
+            _ => return Ok(())
+        }
+    }
+}
+
+fn edit_add_on(stdin: &io::Stdin, currency: Currency, add_on: &mut AddOn, location: &str) -> Result<(), Error> {
+    loop {
+        println!("Editing {} ({})", location, add_on.name.trim());
+        let entries = vec!(
+            format!("Name: {}", add_on.name.trim()),
+            format!("Description: {}", add_on.desc.trim()),
+            format!("Sticker price: {}", add_on.sticker_price.to_string_in(currency)),
+            format!("Actual price: {}", add_on.actual_price.to_string_in(currency)),
+            format!("Category: {}", add_on.category),
+            format!("Tags: {}", tags_to_string(&add_on.tags)),
+            format!("Associated product: {}", add_on.assoc_product.as_ref().map_or("none".to_string(), |x| x.name.trim().to_string()))
+        );
+        match menu(stdin, &entries)? {
+            Choice::Edit(0) => edit_text(stdin, "name", &mut add_on.name)?,
+            Choice::Edit(1) => edit_text(stdin, "description", &mut add_on.desc)?,
+            Choice::Edit(2) => edit_money(stdin, currency, "sticker price", &mut add_on.sticker_price)?,
+            Choice::Edit(3) => edit_money(stdin, currency, "actual price", &mut add_on.actual_price)?,
+            Choice::Edit(4) => edit_category(stdin, &mut add_on.category)?,
+            Choice::Edit(5) => edit_tags(stdin, &mut add_on.tags)?,
+            Choice::Edit(_) => edit_assoc_product(stdin, currency, add_on, location)?,
+            _ => return Ok(())
+        }
+    }
+}
+
+fn edit_assoc_product(stdin: &io::Stdin, currency: Currency, add_on: &mut AddOn, location: &str) -> Result<(), Error> {
+    let location = format!("{}.assoc_product", location);
+    match &mut add_on.assoc_product {
+        None => {
+            println!("{} doesn't have an associated product. Add one? (Type \"Yes\" or \"No\")", add_on.name.trim());
+            if ask_yes_no(stdin)? {
+                add_on.assoc_product = Some(product_cli(currency)?);
+            }
+        },
+        Some(x) => {
+            println!("Type \"edit\" to change the associated product, \"remove\" to remove it, or leave it blank to keep it as it is.");
+            let answer = ask(stdin, |y| match y.trim() {
+                "" | "edit" | "remove" => Ok(y.trim().to_string()),
+                _ => Err(Error::validation("the answer", "it has to be \"edit\", \"remove\" or blank"))
+            })?;
+            match answer.as_str() {
+                "edit" => edit_product(stdin, currency, x, &location)?,
+                "remove" => add_on.assoc_product = None,
+                _ => ()
+            }
+        }
+    }
+    Ok(())
+}
+
+fn edit_breakdown(stdin: &io::Stdin, currency: Currency, breakdown: &mut Breakdown) -> Result<(), Error> {
+    loop {
+        println!("Editing where the total came from");
+        let entries = vec!(
+            format!("Shipping: {}", breakdown.shipping.to_string_in(currency)),
+            format!("Tax: {}", breakdown.tax.to_string_in(currency)),
+            format!("Tax rate: {}", breakdown.tax_rate.map_or("unknown".to_string(), format_percent)),
+            format!("Fees: {}", breakdown.fees.to_string_in(currency)),
+            format!("Tip: {}", breakdown.tip.to_string_in(currency)),
+            format!("Gift cards and store credit: {}", breakdown.credits.to_string_in(currency))
+        );
+        match menu(stdin, &entries)? {
+            Choice::Edit(0) => edit_money(stdin, currency, "shipping", &mut breakdown.shipping)?,
+            Choice::Edit(1) => edit_money(stdin, currency, "tax", &mut breakdown.tax)?,
+            Choice::Edit(2) => {
+                println!("New tax rate? (Leave it blank to keep it, or type \"unknown\" if you don't know it)");
+                let answer = ask(stdin, |x| match x.trim() {
+                    "" => Ok(breakdown.tax_rate),
+                    "unknown" => Ok(None),
+                    y => parse_percent(y, "the tax rate").map(Some)
+                })?;
+                breakdown.tax_rate = answer;
+            },
+            Choice::Edit(3) => edit_money(stdin, currency, "fees", &mut breakdown.fees)?,
+            Choice::Edit(4) => edit_money(stdin, currency, "tip", &mut breakdown.tip)?,
+            Choice::Edit(_) => edit_money(stdin, currency, "amount paid with gift cards and store credit", &mut breakdown.credits)?,
+            _ => return Ok(())
+        }
+    }
+}
+
+// products are the products of the order the discount is on
+fn edit_discount(stdin: &io::Stdin, currency: Currency, discount: &mut Discount, products: &[OrderLine], index: usize) -> Result<(), Error> {
+    loop {
+        println!("Editing discounts[{}]", index);
+        let entries = vec!(
+            format!("Source: {}", discount.source.name()),
+            format!("Coupon code: {}", discount.code),
+            format!("Amount: {} (took {} off)", discount.amount.to_string_in(currency), discount.saved(products).to_string_in(currency)),
+            format!("Products: {}", match &discount.lines {
+                None => "the whole order".to_string(),
+                Some(x) => x.iter().map(|y| y.to_string()).collect::<Vec<String>>().join(", ")
+            })
+        );
+        match menu(stdin, &entries)? {
+            Choice::Edit(0) => {
+                println!("New source? (Type \"promo\", \"loyalty\", \"price match\" or \"other\", or leave it blank to keep it)");
+                let current = discount.source;
+                discount.source = ask(stdin, |x| match x.trim() {
+                    "" => Ok(current),
+                    y => parse_source(y, "where the discount came from")
+                })?;
+            },
+            Choice::Edit(1) => edit_text(stdin, "coupon code", &mut discount.code)?,
+            Choice::Edit(2) => {
+                println!("New amount? (A percentage like \"15%\" or an amount like \"{}\", or leave it blank to keep it)", Money::from_minor(500).to_string_in(currency));
+                let current = discount.amount;
+                discount.amount = ask(stdin, |x| match x.trim() {
+                    "" => Ok(current),
+                    y => parse_amount(y, currency, "the discount")
+                })?;
+            },
+            Choice::Edit(_) => {
+                println!("Which products was it for? (Type their numbers separated by commas, or \"all\" if it was for the whole order)");
+                for (number, line) in products.iter().enumerate() {
+                    println!("  {}. {}", number, line.product.name.trim());
+                }
+                discount.lines = ask(stdin, |x| match x.trim() {
+                    "all" => Ok(None),
+                    "" => Err(Error::validation("the products the discount was for", "type some numbers, or \"all\"")),
+                    y => parse_lines(y, products.len(), "the products the discount was for")
+                })?;
+            },
+            _ => return Ok(())
+        }
+    }
+}
+
+// products are the products of the order the refund is on
+fn edit_refund(stdin: &io::Stdin, currency: Currency, refund: &mut Refund, products: &[OrderLine], index: usize) -> Result<(), Error> {
+    loop {
+        println!("Editing refunds[{}]", index);
+        let entries = vec!(
+            format!("Date: {}", refund.date.to_string()),
+            format!("Product: {}", match refund.line {
+                None => "the order as a whole".to_string(),
+                Some(x) => x.to_string()
+            }),
+            format!("Sent back: {}", refund.returned),
+            format!("Amount: {}", refund.amount.to_string_in(currency)),
+            format!("Restocking fee: {}", refund.restocking_fee.to_string_in(currency)),
+            format!("Reason: {}", refund.reason)
+        );
+        match menu(stdin, &entries)? {
+            Choice::Edit(0) => {
+                println!("New date? (Currently {}, leave it blank to keep that. Use \"MM/DD/YYYY\" or \"YYYY-MM-DD\" format.)", refund.date.to_string());
+                let current = refund.date;
+                refund.date = ask(stdin, |x| match x.trim() {
+                    "" => Ok(current),
+                    y => parse_date(y, "the date of the refund")
+                })?;
+            },
+            Choice::Edit(1) => {
+                println!("Which product was it for? (Type its number, or \"all\" if it was for the order as a whole)");
+                for (number, line) in products.iter().enumerate() {
+                    println!("  {}. {}", number, line.product.name.trim());
+                }
+                refund.line = ask(stdin, |x| match x.trim() {
+                    "all" => Ok(None),
+                    y => match y.parse::<usize>() {
+                        Ok(z) if z < products.len() => Ok(Some(z)),
+                        _ => Err(Error::validation("the product the refund was for", "type the number of one of the products above, or \"all\""))
+                    }
+                })?;
+            },
+            Choice::Edit(2) => {
+                println!("How many were sent back? (Currently {}, leave it blank to keep that)", refund.returned);
+                let current = refund.returned;
+                refund.returned = ask(stdin, |x| match x.trim() {
+                    "" => Ok(current),
+                    y => parse_count(y, "the number of products sent back")
+                })?;
+            },
+            Choice::Edit(3) => edit_money(stdin, currency, "amount that came back", &mut refund.amount)?,
+            Choice::Edit(4) => edit_money(stdin, currency, "restocking fee", &mut refund.restocking_fee)?,
+            Choice::Edit(_) => edit_text(stdin, "reason", &mut refund.reason)?,
+            _ => return Ok(())
+        }
+    }
+}
+
+// Status changes can't come before the order was placed, see update_status_cli
+fn check_dates(order: &Order) -> Result<(), Error> {
+    for change in &order.status_history {
+        if let Some(x) = change.date
+            && x < order.date_placed {
+            return Err(Error::validation("the status", format!("the order became {} on {}, which is before it was placed on {}", change.status.name(), x.to_string(), order.date_placed.to_string())));
+        }
+    }
+    Ok(())
+}
+
+// vendors, methods and catalog belong to the ledger the order is in. Picking a new vendor or
+// payment method adds to vendors or methods.
+fn edit_order(stdin: &io::Stdin, order: &mut Order, vendors: &mut Vec<Vendor>, methods: &mut Vec<PaymentMethod>, catalog: &[CatalogEntry]) -> Result<(), Error> {
+    loop {
+        let currency = order.currency;
+        println!("Editing the order");
+        let entries = vec!(
+            format!("Date placed: {}", order.date_placed.to_string()),
+            format!("Vendor: {}", vendor::vendor_name(order, vendors)),
+            format!("Status: {}", match order.status_history.last() {
+                Some(x) => x.to_string(),
+                None => format!("{} on {}", order.status().name(), order.date_placed.to_string())
+            }),
+            format!("Currency: {}", currency.code()),
+            format!("Subtotal: {}", order.subtotal.to_string_in(currency)),
+            format!("Shipping, tax, fees, tip and credits: {}", match &order.breakdown {
+                Some(x) => x.to_string_in(currency).replace('\n', ", "),
+                None => "not broken down".to_string()
+            }),
+            format!("Total: {}", order.total.to_string_in(currency)),
+            format!("Notes: {}", order.notes.trim()),
+            format!("Discounts: {}", order.discounts.len()),
+            format!("Refunds: {}", order.refunds.len()),
+            format!("Paid with: {}", payment::payments_to_string(order, methods)),
+            format!("Tags: {}", tags_to_string(&order.tags)),
+            format!("Products: {}", order.products.len())
+        );
+        match menu(stdin, &entries)? {
+            Choice::Edit(0) => {
+                println!("New date placed? (Currently {}, leave it blank to keep that. Use \"MM/DD/YYYY\" or \"YYYY-MM-DD\" format.)", order.date_placed.to_string());
+                let answer = ask(stdin, |x| match x.trim() {
+                    "" => Ok(None),
+                    y => parse_date(y, "the date the order was placed").map(Some)
+                })?;
+                if let Some(x) = answer {
+                    order.date_placed = x;
+                }
+            },
+            Choice::Edit(1) => {
+                println!("Picking a vendor now replaces the current one, skipping the question keeps it.");
+                if let Some(x) = vendor::pick_vendor_cli(vendors)? {
+                    order.vendor = Some(x);
+                }
+            },
+            Choice::Edit(2) => {
+                println!("The status history of the order will be replaced with what you type now.");
+                order.status_history = status_cli()?;
+            },
+            Choice::Edit(3) => {
+                println!("New currency? (Currently {}, leave it blank to keep that) The amounts in the order stay the same numbers in the new currency, so change them too if they need converting.", currency.code());
+                order.currency = ask(stdin, |x| match x.trim() {
+                    "" => Ok(currency),
+                    y => Currency::parse(y, "the currency of the order")
+                })?;
+            },
+            Choice::Edit(4) => edit_money(stdin, currency, "subtotal", &mut order.subtotal)?,
+            Choice::Edit(5) => edit_breakdown(stdin, currency, order.breakdown.get_or_insert_with(Breakdown::default))?,
+            Choice::Edit(6) => edit_money(stdin, currency, "total", &mut order.total)?,
+            Choice::Edit(7) => edit_text(stdin, "notes", &mut order.notes)?,
+            Choice::Edit(8) => edit_list(stdin, "Discounts", &mut order.discounts,
+                |x| x.to_string_in(currency, &order.products),
+                || discount_cli(stdin, currency, &order.products),
+                |x, index| edit_discount(stdin, currency, x, &order.products, index))?,
+            Choice::Edit(9) => {
+                // The closures below can't borrow order while order.refunds is borrowed mutably
+                let mut refunds = std::mem::take(&mut order.refunds);
+                let result = edit_list(stdin, "Refunds", &mut refunds,
+                    |x| x.to_string_in(currency, &order.products),
+                    || new_refund_cli(stdin, order),
+                    |x, index| edit_refund(stdin, currency, x, &order.products, index));
+                order.refunds = refunds;
+                result?
+            },
+            Choice::Edit(10) => payment::replace_payments_cli(stdin, methods, order)?,
+            Choice::Edit(11) => edit_tags(stdin, &mut order.tags)?,
+            Choice::Edit(_) => edit_list_removing(stdin, "Products", &mut order.products,
+                |x| format!("{} x {}  {}", x.quantity, x.product.name.trim(), x.paid.to_string_in(currency)),
+                || order_line_cli(currency, catalog),
+                |x, index| edit_line(stdin, currency, x, &format!("products[{}]", index)),
+                |index| {
+                    discount::check_line_removal(&order.discounts, index)?;
+                    refund::check_line_removal(&order.refunds, index)?;
+                    discount::line_removed(&mut order.discounts, index);
+                    refund::line_removed(&mut order.refunds, index);
+                    Ok(())
+                })?,
+            _ => match check_dates(order).and_then(|_| discount::check(order)).and_then(|_| refund::check(order)).and_then(|_| payment::check(order, methods)).and_then(|_| order.check_total()) {
+                Ok(_) => return Ok(()),
+                Err(x) => println!("{}\nPlease fix that before finishing (or type \"{}\" to throw away your changes).", x.to_string(), ABORT)
+            }
+        }
+    }
+}
+
+// purchase_tracker edit [FILE] [ORDER]
+pub fn edit_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &[], &[])?;
+    let (path, index) = match args.positional.as_slice() {
+        [x, y] => (Path::new(x), y),
+        _ => return Err(Error::usage("purchase_tracker edit [FILE] [ORDER]"))
+    };
+    let mut ledger = storage::load(path)?;
+    let index = parse_order_index(index, &ledger.orders, path)?;
+    let before = summary_line(index, &ledger.orders[index], &ledger.vendors);
+    println!("{}.\n{}", index, ledger.orders[index].to_string());
+    println!("(Type \"{}\" in answer to any question to throw away your changes without saving anything.)", ABORT);
+
+    let stdin = io::stdin();
+    edit_order(&stdin, &mut ledger.orders[index], &mut ledger.vendors, &mut ledger.payment_methods, &ledger.catalog)?;
+
+    storage::save(path, &ledger)?;
+    println!("Before: {}", before);
+    println!("After:  {}", summary_line(index, &ledger.orders[index], &ledger.vendors));
+    Ok(())
+}
+
+// purchase_tracker delete [FILE] [ORDER] [--yes]
+pub fn delete_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &[], &["yes"])?;
+    let (path, index) = match args.positional.as_slice() {
+        [x, y] => (Path::new(x), y),
+        _ => return Err(Error::usage("purchase_tracker delete [FILE] [ORDER] [--yes]"))
+    };
+    let mut ledger = storage::load(path)?;
+    let index = parse_order_index(index, &ledger.orders, path)?;
+    let before = summary_line(index, &ledger.orders[index], &ledger.vendors);
+    println!("{}.\n{}", index, ledger.orders[index].to_string());
+
+    if !args.switch("yes") {
+        println!("Delete this order from {}? (Type \"Yes\" or \"No\")", path.display());
+        if !ask_yes_no(&io::stdin())? {
+            println!("Left {} alone.", path.display());
+            return Ok(());
+        }
+    }
+
+    ledger.orders.remove(index);
+    storage::save(path, &ledger)?;
+    println!("Deleted: {}", before);
+    println!("{} went from {} orders to {}. The orders after it moved up by one.", path.display(), ledger.orders.len() + 1, ledger.orders.len());
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use bincode::error::EncodeError;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::format::ReadError;
+
+/*
+ * Everything that can go wrong in purchase_tracker ends up as one of these, and main turns it into
+ * a message on the standard error and one of the exit codes below. The exit codes are part of the
+ * interface (scripts wrapping purchase_tracker rely on them), so never renumber an existing one.
+ *
+ *   0  Success
+ *   2  Usage: the command line didn't make sense
+ *   3  Io: a file (or the standard input) couldn't be read or written
+ *   4  FileExists: refused to overwrite a file that already exists
+ *   5  Decode: a file couldn't be understood as a list of orders
+ *   6  Encode: the orders couldn't be turned into bytes to save
+ *   7  Parse: some input couldn't be understood (a date, an amount, a product file...)
+ *   8  Validation: some input was understood, but doesn't make sense as part of an order
+ *   9  Aborted: the user threw away what they were entering by typing "abort"
+ */
+#[derive(Debug)]
+pub enum Error {
+    Usage(String), // What was wrong with the command line
+    Io(String, io::Error), // What was being done (naming the file involved) when it failed
+    FileExists(PathBuf),
+    Decode(PathBuf, ReadError),
+    Encode(PathBuf, EncodeError),
+    Parse {
+        field: String, // What the input was supposed to be, e.g. "--subtotal"
+        input: String, // What was actually given
+        reason: String // Why it didn't work out
+    },
+    Validation {
+        field: String, // Where the problem is, e.g. "products[0].items[2]"
+        reason: String
+    },
+    Aborted
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) => 2,
+            Error::Io(_, _) => 3,
+            Error::FileExists(_) => 4,
+            Error::Decode(_, _) => 5,
+            Error::Encode(_, _) => 6,
+            Error::Parse { .. } => 7,
+            Error::Validation { .. } => 8,
+            Error::Aborted => 9
+        }
+    }
+
+    pub fn usage(usage: &str) -> Error {
+        Error::Usage(format!("Usage: {}\nRun 'purchase_tracker --help' for more information", usage))
+    }
+
+    // Io errors that happen while working on a particular file
+    pub fn io(action: &str, path: &Path, source: io::Error) -> Error {
+        Error::Io(format!("{} {}", action, path.display()), source)
+    }
+
+    pub fn stdin(source: io::Error) -> Error {
+        Error::Io("reading from the standard input".to_string(), source)
+    }
+
+    pub fn parse(field: &str, input: &str, reason: impl ToString) -> Error {
+        Error::Parse {
+            field: field.to_string(),
+            input: input.trim().to_string(),
+            reason: reason.to_string()
+        }
+    }
+
+    pub fn validation(field: &str, reason: impl ToString) -> Error {
+        Error::Validation {
+            field: field.to_string(),
+            reason: reason.to_string()
+        }
+    }
+}
+
+impl ToString for Error {
+    fn to_string(&self) -> String {
+        match self {
+            Error::Usage(x) => x.clone(),
+            Error::Io(action, x) => format!("Failed while {}.\nHere's the error that was reported: {}", action, x),
+            Error::FileExists(x) => format!("The file {} already exists, and purchase_tracker won't overwrite it here. Run purchase_tracker --help for more information.", x.display()),
+            Error::Decode(path, x) => format!("Failed to decode from {}.\nThis probably means that the file wasn't created with a previous run of purchase_tracker.\nHere's the error that was reported: {}", path.display(), x.to_string()),
+            Error::Encode(path, x) => format!("Failed to encode the orders to save them to {}. The file was left exactly as it was before this run.\nHere's the error that was reported: {}", path.display(), x),
+            Error::Parse { field, input, reason } => format!("Failed to parse \"{}\" as {}.\n{}", input, field, reason),
+            Error::Validation { field, reason } => format!("Problem with {}: {}", field, reason),
+            Error::Aborted => "Aborted. Nothing was saved.".to_string()
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::args::Args;
+use crate::category::{inherit, tags_to_string, Filter};
+use crate::discount;
+use crate::error::Error;
+use crate::money::Money;
+use crate::payment::method_name;
+use crate::refund;
+use crate::vendor::vendor_name;
+use crate::{create_new, discard_new, storage, AddOn, DateRange, Ledger, Order, OrderLine, Product};
+
+/*
+ * Writes orders out as CSV for spreadsheets, in one of two shapes:
+ *
+ * - orders: one row per order, with its amounts, what it was paid with and its notes.
+ * - lines: one row per product, sub-product, add-on and associated product. path says where the
+ *   row is in its order, in the same form search and check use ("products[0].add_ons[1]"), and
+ *   parent is the path of what it's part of ("" for top level products). Only top level products
+ *   have a quantity, a unit price and what was paid, the rest only have their own prices. Category
+ *   and tags are the ones each row ends up with, inherited ones included (see category.rs).
+ *
+ * Amounts are plain numbers in the order's currency, which gets a column of its own, and dates are
+ * "YYYY-MM-DD". Fields are quoted following RFC 4180, so descriptions and notes can have commas,
+ * quotes and line breaks in them.
+ */
+
+// Quotes field if it has anything in it that would otherwise break the row up
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n', '\r']) || field.trim() != field {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string()
+    }
+}
+
+fn csv_row(fields: &[String]) -> String {
+    let mut temp = fields.iter().map(|x| csv_field(x)).collect::<Vec<String>>().join(",");
+    temp.push_str("\r\n");
+    temp
+}
+
+const ORDER_COLUMNS: [&str; 21] = ["order", "placed", "shipped", "status", "vendor", "currency", "subtotal", "discounts", "shipping", "tax", "tax_rate", "fees", "tip", "credits", "total", "refunded", "after_refunds", "products", "paid_with", "tags", "notes"];
+
+fn order_row(ledger: &Ledger, index: usize, order: &Order) -> Result<Vec<String>, Error> {
+    let money = |x: Money| x.to_number_in(order.currency);
+    let (_, discounts) = discount::savings(order)?;
+    let mut row = vec!(
+        index.to_string(),
+        order.date_placed.to_iso_string(),
+        order.date_shipped().map(|x| x.to_iso_string()).unwrap_or_default(),
+        order.status().name().to_string(),
+        match order.vendor {
+            Some(_) => vendor_name(order, &ledger.vendors).to_string(),
+            None => "".to_string()
+        },
+        order.currency.code().to_string(),
+        money(order.subtotal),
+        money(discounts)
+    );
+    match order.breakdown {
+        Some(x) => row.extend([
+            money(x.shipping),
+            money(x.tax),
+            x.tax_rate.map(|y| format!("{}.{:02}", y / 100, y % 100)).unwrap_or_default(),
+            money(x.fees),
+            money(x.tip),
+            money(x.credits)
+        ]),
+        None => row.extend(["", "", "", "", "", ""].map(|x| x.to_string()))
+    }
+    row.extend([
+        money(order.total),
+        money(refund::refunded(order)?),
+        money(order.net_total()),
+        order.products.iter().fold(0u64, |total, x| total.saturating_add(x.quantity)).to_string(),
+        order.payments.iter().map(|x| format!("{} {}", method_name(&ledger.payment_methods, x.method), money(x.amount))).collect::<Vec<String>>().join("; "),
+        tags_to_string(&order.tags),
+        order.notes.trim().to_string()
+    ]);
+    Ok(row)
+}
+
+const LINE_COLUMNS: [&str; 19] = ["order", "placed", "vendor", "currency", "path", "parent", "kind", "name", "desc", "category", "tags", "quantity", "unit_price", "paid", "base_price", "sticker_price", "actual_price", "discount_reason", "notes"];
+
+// Where a row of the lines shape is and what it inherits, shared by everything under one product
+struct Place<'a> {
+    front: &'a [String], // The order, placed, vendor and currency columns
+    path: String,
+    parent: String,
+    inherited: (&'a str, &'a [String])
+}
+
+// line is the line of the order product is, for top level products
+fn product_rows(order: &Order, product: &Product, line: Option<&OrderLine>, kind: &str, place: Place, rows: &mut Vec<Vec<String>>) {
+    let money = |x: Money| x.to_number_in(order.currency);
+    let mine = inherit(&product.category, &product.tags, place.inherited);
+    let mut row = place.front.to_vec();
+    row.extend([place.path.clone(), place.parent.clone(), kind.to_string(), product.name.trim().to_string(), product.desc.trim().to_string(), mine.0.to_string(), tags_to_string(mine.1)]);
+    match line {
+        Some(x) => row.extend([x.quantity.to_string(), money(x.unit_price), money(x.paid)]),
+        None => row.extend(["", "", ""].map(|x| x.to_string()))
+    }
+    row.extend([money(product.base_price), money(product.sticker_price), "".to_string()]);
+    match line {
+        Some(x) => row.extend([x.discount_reason.trim().to_string(), x.notes.trim().to_string()]),
+        None => row.extend(["", ""].map(|x| x.to_string()))
+    }
+    rows.push(row);
+    for (index, item) in product.items.iter().flatten().enumerate() {
+        let path = format!("{}.items[{}]", place.path, index);
+        product_rows(order, item, None, "sub-product", Place { front: place.front, path, parent: place.path.clone(), inherited: mine }, rows);
+    }
+    for (index, add_on) in product.add_ons.iter().flatten().enumerate() {
+        let path = format!("{}.add_ons[{}]", place.path, index);
+        add_on_rows(order, add_on, Place { front: place.front, path, parent: place.path.clone(), inherited: mine }, rows);
+    }
+}
+
+fn add_on_rows(order: &Order, add_on: &AddOn, place: Place, rows: &mut Vec<Vec<String>>) {
+    let money = |x: Money| x.to_number_in(order.currency);
+    let mine = inherit(&add_on.category, &add_on.tags, place.inherited);
+    let mut row = place.front.to_vec();
+    row.extend([place.path.clone(), place.parent.clone(), "add-on".to_string(), add_on.name.trim().to_string(), add_on.desc.trim().to_string(), mine.0.to_string(), tags_to_string(mine.1)]);
+    row.extend(["", "", "", ""].map(|x| x.to_string()));
+    row.extend([money(add_on.sticker_price), money(add_on.actual_price), "".to_string(), "".to_string()]);
+    rows.push(row);
+    if let Some(x) = &add_on.assoc_product {
+        let path = format!("{}.assoc_product", place.path);
+        product_rows(order, x, None, "associated product", Place { front: place.front, path, parent: place.path.clone(), inherited: mine }, rows);
+    }
+}
+
+fn line_rows(ledger: &Ledger, index: usize, order: &Order, rows: &mut Vec<Vec<String>>) {
+    let front = vec!(
+        index.to_string(),
+        order.date_placed.to_iso_string(),
+        match order.vendor {
+            Some(_) => vendor_name(order, &ledger.vendors).to_string(),
+            None => "".to_string()
+        },
+        order.currency.code().to_string()
+    );
+    for (line, x) in order.products.iter().enumerate() {
+        let place = Place {
+            front: &front,
+            path: format!("products[{}]", line),
+            parent: "".to_string(),
+            inherited: ("", &order.tags)
+        };
+        product_rows(order, &x.product, Some(x), "product", place, rows);
+    }
+}
+
+// purchase_tracker export [FILE] [OUT.csv] [--format csv] [--rows orders|lines] [--from DATE] [--to DATE] [--category CATEGORY] [--tag TAG]
+pub fn export_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["format", "rows", "from", "to", "category", "tag"], &[])?;
+    let (path, out) = match args.positional.as_slice() {
+        [x, y] => (Path::new(x), Path::new(y)),
+        _ => return Err(Error::usage("purchase_tracker export [FILE] [OUT.csv] [--format csv] [--rows orders|lines] [--from DATE] [--to DATE] [--category CATEGORY] [--tag TAG]"))
+    };
+    if let Some(x) = args.value("format")
+        && !x.trim().eq_ignore_ascii_case("csv") {
+        return Err(Error::parse("--format", x, "purchase_tracker can only export to \"csv\" for now."));
+    }
+    let lines = match args.value("rows").map(|x| x.trim().to_lowercase()).as_deref() {
+        None | Some("orders") => false,
+        Some("lines") => true,
+        Some(x) => return Err(Error::parse("--rows", x, "It can be \"orders\" (one row per order) or \"lines\" (one row per product, sub-product and add-on)."))
+    };
+    let range = DateRange::from_args(&args)?;
+    let filter = Filter::from_args(&args);
+    let ledger = storage::load(path)?;
+
+    let columns: &[&str] = match lines {
+        true => &LINE_COLUMNS,
+        false => &ORDER_COLUMNS
+    };
+    let mut rows: Vec<Vec<String>> = vec!();
+    let mut count = 0;
+    for (index, order) in ledger.orders.iter().enumerate().filter(|(_, x)| range.contains(x) && filter.matches(x)) {
+        match lines {
+            true => line_rows(&ledger, index, order, &mut rows),
+            false => rows.push(order_row(&ledger, index, order)?)
+        }
+        count += 1;
+    }
+    let mut contents = csv_row(&columns.iter().map(|x| x.to_string()).collect::<Vec<String>>());
+    for row in &rows {
+        contents.push_str(&csv_row(row));
+    }
+
+    let mut file = create_new(out)?;
+    if let Err(x) = file.write_all(contents.as_bytes()).and_then(|_| file.sync_all()) {
+        return Err(discard_new(out, Error::io("writing", out, x)));
+    }
+    println!("Exported {} rows from {} orders{}{} to {}.", rows.len(), count, range.describe(), filter.describe(), out.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::USD;
+    use crate::{Breakdown, Date};
+
+    #[test]
+    fn plain_fields_are_left_alone() {
+        assert_eq!(csv_field("Economy Rapier"), "Economy Rapier");
+        assert_eq!(csv_field("12.05"), "12.05");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn fields_that_would_break_the_row_are_quoted() {
+        assert_eq!(csv_field("A, B and more"), "\"A, B and more\"");
+        assert_eq!(csv_field("the \"good\" one"), "\"the \"\"good\"\" one\"");
+        assert_eq!(csv_field("\""), "\"\"\"\"");
+        assert_eq!(csv_field("first\nsecond"), "\"first\nsecond\"");
+        assert_eq!(csv_field("first\r\nsecond"), "\"first\r\nsecond\"");
+        assert_eq!(csv_field(" padded "), "\" padded \"");
+        assert_eq!(csv_field("trailing "), "\"trailing \"");
+    }
+
+    #[test]
+    fn rows_end_in_crlf() {
+        let row = csv_row(&["a".to_string(), "".to_string(), "b,c".to_string()]);
+        assert_eq!(row, "a,,\"b,c\"\r\n");
+    }
+
+    fn product(name: &str, items: Option<Vec<Product>>, add_ons: Option<Vec<AddOn>>) -> Product {
+        Product {
+            name: name.to_string(),
+            desc: "".to_string(),
+            base_price: Money::from_minor(1000),
+            sticker_price: Money::from_minor(1200),
+            category: "".to_string(),
+            tags: vec!(),
+            items,
+            add_ons
+        }
+    }
+
+    fn order(breakdown: Option<Breakdown>) -> Order {
+        let add_on = AddOn {
+            name: "Grip".to_string(),
+            desc: "".to_string(),
+            sticker_price: Money::from_minor(500),
+            actual_price: Money::from_minor(300),
+            category: "".to_string(),
+            tags: vec!(),
+            assoc_product: Some(product("Wrap", None, None))
+        };
+        Order {
+            date_placed: Date::new(2026, 1, 2).unwrap(),
+            vendor: None,
+            status_history: vec!(),
+            currency: USD,
+            subtotal: Money::from_minor(1200),
+            breakdown,
+            total: Money::from_minor(1200),
+            products: vec!(OrderLine {
+                product: product("Kit", Some(vec!(product("Blade", None, None))), Some(vec!(add_on))),
+                quantity: 1,
+                unit_price: Money::from_minor(1200),
+                paid: Money::from_minor(1200),
+                discount_reason: "".to_string(),
+                notes: "".to_string()
+            }),
+            discounts: vec!(),
+            refunds: vec!(),
+            payments: vec!(),
+            tags: vec!(),
+            notes: "".to_string()
+        }
+    }
+
+    #[test]
+    fn every_line_row_has_every_column() {
+        let mut rows: Vec<Vec<String>> = vec!();
+        line_rows(&Ledger::new(), 0, &order(None), &mut rows);
+        // The product, its sub-product, its add-on and the add-on's associated product
+        assert_eq!(rows.len(), 4);
+        for row in rows {
+            assert_eq!(row.len(), LINE_COLUMNS.len(), "{:?}", row);
+        }
+    }
+
+    #[test]
+    fn every_order_row_has_every_column() {
+        let breakdown = Breakdown {
+            shipping: Money::default(),
+            tax: Money::default(),
+            tax_rate: Some(825),
+            fees: Money::default(),
+            tip: Money::default(),
+            credits: Money::default()
+        };
+        for x in [None, Some(breakdown)] {
+            assert_eq!(order_row(&Ledger::new(), 0, &order(x)).unwrap().len(), ORDER_COLUMNS.len());
+        }
+    }
+}
@@ -0,0 +1,487 @@
+use bincode::config;
+use bincode::error::{DecodeError, EncodeError};
+
+use crate::Ledger;
+
+mod v1;
+mod v2;
+mod v3;
+mod v4;
+mod v5;
+mod v6;
+mod v7;
+mod v8;
+mod v9;
+mod v10;
+mod v11;
+
+/*
+ * Layout of a file written by purchase_tracker:
+ *
+ *   8 bytes   MAGIC
+ *   4 bytes   format version, little endian u32
+ *   the rest  the bincode encoded payload for that version
+ *
+ * Files written before the header existed are nothing but a bincode encoded Vec<v1::Order>. Those
+ * don't start with MAGIC, so they're read as version 0.
+ *
+ * Whenever Ledger, Order, Product, AddOn (or anything else that gets saved) changes shape, bump
+ * CURRENT_VERSION, freeze a copy of the old structs in format/v{old version}.rs so that old
+ * payloads can still be decoded, add a variant to Payload for the new version and teach
+ * Payload::upgrade how to turn the previous version into the new one. Old files then get walked up
+ * one version at a time until they reach CURRENT_VERSION, and get saved in the current format the
+ * next time they're written.
+ *
+ * Nothing under format/ uses the live types, because changing one of those would quietly change
+ * how every old version is read. Each saved type is frozen once, in the version that first saved
+ * it that way, and later versions use that copy for as long as it stays the same. Money is saved
+ * as its bare count of minor units, so the frozen copies hold a u64 for it. Only the newest
+ * version's upgrade turns the frozen copies into the live types.
+ */
+pub const MAGIC: [u8; 8] = *b"PTLEDGER";
+pub const CURRENT_VERSION: u32 = 12;
+const HEADER_LEN: usize = MAGIC.len() + 4;
+
+#[derive(Debug)]
+pub enum ReadError {
+    UnsupportedVersion(u32), // The file was written by a newer purchase_tracker
+    Decode(DecodeError)
+}
+
+impl ToString for ReadError {
+    fn to_string(&self) -> String {
+        match self {
+            ReadError::UnsupportedVersion(x) => format!("the file uses format version {}, but this build of purchase_tracker only understands versions up to {}. Try a newer purchase_tracker", x, CURRENT_VERSION),
+            ReadError::Decode(x) => x.to_string()
+        }
+    }
+}
+
+// The payload of a file, as it was laid out in the version it was written in
+enum Payload {
+    V0(Vec<v1::Order>), // Headerless files from before versioning
+    V1(Vec<v1::Order>),
+    V2(Vec<v2::Order>),
+    V3(v3::Ledger),
+    V4(v4::Ledger),
+    V5(v5::Ledger),
+    V6(v6::Ledger),
+    V7(v7::Ledger),
+    V8(v8::Ledger),
+    V9(v9::Ledger),
+    V10(v10::Ledger),
+    V11(v11::Ledger),
+    V12(Ledger)
+}
+
+impl Payload {
+    fn version(&self) -> u32 {
+        match self {
+            Payload::V0(_) => 0,
+            Payload::V1(_) => 1,
+            Payload::V2(_) => 2,
+            Payload::V3(_) => 3,
+            Payload::V4(_) => 4,
+            Payload::V5(_) => 5,
+            Payload::V6(_) => 6,
+            Payload::V7(_) => 7,
+            Payload::V8(_) => 8,
+            Payload::V9(_) => 9,
+            Payload::V10(_) => 10,
+            Payload::V11(_) => 11,
+            Payload::V12(_) => 12
+        }
+    }
+
+    // Moves the payload exactly one version forward
+    fn upgrade(self) -> Payload {
+        match self {
+            // The header was the only thing that changed, the orders themselves look the same
+            Payload::V0(x) => Payload::V1(x),
+            Payload::V1(x) => Payload::V2(x.into_iter().map(v1::Order::upgrade).collect()),
+            Payload::V2(x) => Payload::V3(v2::upgrade(x)),
+            Payload::V3(x) => Payload::V4(x.upgrade()),
+            Payload::V4(x) => Payload::V5(x.upgrade()),
+            Payload::V5(x) => Payload::V6(x.upgrade()),
+            Payload::V6(x) => Payload::V7(x.upgrade()),
+            Payload::V7(x) => Payload::V8(x.upgrade()),
+            Payload::V8(x) => Payload::V9(x.upgrade()),
+            Payload::V9(x) => Payload::V10(x.upgrade()),
+            Payload::V10(x) => Payload::V11(x.upgrade()),
+            Payload::V11(x) => Payload::V12(x.upgrade()),
+            Payload::V12(_) => unreachable!("Tried to upgrade a payload that's already at the current version")
+        }
+    }
+}
+
+// Returns the version a file was written in, alongside where its payload starts
+fn read_header(bytes: &[u8]) -> (u32, usize) {
+    if bytes.len() < HEADER_LEN || bytes[..MAGIC.len()] != MAGIC {
+        return (0, 0);
+    }
+    let mut version = [0u8; 4];
+    version.copy_from_slice(&bytes[MAGIC.len()..HEADER_LEN]);
+    (u32::from_le_bytes(version), HEADER_LEN)
+}
+
+fn decode_payload(version: u32, bytes: &[u8]) -> Result<Payload, ReadError> {
+    let config = config::standard();
+    let payload = match version {
+        0 => Payload::V0(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        1 => Payload::V1(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        2 => Payload::V2(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        3 => Payload::V3(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        4 => Payload::V4(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        5 => Payload::V5(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        6 => Payload::V6(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        7 => Payload::V7(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        8 => Payload::V8(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        9 => Payload::V9(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        10 => Payload::V10(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        11 => Payload::V11(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        12 => Payload::V12(bincode::decode_from_slice(bytes, config).map_err(ReadError::Decode)?.0),
+        x => return Err(ReadError::UnsupportedVersion(x))
+    };
+    Ok(payload)
+}
+
+// Decodes the contents of a file in any known version into the current structs.
+// Also returns the version the file was originally written in.
+pub fn decode(bytes: &[u8]) -> Result<(Ledger, u32), ReadError> {
+    let (version, start) = read_header(bytes);
+    let mut payload = decode_payload(version, &bytes[start..])?;
+    loop {
+        match payload {
+            Payload::V12(x) => return Ok((x, version)),
+            older => {
+                debug_assert!(older.version() < CURRENT_VERSION);
+                payload = older.upgrade();
+            }
+        }
+    }
+}
+
+// Encodes a ledger in the current version, header included
+pub fn encode(ledger: &Ledger) -> Result<Vec<u8>, EncodeError> {
+    let mut bytes = Vec::from(MAGIC);
+    bytes.extend_from_slice(&CURRENT_VERSION.to_le_bytes());
+    bytes.extend(bincode::encode_to_vec(ledger, config::standard())?);
+    Ok(bytes)
+}
+
+/*
+ * tests/fixtures/v{N}.ptl is a real file written by the purchase_tracker that saved format version
+ * N, with something in it for everything that version added. Each one was written by opening the
+ * fixture before it with that build and adding to it, so later fixtures also hold everything the
+ * earlier ones did, saved in the later version. Whenever CURRENT_VERSION gets bumped, write the
+ * fixture for the version that's now old with the build from just before the bump, add it to
+ * FIXTURES and its variant to encode_payload, and check what it added in a test of its own below.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog;
+    use crate::discount::{Amount, Source};
+    use crate::money::{Currency, Money, USD};
+    use crate::payment::Kind;
+    use crate::rates;
+    use crate::{Date, Status};
+
+    const FIXTURES: [&[u8]; CURRENT_VERSION as usize] = [
+        include_bytes!("../tests/fixtures/v0.ptl"),
+        include_bytes!("../tests/fixtures/v1.ptl"),
+        include_bytes!("../tests/fixtures/v2.ptl"),
+        include_bytes!("../tests/fixtures/v3.ptl"),
+        include_bytes!("../tests/fixtures/v4.ptl"),
+        include_bytes!("../tests/fixtures/v5.ptl"),
+        include_bytes!("../tests/fixtures/v6.ptl"),
+        include_bytes!("../tests/fixtures/v7.ptl"),
+        include_bytes!("../tests/fixtures/v8.ptl"),
+        include_bytes!("../tests/fixtures/v9.ptl"),
+        include_bytes!("../tests/fixtures/v10.ptl"),
+        include_bytes!("../tests/fixtures/v11.ptl")
+    ];
+
+    // Writes a payload the way purchase_tracker did back when it was the current version
+    fn encode_payload(payload: &Payload) -> Vec<u8> {
+        let config = config::standard();
+        let body = match payload {
+            Payload::V0(x) => bincode::encode_to_vec(x, config),
+            Payload::V1(x) => bincode::encode_to_vec(x, config),
+            Payload::V2(x) => bincode::encode_to_vec(x, config),
+            Payload::V3(x) => bincode::encode_to_vec(x, config),
+            Payload::V4(x) => bincode::encode_to_vec(x, config),
+            Payload::V5(x) => bincode::encode_to_vec(x, config),
+            Payload::V6(x) => bincode::encode_to_vec(x, config),
+            Payload::V7(x) => bincode::encode_to_vec(x, config),
+            Payload::V8(x) => bincode::encode_to_vec(x, config),
+            Payload::V9(x) => bincode::encode_to_vec(x, config),
+            Payload::V10(x) => bincode::encode_to_vec(x, config),
+            Payload::V11(x) => bincode::encode_to_vec(x, config),
+            Payload::V12(x) => bincode::encode_to_vec(x, config)
+        }.unwrap();
+        match payload.version() {
+            0 => body,
+            x => {
+                let mut bytes = Vec::from(MAGIC);
+                bytes.extend_from_slice(&x.to_le_bytes());
+                bytes.extend(body);
+                bytes
+            }
+        }
+    }
+
+    fn read(version: usize) -> Ledger {
+        let (ledger, read_as) = decode(FIXTURES[version]).unwrap();
+        assert_eq!(read_as, version as u32);
+        ledger
+    }
+
+    fn date(year: u64, month: u8, day: u8) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    fn currency(code: &str) -> Currency {
+        Currency::parse(code, "currency").unwrap()
+    }
+
+    #[test]
+    fn every_fixture_decodes_through_every_later_version() {
+        for (version, bytes) in FIXTURES.iter().enumerate() {
+            let expected = encode(&read(version)).unwrap();
+            let (_, start) = read_header(bytes);
+            let mut payload = decode_payload(version as u32, &bytes[start..]).unwrap();
+            // The frozen structs still lay a file out exactly the way the old build did
+            assert_eq!(encode_payload(&payload), *bytes, "version {} was read differently", version);
+            loop {
+                let (ledger, read_as) = decode(&encode_payload(&payload)).unwrap();
+                assert_eq!(read_as, payload.version());
+                assert_eq!(encode(&ledger).unwrap(), expected, "version {} upgraded differently through version {}", version, read_as);
+                if read_as == CURRENT_VERSION {
+                    break;
+                }
+                payload = payload.upgrade();
+            }
+        }
+    }
+
+    #[test]
+    fn version_0_file_decodes() {
+        let ledger = read(0);
+        assert_eq!(ledger.orders.len(), 1);
+        let order = &ledger.orders[0];
+        assert_eq!(order.date_placed, date(2025, 10, 25));
+        assert_eq!(order.total, Money::from_minor(38900));
+        assert_eq!(order.subtotal, Money::from_minor(35500));
+        // Every order saved before version 2 had been shipped
+        assert_eq!(order.status_history.len(), 1);
+        assert_eq!(order.status_history[0].status, Status::Shipped);
+        assert_eq!(order.status_history[0].date, Some(date(2025, 10, 28)));
+        assert_eq!(order.currency, USD);
+        assert_eq!(order.products[0].quantity, 1);
+        // Names used to be saved with the newline they were typed with
+        assert_eq!(order.products[0].product.name, "Economy Rapier\n");
+        assert_eq!(ledger.home_currency, USD);
+    }
+
+    #[test]
+    fn version_1_file_decodes() {
+        let ledger = read(1);
+        assert_eq!(ledger.orders.len(), 1);
+        let order = &ledger.orders[0];
+        assert_eq!(order.date_placed, date(2025, 10, 1));
+        assert_eq!(order.status_history[0].status, Status::Shipped);
+        assert_eq!(order.status_history[0].date, Some(date(2025, 10, 3)));
+        assert_eq!(order.subtotal, Money::from_minor(38000));
+        assert_eq!(order.total, Money::from_minor(40850));
+        assert!(order.breakdown.is_none());
+        assert_eq!(order.vendor, None);
+        assert_eq!(order.notes, "Birthday gift");
+        // Lines used to be (product, what was paid for it), one of each
+        assert_eq!(order.products.len(), 2);
+        let line = &order.products[0];
+        assert_eq!(line.quantity, 1);
+        assert_eq!(line.unit_price, Money::from_minor(34000));
+        assert_eq!(line.paid, Money::from_minor(34000));
+        assert_eq!(line.discount_reason, "");
+        assert_eq!(line.product.name, "Economy Rapier");
+        assert_eq!(line.product.category, "");
+        assert!(line.product.tags.is_empty());
+        assert_eq!(line.product.items.as_ref().map(|x| x[0].name.as_str()), Some("Practice Blade"));
+        let add_ons = line.product.add_ons.as_ref().unwrap();
+        assert_eq!(add_ons[0].name, "Leather Grip");
+        assert_eq!(add_ons[0].sticker_price, Money::from_minor(500));
+        assert_eq!(add_ons[0].actual_price, Money::from_minor(300));
+        assert_eq!(add_ons[0].assoc_product.as_ref().map(|x| x.name.as_str()), Some("Grip Wrap"));
+        assert_eq!(order.products[1].paid, Money::from_minor(4000));
+    }
+
+    #[test]
+    fn version_2_file_decodes() {
+        let ledger = read(2);
+        assert_eq!(ledger.orders.len(), 2);
+        let delivered = &ledger.orders[0].status_history;
+        assert_eq!(delivered.len(), 2);
+        assert_eq!(delivered[1].status, Status::Delivered);
+        assert_eq!(delivered[1].date, Some(date(2025, 10, 6)));
+        let processing = &ledger.orders[1].status_history;
+        assert_eq!(processing.last().map(|x| (x.status, x.date)), Some((Status::Processing, Some(date(2025, 10, 21)))));
+    }
+
+    #[test]
+    fn version_3_file_decodes() {
+        let ledger = read(3);
+        assert_eq!(ledger.orders.len(), 3);
+        assert_eq!(ledger.orders[0].currency, USD);
+        assert_eq!(ledger.orders[2].currency, currency("EUR"));
+        assert_eq!(ledger.home_currency, currency("GBP"));
+        assert_eq!(ledger.rates.len(), 2);
+        let converted = rates::convert(&ledger, Money::from_minor(10000), currency("EUR"), USD, date(2025, 10, 2));
+        assert_eq!(converted.unwrap(), Money::from_minor(10834));
+        let converted = rates::convert(&ledger, Money::from_minor(10000), currency("JPY"), USD, date(2025, 10, 15));
+        assert_eq!(converted.unwrap(), Money::from_minor(6700));
+    }
+
+    #[test]
+    fn version_4_file_decodes() {
+        let ledger = read(4);
+        assert!(ledger.orders[2].breakdown.is_none());
+        let breakdown = ledger.orders[3].breakdown.as_ref().unwrap();
+        assert_eq!(breakdown.shipping, Money::from_minor(100000));
+        assert_eq!(breakdown.tax, Money::from_minor(313500));
+        assert_eq!(breakdown.tax_rate, Some(825));
+        assert_eq!(breakdown.fees, Money::from_minor(10000));
+    }
+
+    #[test]
+    fn version_5_file_decodes() {
+        let ledger = read(5);
+        assert_eq!(ledger.vendors.len(), 2);
+        let vendor = &ledger.vendors[0];
+        assert_eq!(vendor.name, "Darkwood Armory");
+        assert_eq!(vendor.website, "https://darkwood.example");
+        assert_eq!(vendor.contact, "sales@darkwood.example");
+        assert_eq!(vendor.notes, "Ships slowly");
+        assert_eq!(ledger.vendors[1].name, "Fencing Post");
+        assert_eq!(ledger.orders[3].vendor, None);
+        assert_eq!(ledger.orders[4].vendor, Some(0));
+    }
+
+    #[test]
+    fn version_6_file_decodes() {
+        let ledger = read(6);
+        assert_eq!(ledger.catalog.len(), 2);
+        let (mask, in_currency) = catalog::lookup(&ledger.catalog, "Mask", "name").unwrap();
+        assert_eq!(mask.desc, "FIE mask\n");
+        assert_eq!(in_currency, currency("EUR"));
+        assert_eq!(mask.add_ons.as_ref().map(|x| x[0].name.as_str()), Some("Bib\n"));
+        // Retired entries can't be used until they're unretired
+        assert!(catalog::lookup(&ledger.catalog, "Glove", "name").is_err());
+    }
+
+    #[test]
+    fn version_7_file_decodes() {
+        let ledger = read(7);
+        let order = &ledger.orders[5];
+        assert_eq!(order.currency, currency("EUR"));
+        assert_eq!(order.vendor, Some(1));
+        let line = &order.products[0];
+        assert_eq!(line.product.name, "Blade");
+        assert_eq!(line.quantity, 3);
+        assert_eq!(line.unit_price, Money::from_minor(1800));
+        assert_eq!(line.paid, Money::from_minor(5000));
+        // Lines from before OrderLine didn't have a discount reason or notes
+        assert_eq!(line.discount_reason, "");
+        assert_eq!(line.notes, "");
+        assert_eq!(order.products[1].product.name, "Mask\n");
+        assert_eq!(order.products[1].paid, Money::from_minor(3800));
+    }
+
+    #[test]
+    fn version_8_file_decodes() {
+        let ledger = read(8);
+        let line = &ledger.orders[6].products[0];
+        assert_eq!(line.product.name, "Jacket");
+        assert_eq!(line.product.sticker_price, Money::from_minor(8000));
+        assert_eq!(line.paid, Money::from_minor(6000));
+        assert_eq!(line.discount_reason, "clearance");
+        assert_eq!(line.notes, "Size 48");
+        assert!(ledger.orders[6].discounts.is_empty());
+    }
+
+    #[test]
+    fn version_9_file_decodes() {
+        let ledger = read(9);
+        let discounts = &ledger.orders[7].discounts;
+        assert_eq!(discounts.len(), 2);
+        assert!(matches!(discounts[0].source, Source::Promo));
+        assert_eq!(discounts[0].code, "SAVE10");
+        assert!(matches!(discounts[0].amount, Amount::Percent(1000)));
+        assert_eq!(discounts[0].lines, Some(vec!(0)));
+        assert!(matches!(discounts[1].source, Source::Loyalty));
+        assert!(matches!(discounts[1].amount, Amount::Fixed(x) if x == Money::from_minor(500)));
+        assert_eq!(discounts[1].lines, None);
+        assert!(ledger.orders[7].refunds.is_empty());
+    }
+
+    #[test]
+    fn version_10_file_decodes() {
+        let ledger = read(10);
+        let refund = &ledger.orders[7].refunds[0];
+        assert_eq!(refund.date, date(2025, 12, 20));
+        assert_eq!(refund.line, Some(1));
+        assert_eq!(refund.returned, 1);
+        assert_eq!(refund.amount, Money::from_minor(800));
+        assert_eq!(refund.restocking_fee, Money::from_minor(100));
+        assert_eq!(refund.reason, "too small");
+        let refund = &ledger.orders[3].refunds[0];
+        assert_eq!(refund.line, None);
+        assert_eq!(refund.amount, Money::from_minor(1000));
+        assert_eq!(refund.reason, "late delivery");
+        assert!(ledger.payment_methods.is_empty());
+    }
+
+    #[test]
+    fn version_11_file_decodes() {
+        let ledger = read(11);
+        assert_eq!(ledger.orders.len(), 10);
+        let methods = &ledger.payment_methods;
+        assert_eq!(methods.len(), 2);
+        assert_eq!(methods[0].name, "Visa");
+        assert_eq!(methods[0].last_four, "1234");
+        assert!(matches!(methods[0].kind, Kind::Credit));
+        assert!(matches!(methods[1].kind, Kind::GiftCard));
+        let paid = |x: usize| ledger.orders[x].payments.iter().map(|x| (x.method, x.amount.minor())).collect::<Vec<_>>();
+        assert_eq!(paid(8), vec!((0, 5000)));
+        assert_eq!(paid(9), vec!((0, 3000), (1, 2000)));
+        // Nothing saved before version 12 had a category or tags
+        assert!(ledger.orders.iter().all(|x| x.tags.is_empty()));
+    }
+
+    #[test]
+    fn encode_round_trips() {
+        let ledger = read(FIXTURES.len() - 1);
+        let bytes = encode(&ledger).unwrap();
+        assert_eq!(&bytes[..MAGIC.len()], &MAGIC);
+        let (again, version) = decode(&bytes).unwrap();
+        assert_eq!(version, CURRENT_VERSION);
+        assert_eq!(encode(&again).unwrap(), bytes);
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let mut bytes = encode(&Ledger::new()).unwrap();
+        bytes[MAGIC.len()..HEADER_LEN].copy_from_slice(&(CURRENT_VERSION + 1).to_le_bytes());
+        assert!(matches!(decode(&bytes), Err(ReadError::UnsupportedVersion(x)) if x == CURRENT_VERSION + 1));
+    }
+
+    #[test]
+    fn bad_magic_is_rejected() {
+        // Without MAGIC a file is read as version 0, which this isn't
+        let mut bytes = encode(&Ledger::new()).unwrap();
+        bytes[0] = b'X';
+        assert!(matches!(decode(&bytes), Err(ReadError::Decode(_))));
+        assert!(matches!(decode(b"not a ledger"), Err(ReadError::Decode(_))));
+    }
+
+}
@@ -0,0 +1,61 @@
+use bincode::{Decode, Encode};
+
+use super::v2::{self, Status, StatusChange};
+
+// Date as it was saved in every format version up to 11
+#[derive(Encode, Decode, Debug, Clone, Copy)]
+pub struct Date {
+    pub(super) month: u8,
+    pub(super) day: u8,
+    pub(super) year: u64
+}
+
+// Order as it was saved in format versions 0 and 1, back when every order had a shipping date
+#[derive(Encode, Decode, Debug)]
+pub struct Order {
+    date_placed: Date,
+    date_shipped: Date,
+    subtotal: u64,
+    total: u64,
+    products: Vec<(Product, u64)>,
+    notes: String
+}
+
+// Product and AddOn as they were saved in every format version up to 11, before products and
+// add-ons had a category and tags
+#[derive(Encode, Decode, Debug)]
+pub struct Product {
+    pub(super) name: String,
+    pub(super) desc: String,
+    pub(super) base_price: u64,
+    pub(super) sticker_price: u64,
+    pub(super) items: Option<Vec<Product>>,
+    pub(super) add_ons: Option<Vec<AddOn>>
+}
+
+#[derive(Encode, Decode, Debug)]
+pub struct AddOn {
+    pub(super) name: String,
+    pub(super) desc: String,
+    pub(super) sticker_price: u64,
+    pub(super) actual_price: u64,
+    pub(super) assoc_product: Option<Product>
+}
+
+impl Order {
+    // Version 2 replaced date_shipped with the status history. Every order saved before then had
+    // been shipped.
+    pub fn upgrade(self) -> v2::Order {
+        v2::Order {
+            date_placed: self.date_placed,
+            status_history: vec!(StatusChange {
+                status: Status::Shipped,
+                date: Some(self.date_shipped)
+            }),
+            subtotal: self.subtotal,
+            total: self.total,
+            products: self.products,
+            notes: self.notes
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use bincode::{Decode, Encode};
+
+use super::v1::Date;
+use super::v2::StatusChange;
+use super::v3::{Currency, Rate};
+use super::v4::Breakdown;
+use super::v5::Vendor;
+use super::v6::CatalogEntry;
+use super::v8::OrderLine;
+use super::v9::Discount;
+use super::v11;
+
+// Ledger as it was saved in format version 10
+#[derive(Encode, Decode, Debug)]
+pub struct Ledger {
+    pub(super) orders: Vec<Order>,
+    pub(super) vendors: Vec<Vendor>,
+    pub(super) catalog: Vec<CatalogEntry>,
+    pub(super) home_currency: Currency,
+    pub(super) rates: Vec<Rate>
+}
+
+// Order as it was saved in format version 10, before payment methods were recorded
+#[derive(Encode, Decode, Debug)]
+pub struct Order {
+    pub(super) date_placed: Date,
+    pub(super) vendor: Option<usize>,
+    pub(super) status_history: Vec<StatusChange>,
+    pub(super) currency: Currency,
+    pub(super) subtotal: u64,
+    pub(super) breakdown: Option<Breakdown>,
+    pub(super) total: u64,
+    pub(super) products: Vec<OrderLine>,
+    pub(super) discounts: Vec<Discount>,
+    pub(super) refunds: Vec<Refund>,
+    pub(super) notes: String
+}
+
+// Refund as it was saved in every format version from 10 up to 11
+#[derive(Encode, Decode, Debug)]
+pub struct Refund {
+    pub(super) date: Date,
+    pub(super) line: Option<usize>,
+    pub(super) returned: u64,
+    pub(super) amount: u64,
+    pub(super) restocking_fee: u64,
+    pub(super) reason: String
+}
+
+impl Ledger {
+    pub fn upgrade(self) -> v11::Ledger {
+        v11::Ledger {
+            orders: self.orders.into_iter().map(|x| v11::Order {
+                date_placed: x.date_placed,
+                vendor: x.vendor,
+                status_history: x.status_history,
+                currency: x.currency,
+                subtotal: x.subtotal,
+                breakdown: x.breakdown,
+                total: x.total,
+                products: x.products,
+                discounts: x.discounts,
+                refunds: x.refunds,
+                payments: vec!(),
+                notes: x.notes
+            }).collect(),
+            vendors: self.vendors,
+            catalog: self.catalog,
+            payment_methods: vec!(),
+            home_currency: self.home_currency,
+            rates: self.rates
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use bincode::{Decode, Encode};
+
+use crate::catalog;
+use crate::discount;
+use crate::money::{self, Money};
+use crate::payment;
+use crate::rates;
+use crate::refund;
+use crate::vendor;
+
+use super::v1::{AddOn, Date, Product};
+use super::v2::{Status, StatusChange};
+use super::v3::{Currency, Rate};
+use super::v4::Breakdown;
+use super::v5::Vendor;
+use super::v6::CatalogEntry;
+use super::v8::OrderLine;
+use super::v9::{Amount, Discount, Source};
+use super::v10::Refund;
+
+// Ledger as it was saved in format version 11
+#[derive(Encode, Decode, Debug)]
+pub struct Ledger {
+    pub(super) orders: Vec<Order>,
+    pub(super) vendors: Vec<Vendor>,
+    pub(super) catalog: Vec<CatalogEntry>,
+    pub(super) payment_methods: Vec<PaymentMethod>,
+    pub(super) home_currency: Currency,
+    pub(super) rates: Vec<Rate>
+}
+
+// Order as it was saved in format version 11, before orders had tags
+#[derive(Encode, Decode, Debug)]
+pub struct Order {
+    pub(super) date_placed: Date,
+    pub(super) vendor: Option<usize>,
+    pub(super) status_history: Vec<StatusChange>,
+    pub(super) currency: Currency,
+    pub(super) subtotal: u64,
+    pub(super) breakdown: Option<Breakdown>,
+    pub(super) total: u64,
+    pub(super) products: Vec<OrderLine>,
+    pub(super) discounts: Vec<Discount>,
+    pub(super) refunds: Vec<Refund>,
+    pub(super) payments: Vec<Payment>,
+    pub(super) notes: String
+}
+
+// PaymentMethod, Kind and Payment as they were saved in format version 11
+#[derive(Encode, Decode, Debug)]
+pub struct PaymentMethod {
+    pub(super) name: String,
+    pub(super) last_four: String,
+    pub(super) kind: Kind
+}
+
+#[derive(Encode, Decode, Debug, Clone, Copy)]
+pub enum Kind {
+    Credit,
+    Debit,
+    Bank,
+    GiftCard,
+    Cash,
+    Other
+}
+
+#[derive(Encode, Decode, Debug)]
+pub struct Payment {
+    pub(super) method: usize,
+    pub(super) amount: u64
+}
+
+// Version 12 gave products, add-ons and orders a category and tags. Everything that was saved
+// before they existed simply doesn't have any, and everything else is saved the same way today, so
+// it's copied into the current structs as it is.
+impl Product {
+    fn upgrade(self) -> crate::Product {
+        crate::Product {
+            name: self.name,
+            desc: self.desc,
+            base_price: Money::from_minor(self.base_price),
+            sticker_price: Money::from_minor(self.sticker_price),
+            category: "".to_string(),
+            tags: vec!(),
+            items: self.items.map(|x| x.into_iter().map(Product::upgrade).collect()),
+            add_ons: self.add_ons.map(|x| x.into_iter().map(AddOn::upgrade).collect())
+        }
+    }
+}
+
+impl AddOn {
+    fn upgrade(self) -> crate::AddOn {
+        crate::AddOn {
+            name: self.name,
+            desc: self.desc,
+            sticker_price: Money::from_minor(self.sticker_price),
+            actual_price: Money::from_minor(self.actual_price),
+            category: "".to_string(),
+            tags: vec!(),
+            assoc_product: self.assoc_product.map(Product::upgrade)
+        }
+    }
+}
+
+impl Date {
+    fn upgrade(self) -> crate::Date {
+        crate::Date {
+            month: self.month,
+            day: self.day,
+            year: self.year
+        }
+    }
+}
+
+impl Currency {
+    fn upgrade(self) -> money::Currency {
+        money::Currency::upgraded(self.code)
+    }
+}
+
+impl StatusChange {
+    fn upgrade(self) -> crate::StatusChange {
+        crate::StatusChange {
+            status: match self.status {
+                Status::Placed => crate::Status::Placed,
+                Status::Processing => crate::Status::Processing,
+                Status::Shipped => crate::Status::Shipped,
+                Status::Delivered => crate::Status::Delivered,
+                Status::Cancelled => crate::Status::Cancelled,
+                Status::Returned => crate::Status::Returned
+            },
+            date: self.date.map(Date::upgrade)
+        }
+    }
+}
+
+impl Breakdown {
+    fn upgrade(self) -> crate::Breakdown {
+        crate::Breakdown {
+            shipping: Money::from_minor(self.shipping),
+            tax: Money::from_minor(self.tax),
+            tax_rate: self.tax_rate,
+            fees: Money::from_minor(self.fees),
+            tip: Money::from_minor(self.tip),
+            credits: Money::from_minor(self.credits)
+        }
+    }
+}
+
+impl OrderLine {
+    fn upgrade(self) -> crate::OrderLine {
+        crate::OrderLine {
+            product: self.product.upgrade(),
+            quantity: self.quantity,
+            unit_price: Money::from_minor(self.unit_price),
+            paid: Money::from_minor(self.paid),
+            discount_reason: self.discount_reason,
+            notes: self.notes
+        }
+    }
+}
+
+impl Discount {
+    fn upgrade(self) -> discount::Discount {
+        discount::Discount {
+            source: match self.source {
+                Source::Promo => discount::Source::Promo,
+                Source::Loyalty => discount::Source::Loyalty,
+                Source::PriceMatch => discount::Source::PriceMatch,
+                Source::Other => discount::Source::Other
+            },
+            code: self.code,
+            amount: match self.amount {
+                Amount::Percent(x) => discount::Amount::Percent(x),
+                Amount::Fixed(x) => discount::Amount::Fixed(Money::from_minor(x))
+            },
+            lines: self.lines
+        }
+    }
+}
+
+impl Refund {
+    fn upgrade(self) -> refund::Refund {
+        refund::Refund {
+            date: self.date.upgrade(),
+            line: self.line,
+            returned: self.returned,
+            amount: Money::from_minor(self.amount),
+            restocking_fee: Money::from_minor(self.restocking_fee),
+            reason: self.reason
+        }
+    }
+}
+
+impl Payment {
+    fn upgrade(self) -> payment::Payment {
+        payment::Payment {
+            method: self.method,
+            amount: Money::from_minor(self.amount)
+        }
+    }
+}
+
+impl PaymentMethod {
+    fn upgrade(self) -> payment::PaymentMethod {
+        payment::PaymentMethod {
+            name: self.name,
+            last_four: self.last_four,
+            kind: match self.kind {
+                Kind::Credit => payment::Kind::Credit,
+                Kind::Debit => payment::Kind::Debit,
+                Kind::Bank => payment::Kind::Bank,
+                Kind::GiftCard => payment::Kind::GiftCard,
+                Kind::Cash => payment::Kind::Cash,
+                Kind::Other => payment::Kind::Other
+            }
+        }
+    }
+}
+
+impl Vendor {
+    fn upgrade(self) -> vendor::Vendor {
+        vendor::Vendor {
+            name: self.name,
+            website: self.website,
+            contact: self.contact,
+            notes: self.notes
+        }
+    }
+}
+
+impl Rate {
+    fn upgrade(self) -> rates::Rate {
+        rates::Rate::upgraded(self.date.upgrade(), self.from.upgrade(), self.to.upgrade(), self.micros)
+    }
+}
+
+impl CatalogEntry {
+    fn upgrade(self) -> catalog::CatalogEntry {
+        catalog::CatalogEntry::upgraded(self.product.upgrade(), self.currency.upgrade(), self.retired)
+    }
+}
+
+impl Order {
+    fn upgrade(self) -> crate::Order {
+        crate::Order {
+            date_placed: self.date_placed.upgrade(),
+            vendor: self.vendor,
+            status_history: self.status_history.into_iter().map(StatusChange::upgrade).collect(),
+            currency: self.currency.upgrade(),
+            subtotal: Money::from_minor(self.subtotal),
+            breakdown: self.breakdown.map(Breakdown::upgrade),
+            total: Money::from_minor(self.total),
+            products: self.products.into_iter().map(OrderLine::upgrade).collect(),
+            discounts: self.discounts.into_iter().map(Discount::upgrade).collect(),
+            refunds: self.refunds.into_iter().map(Refund::upgrade).collect(),
+            payments: self.payments.into_iter().map(Payment::upgrade).collect(),
+            tags: vec!(),
+            notes: self.notes
+        }
+    }
+}
+
+impl Ledger {
+    pub fn upgrade(self) -> crate::Ledger {
+        crate::Ledger {
+            orders: self.orders.into_iter().map(Order::upgrade).collect(),
+            vendors: self.vendors.into_iter().map(Vendor::upgrade).collect(),
+            catalog: self.catalog.into_iter().map(CatalogEntry::upgrade).collect(),
+            payment_methods: self.payment_methods.into_iter().map(PaymentMethod::upgrade).collect(),
+            home_currency: self.home_currency.upgrade(),
+            rates: self.rates.into_iter().map(Rate::upgrade).collect()
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use bincode::{Decode, Encode};
+
+use super::v1::{Date, Product};
+use super::v3::{self, Currency};
+
+// Order as it was saved in format version 2, before orders had a currency. The file was nothing but
+// a list of these back then.
+#[derive(Encode, Decode, Debug)]
+pub struct Order {
+    pub(super) date_placed: Date,
+    pub(super) status_history: Vec<StatusChange>,
+    pub(super) subtotal: u64,
+    pub(super) total: u64,
+    pub(super) products: Vec<(Product, u64)>,
+    pub(super) notes: String
+}
+
+// Status and StatusChange as they were saved in every format version from 2 up to 11
+#[derive(Encode, Decode, Debug, Clone, Copy)]
+pub enum Status {
+    Placed,
+    Processing,
+    Shipped,
+    Delivered,
+    Cancelled,
+    Returned
+}
+
+#[derive(Encode, Decode, Debug)]
+pub struct StatusChange {
+    pub(super) status: Status,
+    pub(super) date: Option<Date>
+}
+
+// Version 3 wrapped the orders in a Ledger, which also holds exchange rates, and gave every order a
+// currency. Everything saved before then was in dollars.
+pub fn upgrade(orders: Vec<Order>) -> v3::Ledger {
+    let usd = Currency {
+        code: *b"USD"
+    };
+    v3::Ledger {
+        orders: orders.into_iter().map(|x| v3::Order {
+            date_placed: x.date_placed,
+            status_history: x.status_history,
+            currency: usd,
+            subtotal: x.subtotal,
+            total: x.total,
+            products: x.products,
+            notes: x.notes
+        }).collect(),
+        home_currency: usd,
+        rates: vec!()
+    }
+}
@@ -0,0 +1,60 @@
+use bincode::{Decode, Encode};
+
+use super::v1::{Date, Product};
+use super::v2::StatusChange;
+use super::v4;
+
+// Ledger as it was saved in format version 3
+#[derive(Encode, Decode, Debug)]
+pub struct Ledger {
+    pub(super) orders: Vec<Order>,
+    pub(super) home_currency: Currency,
+    pub(super) rates: Vec<Rate>
+}
+
+// Order as it was saved in format version 3, before the total was broken down
+#[derive(Encode, Decode, Debug)]
+pub struct Order {
+    pub(super) date_placed: Date,
+    pub(super) status_history: Vec<StatusChange>,
+    pub(super) currency: Currency,
+    pub(super) subtotal: u64,
+    pub(super) total: u64,
+    pub(super) products: Vec<(Product, u64)>,
+    pub(super) notes: String
+}
+
+// Currency and Rate as they were saved in every format version from 3 up to 11
+#[derive(Encode, Decode, Debug, Clone, Copy)]
+pub struct Currency {
+    pub(super) code: [u8; 3]
+}
+
+#[derive(Encode, Decode, Debug)]
+pub struct Rate {
+    pub(super) date: Date,
+    pub(super) from: Currency,
+    pub(super) to: Currency,
+    pub(super) micros: u64
+}
+
+impl Ledger {
+    // Version 4 added Order.breakdown. Nobody was asked how older totals broke down, so they
+    // don't have one.
+    pub fn upgrade(self) -> v4::Ledger {
+        v4::Ledger {
+            orders: self.orders.into_iter().map(|x| v4::Order {
+                date_placed: x.date_placed,
+                status_history: x.status_history,
+                currency: x.currency,
+                subtotal: x.subtotal,
+                breakdown: None,
+                total: x.total,
+                products: x.products,
+                notes: x.notes
+            }).collect(),
+            home_currency: self.home_currency,
+            rates: self.rates
+        }
+    }
+}
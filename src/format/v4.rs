@@ -0,0 +1,60 @@
+use bincode::{Decode, Encode};
+
+use super::v1::{Date, Product};
+use super::v2::StatusChange;
+use super::v3::{Currency, Rate};
+use super::{v5, v6};
+
+// Ledger as it was saved in format version 4
+#[derive(Encode, Decode, Debug)]
+pub struct Ledger {
+    pub(super) orders: Vec<Order>,
+    pub(super) home_currency: Currency,
+    pub(super) rates: Vec<Rate>
+}
+
+// Order as it was saved in format version 4, before vendors
+#[derive(Encode, Decode, Debug)]
+pub struct Order {
+    pub(super) date_placed: Date,
+    pub(super) status_history: Vec<StatusChange>,
+    pub(super) currency: Currency,
+    pub(super) subtotal: u64,
+    pub(super) breakdown: Option<Breakdown>,
+    pub(super) total: u64,
+    pub(super) products: Vec<(Product, u64)>,
+    pub(super) notes: String
+}
+
+// Breakdown as it was saved in every format version from 4 up to 11
+#[derive(Encode, Decode, Debug)]
+pub struct Breakdown {
+    pub(super) shipping: u64,
+    pub(super) tax: u64,
+    pub(super) tax_rate: Option<u32>,
+    pub(super) fees: u64,
+    pub(super) tip: u64,
+    pub(super) credits: u64
+}
+
+impl Ledger {
+    // Version 5 added the vendor registry. Older orders don't say where they were placed.
+    pub fn upgrade(self) -> v5::Ledger {
+        v5::Ledger {
+            orders: self.orders.into_iter().map(|x| v6::Order {
+                date_placed: x.date_placed,
+                vendor: None,
+                status_history: x.status_history,
+                currency: x.currency,
+                subtotal: x.subtotal,
+                breakdown: x.breakdown,
+                total: x.total,
+                products: x.products,
+                notes: x.notes
+            }).collect(),
+            vendors: vec!(),
+            home_currency: self.home_currency,
+            rates: self.rates
+        }
+    }
+}
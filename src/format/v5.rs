@@ -0,0 +1,34 @@
+use bincode::{Decode, Encode};
+
+use super::v3::{Currency, Rate};
+use super::v6::{self, Order};
+
+// Ledger as it was saved in format version 5, before the product catalog
+#[derive(Encode, Decode, Debug)]
+pub struct Ledger {
+    pub(super) orders: Vec<Order>,
+    pub(super) vendors: Vec<Vendor>,
+    pub(super) home_currency: Currency,
+    pub(super) rates: Vec<Rate>
+}
+
+// Vendor as it was saved in every format version from 5 up to 11
+#[derive(Encode, Decode, Debug)]
+pub struct Vendor {
+    pub(super) name: String,
+    pub(super) website: String,
+    pub(super) contact: String,
+    pub(super) notes: String
+}
+
+impl Ledger {
+    pub fn upgrade(self) -> v6::Ledger {
+        v6::Ledger {
+            orders: self.orders,
+            vendors: self.vendors,
+            catalog: vec!(),
+            home_currency: self.home_currency,
+            rates: self.rates
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use bincode::{Decode, Encode};
+
+use super::v1::{Date, Product};
+use super::v2::StatusChange;
+use super::v3::{Currency, Rate};
+use super::v4::Breakdown;
+use super::v5::Vendor;
+use super::v7;
+
+// Ledger as it was saved in format version 6
+#[derive(Encode, Decode, Debug)]
+pub struct Ledger {
+    pub(super) orders: Vec<Order>,
+    pub(super) vendors: Vec<Vendor>,
+    pub(super) catalog: Vec<CatalogEntry>,
+    pub(super) home_currency: Currency,
+    pub(super) rates: Vec<Rate>
+}
+
+// Order as it was saved in format versions 5 and 6, when every product was bought exactly once
+#[derive(Encode, Decode, Debug)]
+pub struct Order {
+    pub(super) date_placed: Date,
+    pub(super) vendor: Option<usize>,
+    pub(super) status_history: Vec<StatusChange>,
+    pub(super) currency: Currency,
+    pub(super) subtotal: u64,
+    pub(super) breakdown: Option<Breakdown>,
+    pub(super) total: u64,
+    pub(super) products: Vec<(Product, u64)>,
+    pub(super) notes: String
+}
+
+// CatalogEntry as it was saved in every format version from 6 up to 11
+#[derive(Encode, Decode, Debug)]
+pub struct CatalogEntry {
+    pub(super) product: Product,
+    pub(super) currency: Currency,
+    pub(super) retired: bool
+}
+
+impl Ledger {
+    // What was paid for a product so far becomes the price of the one unit that was bought
+    pub fn upgrade(self) -> v7::Ledger {
+        v7::Ledger {
+            orders: self.orders.into_iter().map(|x| v7::Order {
+                date_placed: x.date_placed,
+                vendor: x.vendor,
+                status_history: x.status_history,
+                currency: x.currency,
+                subtotal: x.subtotal,
+                breakdown: x.breakdown,
+                total: x.total,
+                products: x.products.into_iter().map(|(product, paid)| (product, 1, paid, paid)).collect(),
+                notes: x.notes
+            }).collect(),
+            vendors: self.vendors,
+            catalog: self.catalog,
+            home_currency: self.home_currency,
+            rates: self.rates
+        }
+    }
+}
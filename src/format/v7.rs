@@ -0,0 +1,64 @@
+use bincode::{Decode, Encode};
+
+use super::v1::{Date, Product};
+use super::v2::StatusChange;
+use super::v3::{Currency, Rate};
+use super::v4::Breakdown;
+use super::v5::Vendor;
+use super::v6::CatalogEntry;
+use super::v8::{self, OrderLine};
+
+// Ledger as it was saved in format version 7
+#[derive(Encode, Decode, Debug)]
+pub struct Ledger {
+    pub(super) orders: Vec<Order>,
+    pub(super) vendors: Vec<Vendor>,
+    pub(super) catalog: Vec<CatalogEntry>,
+    pub(super) home_currency: Currency,
+    pub(super) rates: Vec<Rate>
+}
+
+// Order as it was saved in format version 7, when order lines were
+// (product, quantity, unit price, paid) tuples
+#[derive(Encode, Decode, Debug)]
+pub struct Order {
+    pub(super) date_placed: Date,
+    pub(super) vendor: Option<usize>,
+    pub(super) status_history: Vec<StatusChange>,
+    pub(super) currency: Currency,
+    pub(super) subtotal: u64,
+    pub(super) breakdown: Option<Breakdown>,
+    pub(super) total: u64,
+    pub(super) products: Vec<(Product, u64, u64, u64)>,
+    pub(super) notes: String
+}
+
+impl Ledger {
+    // Lines from before OrderLine didn't have a discount reason or notes
+    pub fn upgrade(self) -> v8::Ledger {
+        v8::Ledger {
+            orders: self.orders.into_iter().map(|x| v8::Order {
+                date_placed: x.date_placed,
+                vendor: x.vendor,
+                status_history: x.status_history,
+                currency: x.currency,
+                subtotal: x.subtotal,
+                breakdown: x.breakdown,
+                total: x.total,
+                products: x.products.into_iter().map(|(product, quantity, unit_price, paid)| OrderLine {
+                    product,
+                    quantity,
+                    unit_price,
+                    paid,
+                    discount_reason: "".to_string(),
+                    notes: "".to_string()
+                }).collect(),
+                notes: x.notes
+            }).collect(),
+            vendors: self.vendors,
+            catalog: self.catalog,
+            home_currency: self.home_currency,
+            rates: self.rates
+        }
+    }
+}
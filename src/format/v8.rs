@@ -0,0 +1,67 @@
+use bincode::{Decode, Encode};
+
+use super::v1::{Date, Product};
+use super::v2::StatusChange;
+use super::v3::{Currency, Rate};
+use super::v4::Breakdown;
+use super::v5::Vendor;
+use super::v6::CatalogEntry;
+use super::v9;
+
+// Ledger as it was saved in format version 8
+#[derive(Encode, Decode, Debug)]
+pub struct Ledger {
+    pub(super) orders: Vec<Order>,
+    pub(super) vendors: Vec<Vendor>,
+    pub(super) catalog: Vec<CatalogEntry>,
+    pub(super) home_currency: Currency,
+    pub(super) rates: Vec<Rate>
+}
+
+// Order as it was saved in format version 8, before discounts were recorded
+#[derive(Encode, Decode, Debug)]
+pub struct Order {
+    pub(super) date_placed: Date,
+    pub(super) vendor: Option<usize>,
+    pub(super) status_history: Vec<StatusChange>,
+    pub(super) currency: Currency,
+    pub(super) subtotal: u64,
+    pub(super) breakdown: Option<Breakdown>,
+    pub(super) total: u64,
+    pub(super) products: Vec<OrderLine>,
+    pub(super) notes: String
+}
+
+// OrderLine as it was saved in every format version from 8 up to 11
+#[derive(Encode, Decode, Debug)]
+pub struct OrderLine {
+    pub(super) product: Product,
+    pub(super) quantity: u64,
+    pub(super) unit_price: u64,
+    pub(super) paid: u64,
+    pub(super) discount_reason: String,
+    pub(super) notes: String
+}
+
+impl Ledger {
+    pub fn upgrade(self) -> v9::Ledger {
+        v9::Ledger {
+            orders: self.orders.into_iter().map(|x| v9::Order {
+                date_placed: x.date_placed,
+                vendor: x.vendor,
+                status_history: x.status_history,
+                currency: x.currency,
+                subtotal: x.subtotal,
+                breakdown: x.breakdown,
+                total: x.total,
+                products: x.products,
+                discounts: vec!(),
+                notes: x.notes
+            }).collect(),
+            vendors: self.vendors,
+            catalog: self.catalog,
+            home_currency: self.home_currency,
+            rates: self.rates
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use bincode::{Decode, Encode};
+
+use super::v1::Date;
+use super::v2::StatusChange;
+use super::v3::{Currency, Rate};
+use super::v4::Breakdown;
+use super::v5::Vendor;
+use super::v6::CatalogEntry;
+use super::v8::OrderLine;
+use super::v10;
+
+// Ledger as it was saved in format version 9
+#[derive(Encode, Decode, Debug)]
+pub struct Ledger {
+    pub(super) orders: Vec<Order>,
+    pub(super) vendors: Vec<Vendor>,
+    pub(super) catalog: Vec<CatalogEntry>,
+    pub(super) home_currency: Currency,
+    pub(super) rates: Vec<Rate>
+}
+
+// Order as it was saved in format version 9, before refunds were recorded
+#[derive(Encode, Decode, Debug)]
+pub struct Order {
+    pub(super) date_placed: Date,
+    pub(super) vendor: Option<usize>,
+    pub(super) status_history: Vec<StatusChange>,
+    pub(super) currency: Currency,
+    pub(super) subtotal: u64,
+    pub(super) breakdown: Option<Breakdown>,
+    pub(super) total: u64,
+    pub(super) products: Vec<OrderLine>,
+    pub(super) discounts: Vec<Discount>,
+    pub(super) notes: String
+}
+
+// Discount, Source and Amount as they were saved in every format version from 9 up to 11
+#[derive(Encode, Decode, Debug)]
+pub struct Discount {
+    pub(super) source: Source,
+    pub(super) code: String,
+    pub(super) amount: Amount,
+    pub(super) lines: Option<Vec<usize>>
+}
+
+#[derive(Encode, Decode, Debug, Clone, Copy)]
+pub enum Source {
+    Promo,
+    Loyalty,
+    PriceMatch,
+    Other
+}
+
+#[derive(Encode, Decode, Debug, Clone, Copy)]
+pub enum Amount {
+    Percent(u32),
+    Fixed(u64)
+}
+
+impl Ledger {
+    pub fn upgrade(self) -> v10::Ledger {
+        v10::Ledger {
+            orders: self.orders.into_iter().map(|x| v10::Order {
+                date_placed: x.date_placed,
+                vendor: x.vendor,
+                status_history: x.status_history,
+                currency: x.currency,
+                subtotal: x.subtotal,
+                breakdown: x.breakdown,
+                total: x.total,
+                products: x.products,
+                discounts: x.discounts,
+                refunds: vec!(),
+                notes: x.notes
+            }).collect(),
+            vendors: self.vendors,
+            catalog: self.catalog,
+            home_currency: self.home_currency,
+            rates: self.rates
+        }
+    }
+}
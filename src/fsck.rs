@@ -0,0 +1,296 @@
+use std::path::Path;
+
+use crate::args::Args;
+use crate::error::Error;
+use crate::money::Money;
+use crate::{storage, AddOn, Order, Product};
+
+/*
+ * Rules about prices and dates that orders normally follow, but that nothing stops them from
+ * breaking: the comments on Order, OrderLine and AddOn describe them, and old files (or typos) can
+ * have orders that don't. They're not errors, since there are real orders that break some of them
+ * (a bundle whose sticker price was marked down below its base price...), so 'check' only reports
+ * them, and add and update only refuse to save an order that breaks them when given --strict.
+ *
+ * Every problem says where it is, in the same form search uses, like "products[0].items[2]".
+ */
+pub struct Problem {
+    pub location: String,
+    pub reason: String
+}
+
+fn problem(found: &mut Vec<Problem>, location: &str, reason: String) {
+    found.push(Problem {
+        location: location.to_string(),
+        reason
+    });
+}
+
+fn check_product(order: &Order, product: &Product, location: &str, found: &mut Vec<Problem>) {
+    let currency = order.currency;
+    if product.sticker_price < product.base_price {
+        problem(found, location, format!("its sticker price ({}) is less than its base price ({})", product.sticker_price.to_string_in(currency), product.base_price.to_string_in(currency)));
+    }
+    for (index, item) in product.items.iter().flatten().enumerate() {
+        check_product(order, item, &format!("{}.items[{}]", location, index), found);
+    }
+    for (index, add_on) in product.add_ons.iter().flatten().enumerate() {
+        check_add_on(order, add_on, &format!("{}.add_ons[{}]", location, index), found);
+    }
+}
+
+fn check_add_on(order: &Order, add_on: &AddOn, location: &str, found: &mut Vec<Problem>) {
+    let currency = order.currency;
+    if add_on.actual_price > add_on.sticker_price {
+        problem(found, location, format!("its price after discounts ({}) is more than its sticker price ({})", add_on.actual_price.to_string_in(currency), add_on.sticker_price.to_string_in(currency)));
+    }
+    if let Some(x) = &add_on.assoc_product {
+        check_product(order, x, &format!("{}.assoc_product", location), found);
+    }
+}
+
+// Everything about order that breaks the rules above
+pub fn problems(order: &Order) -> Vec<Problem> {
+    let currency = order.currency;
+    let mut found: Vec<Problem> = vec!();
+    if !order.date_placed.is_valid() {
+        problem(&mut found, "date_placed", format!("{} isn't on the calendar", order.date_placed.to_string()));
+    }
+    for (index, change) in order.status_history.iter().enumerate() {
+        if let Some(x) = change.date
+            && x < order.date_placed {
+            problem(&mut found, &format!("status_history[{}]", index), format!("the order became {} on {}, which is before it was placed on {}", change.status.name(), x.to_string(), order.date_placed.to_string()));
+        }
+    }
+    // Order.subtotal is after the order's discounts but before shipping and such, so it can't be
+    // more than what the products cost
+    if !order.products.is_empty()
+        && let Ok(x) = Money::sum(order.products.iter().map(|y| y.paid), "")
+        && order.subtotal > x {
+        problem(&mut found, "subtotal", format!("it's {}, but the products only cost {} altogether", order.subtotal.to_string_in(currency), x.to_string_in(currency)));
+    }
+    // Gift cards and store credit are the only thing that can take the total below the subtotal
+    let credits = order.breakdown.map(|x| x.credits).unwrap_or_default();
+    if order.total < order.subtotal && credits == Money::default() {
+        problem(&mut found, "total", format!("it's {}, which is less than the subtotal of {}", order.total.to_string_in(currency), order.subtotal.to_string_in(currency)));
+    }
+    for (index, line) in order.products.iter().enumerate() {
+        let location = format!("products[{}]", index);
+        if line.quantity == 0 {
+            problem(&mut found, &location, "its quantity is 0".to_string());
+        }
+        check_product(order, &line.product, &location, &mut found);
+    }
+    found
+}
+
+pub fn print_problems(found: &[Problem], indent: &str) {
+    for x in found {
+        println!("{}{}: {}", indent, x.location, x.reason);
+    }
+}
+
+// For add and update: with --strict, an order that breaks any of the rules isn't saved. Without
+// it, the problems are only pointed out.
+pub fn check_new_order(order: &Order, strict: bool) -> Result<(), Error> {
+    let found = problems(order);
+    if found.is_empty() {
+        return Ok(());
+    }
+    println!("The order looks off:");
+    print_problems(&found, "  ");
+    match strict {
+        true => Err(Error::validation("the order", "it was given with --strict, so it wasn't saved")),
+        false => Ok(())
+    }
+}
+
+// purchase_tracker check [FILE]
+pub fn check_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &[], &[])?;
+    let path = match args.positional.as_slice() {
+        [x] => Path::new(x),
+        _ => return Err(Error::usage("purchase_tracker check [FILE]"))
+    };
+    let ledger = storage::load(path)?;
+    let mut bad = 0;
+    let mut count = 0;
+    for (index, order) in ledger.orders.iter().enumerate() {
+        let found = problems(order);
+        if found.is_empty() {
+            continue;
+        }
+        println!("Order {} ({}):", index, order.date_placed.to_string());
+        print_problems(&found, "  ");
+        bad += 1;
+        count += found.len();
+    }
+    match bad {
+        0 => {
+            println!("Checked {} orders, and found nothing wrong.", ledger.orders.len());
+            Ok(())
+        },
+        _ => Err(Error::validation(&path.display().to_string(), format!("found {} problems in {} of its {} orders", count, bad, ledger.orders.len())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::USD;
+    use crate::{Breakdown, Date, OrderLine, Status, StatusChange};
+
+    fn product(name: &str, base_price: u64, sticker_price: u64) -> Product {
+        Product {
+            name: name.to_string(),
+            desc: "".to_string(),
+            base_price: Money::from_minor(base_price),
+            sticker_price: Money::from_minor(sticker_price),
+            category: "".to_string(),
+            tags: vec!(),
+            items: None,
+            add_ons: None
+        }
+    }
+
+    fn add_on(sticker_price: u64, actual_price: u64) -> AddOn {
+        AddOn {
+            name: "Grip".to_string(),
+            desc: "".to_string(),
+            sticker_price: Money::from_minor(sticker_price),
+            actual_price: Money::from_minor(actual_price),
+            category: "".to_string(),
+            tags: vec!(),
+            assoc_product: None
+        }
+    }
+
+    // Placed on 2026-10-01 and shipped two days later: a $40 mask and a $10 glove that came with
+    // a grip, and $5 of shipping
+    fn clean_order() -> Order {
+        let mut glove = product("Glove", 1000, 1200);
+        glove.add_ons = Some(vec!(add_on(600, 500)));
+        let line = |product: Product, paid: u64| OrderLine {
+            product,
+            quantity: 1,
+            unit_price: Money::from_minor(paid),
+            paid: Money::from_minor(paid),
+            discount_reason: "".to_string(),
+            notes: "".to_string()
+        };
+        Order {
+            date_placed: Date::new(2026, 10, 1).unwrap(),
+            vendor: None,
+            status_history: vec!(StatusChange {
+                status: Status::Shipped,
+                date: Date::new(2026, 10, 3)
+            }),
+            currency: USD,
+            subtotal: Money::from_minor(5000),
+            breakdown: Some(Breakdown {
+                shipping: Money::from_minor(500),
+                ..Breakdown::default()
+            }),
+            total: Money::from_minor(5500),
+            products: vec!(line(product("Mask", 4000, 4000), 4000), line(glove, 1000)),
+            discounts: vec!(),
+            refunds: vec!(),
+            payments: vec!(),
+            tags: vec!(),
+            notes: "".to_string()
+        }
+    }
+
+    fn found(order: &Order) -> Vec<(String, String)> {
+        problems(order).into_iter().map(|x| (x.location, x.reason)).collect()
+    }
+
+    fn one(location: &str, reason: &str) -> Vec<(String, String)> {
+        vec!((location.to_string(), reason.to_string()))
+    }
+
+    #[test]
+    fn clean_orders_have_no_problems() {
+        assert!(found(&clean_order()).is_empty());
+        assert!(check_new_order(&clean_order(), true).is_ok());
+        // An order without products or a breakdown doesn't break anything either
+        let mut order = clean_order();
+        order.products = vec!();
+        order.breakdown = None;
+        order.status_history = vec!();
+        assert!(found(&order).is_empty());
+    }
+
+    #[test]
+    fn placed_dates_have_to_be_on_the_calendar() {
+        let mut order = clean_order();
+        order.date_placed = Date { month: 2, day: 30, year: 2026 };
+        order.status_history = vec!();
+        assert_eq!(found(&order), one("date_placed", "2/30/2026 isn't on the calendar"));
+    }
+
+    #[test]
+    fn statuses_cant_change_before_the_order_was_placed() {
+        let mut order = clean_order();
+        order.status_history.insert(0, StatusChange {
+            status: Status::Processing,
+            date: Date::new(2026, 9, 30)
+        });
+        order.status_history.push(StatusChange {
+            status: Status::Delivered,
+            date: None
+        });
+        assert_eq!(found(&order), one("status_history[0]", "the order became processing on 9/30/2026, which is before it was placed on 10/1/2026"));
+    }
+
+    #[test]
+    fn subtotals_cant_be_more_than_the_products() {
+        let mut order = clean_order();
+        order.subtotal = Money::from_minor(5001);
+        order.total = Money::from_minor(5501);
+        assert_eq!(found(&order), one("subtotal", "it's $50.01, but the products only cost $50.00 altogether"));
+    }
+
+    #[test]
+    fn only_credits_take_the_total_below_the_subtotal() {
+        let mut order = clean_order();
+        order.total = Money::from_minor(4999);
+        assert_eq!(found(&order), one("total", "it's $49.99, which is less than the subtotal of $50.00"));
+        order.breakdown = Some(Breakdown {
+            credits: Money::from_minor(1),
+            ..Breakdown::default()
+        });
+        assert!(found(&order).is_empty());
+    }
+
+    #[test]
+    fn quantities_cant_be_0() {
+        let mut order = clean_order();
+        order.products[1].quantity = 0;
+        assert_eq!(found(&order), one("products[1]", "its quantity is 0"));
+    }
+
+    #[test]
+    fn sticker_prices_cant_be_below_base_prices() {
+        let mut order = clean_order();
+        order.products[0].product.sticker_price = Money::from_minor(3999);
+        assert_eq!(found(&order), one("products[0]", "its sticker price ($39.99) is less than its base price ($40.00)"));
+        // Sub-products and the products that come with add-ons are checked too
+        let mut order = clean_order();
+        order.products[0].product.items = Some(vec!(product("Bib", 500, 500), product("Strap", 300, 200)));
+        order.products[1].product.add_ons.as_mut().unwrap()[0].assoc_product = Some(product("Wrap", 200, 100));
+        assert_eq!(found(&order), vec!(
+            ("products[0].items[1]".to_string(), "its sticker price ($2.00) is less than its base price ($3.00)".to_string()),
+            ("products[1].add_ons[0].assoc_product".to_string(), "its sticker price ($1.00) is less than its base price ($2.00)".to_string())
+        ));
+    }
+
+    #[test]
+    fn add_ons_cant_cost_more_than_their_sticker_price() {
+        let mut order = clean_order();
+        order.products[1].product.add_ons = Some(vec!(add_on(600, 600), add_on(600, 601)));
+        assert_eq!(found(&order), one("products[1].add_ons[1]", "its price after discounts ($6.01) is more than its sticker price ($6.00)"));
+        assert!(check_new_order(&order, false).is_ok());
+        assert!(matches!(check_new_order(&order, true), Err(Error::Validation { .. })));
+    }
+}
@@ -1,14 +1,143 @@
-use bincode::{config, Decode, Encode};
-use std::fs::File;
+// Every printable type in here implements ToString directly, which is how the output is built
+#![allow(clippy::to_string_trait_impl)]
+
+use bincode::{Decode, Encode};
+use std::fs::{self, File};
 use std::env::args;
 use std::io;
+use std::path::Path;
+use std::process;
 
-#[derive(Encode, Decode, Debug)]
+use crate::error::Error;
+use crate::catalog::CatalogEntry;
+use crate::discount::Discount;
+use crate::money::{Currency, Money};
+use crate::payment::{Payment, PaymentMethod};
+use crate::rates::Rate;
+use crate::refund::Refund;
+use crate::vendor::Vendor;
+
+mod args;
+mod backup;
+mod catalog;
+mod category;
+mod discount;
+mod browse;
+mod edit;
+mod error;
+mod export;
+mod format;
+mod fsck;
+mod money;
+mod payment;
+mod rates;
+mod refund;
+mod report;
+mod savings;
+mod spec;
+mod storage;
+mod vendor;
+
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
 struct Date {
     month: u8,
     day: u8,
     year: u64
 }
+// The fields are in this order because that's how Dates were saved before they got validated, so
+// don't reorder them. That's also why Ord is implemented by hand below instead of derived.
+// Dates made with Date::new or parse_date are always real calendar dates between the years 1 and
+// 9999, but Dates decoded from old files are whatever was typed back then.
+
+const MAX_YEAR: u64 = 9999;
+const WEEKDAYS: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+
+impl Date {
+    // None if there's no such day on the calendar
+    fn new(year: u64, month: u8, day: u8) -> Option<Date> {
+        if year == 0 || year > MAX_YEAR || month == 0 || month > 12 || day == 0 || day > Date::days_in_month(year, month) {
+            return None;
+        }
+        Some(Date {
+            month,
+            day,
+            year
+        })
+    }
+
+    fn is_leap_year(year: u64) -> bool {
+        year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+    }
+
+    fn days_in_month(year: u64, month: u8) -> u8 {
+        match month {
+            2 if Date::is_leap_year(year) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31
+        }
+    }
+
+    // Number of days since 1970-01-01 (negative before it), using the proleptic Gregorian calendar.
+    // See Howard Hinnant's "chrono-compatible low-level date algorithms" for how this works.
+    fn days_since_epoch(&self) -> i64 {
+        let month = self.month as i64;
+        let year = self.year as i64 - if month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    fn from_days_since_epoch(days: i64) -> Date {
+        let shifted = days + 719468;
+        let era = shifted.div_euclid(146097);
+        let day_of_era = shifted.rem_euclid(146097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        Date {
+            month: month as u8,
+            day: day as u8,
+            year: year as u64
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        Date::new(self.year, self.month, self.day).is_some()
+    }
+
+    // How many days after self other is (negative if other comes first)
+    fn days_until(&self, other: &Date) -> i64 {
+        other.days_since_epoch() - self.days_since_epoch()
+    }
+
+    fn weekday(&self) -> &'static str {
+        // 1970-01-01 was a Thursday
+        WEEKDAYS[(self.days_since_epoch() + 3).rem_euclid(7) as usize]
+    }
+
+    // "YYYY-MM-DD"
+    fn to_iso_string(self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl Ord for Date {
+    fn cmp(&self, other: &Date) -> std::cmp::Ordering {
+        (self.year, self.month, self.day).cmp(&(other.year, other.month, other.day))
+    }
+}
+
+impl PartialOrd for Date {
+    fn partial_cmp(&self, other: &Date) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
 impl ToString for Date {
     fn to_string(&self) -> String {
@@ -22,55 +151,179 @@ impl ToString for Date {
     }
 }
 
-fn date_cli() -> Date {
+fn date_cli(field: &str) -> Result<Date, Error> {
     let stdin = io::stdin();
-    let mut date_as_string = "".to_string();
-    match stdin.read_line(&mut date_as_string) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-    }
-    let mut month: u8 = 0;
-    let mut day: u8 = 0;
-    let mut year: u64 = 0;
-    for (index, i) in date_as_string.trim().split('/').enumerate() {
-        match index {
-            0 => {
-                month = match i.trim().parse::<u8>() {
-                    Ok(x) => x,
-                    Err(x) => panic!("Failed to parse your input as a date. Maybe you didn't correctly write your input as \"MM/DD/YYYY\"?\nHere's the reported error, if it helps: {}", x)
-                }
-            },
-            1 => {
-                day = match i.trim().parse::<u8>() {
-                    Ok(x) => x,
-                    Err(x) => panic!("Failed to parse your input as a date. Maybe you didn't correctly write your input as \"MM/DD/YYYY\"?\nHere's the reported error, if it helps: {}", x)
-                }
-            },
-            2 => {
-                year = match i.trim().parse::<u64>() {
-                    Ok(x) => x,
-                    Err(x) => panic!("Failed to parse your input as a date. Maybe you didn't correctly write your input as \"MM/DD/YYYY\"?\nHere's the reported error, if it helps: {}", x)
-                }
-            }
-            _ => println!("Ignoring errenous additional date field: {}", i)
+    ask(&stdin, |x| parse_date(x, field))
+}
+
+// Accepts "MM/DD/YYYY" as well as ISO "YYYY-MM-DD", and rejects dates that aren't on the calendar.
+// field names what the date is for, in case it fails to parse.
+fn parse_date(date_as_string: &str, field: &str) -> Result<Date, Error> {
+    let date_as_string = date_as_string.trim();
+    let (fields, format): (Vec<&str>, &str) = if date_as_string.contains('-') {
+        (date_as_string.split('-').collect(), "YYYY-MM-DD")
+    } else {
+        (date_as_string.split('/').collect(), "MM/DD/YYYY")
+    };
+    if fields.len() != 3 {
+        return Err(Error::parse(field, date_as_string, format!("Maybe you didn't correctly write it as \"{}\"?", format)));
+    }
+    let (year, month, day) = match format {
+        "YYYY-MM-DD" => (fields[0], fields[1], fields[2]),
+        _ => (fields[2], fields[0], fields[1])
+    };
+    let year = match year.trim().parse::<u64>() {
+        Ok(x) => x,
+        Err(x) => return Err(Error::parse(field, date_as_string, format!("Maybe you didn't correctly write it as \"{}\"?\nHere's the reported error, if it helps: {}", format, x)))
+    };
+    let month = match month.trim().parse::<u8>() {
+        Ok(x) => x,
+        Err(x) => return Err(Error::parse(field, date_as_string, format!("Maybe you didn't correctly write it as \"{}\"?\nHere's the reported error, if it helps: {}", format, x)))
+    };
+    let day = match day.trim().parse::<u8>() {
+        Ok(x) => x,
+        Err(x) => return Err(Error::parse(field, date_as_string, format!("Maybe you didn't correctly write it as \"{}\"?\nHere's the reported error, if it helps: {}", format, x)))
+    };
+
+    if year == 0 || year > MAX_YEAR {
+        return Err(Error::parse(field, date_as_string, format!("The year has to be between 1 and {}.", MAX_YEAR)));
+    }
+    if month == 0 || month > 12 {
+        return Err(Error::parse(field, date_as_string, "The month has to be between 1 and 12."));
+    }
+    match Date::new(year, month, day) {
+        Some(x) => Ok(x),
+        None => Err(Error::parse(field, date_as_string, format!("That month only has {} days in {}.", Date::days_in_month(year, month), year)))
+    }
+}
+
+// Picks out orders placed between --from and --to (both optional, and both included)
+struct DateRange {
+    from: Option<Date>,
+    to: Option<Date>
+}
+
+impl DateRange {
+    // args has to have been parsed with "from" and "to" as flags that take a value
+    fn from_args(args: &args::Args) -> Result<DateRange, Error> {
+        let from = match args.value("from") {
+            Some(x) => Some(parse_date(x, "--from")?),
+            None => None
+        };
+        let to = match args.value("to") {
+            Some(x) => Some(parse_date(x, "--to")?),
+            None => None
+        };
+        if let (Some(x), Some(y)) = (from, to)
+            && y < x {
+            return Err(Error::validation("--to", format!("it's {}, which is before --from ({})", y.to_string(), x.to_string())));
         }
+        Ok(DateRange { from, to })
     }
-    if month == 0 || day == 0 || year == 0 {
-        panic!("Failed to parse your input as a date. Maybe you didn't correctly write your input as \"MM/DD/YYYY\"? Note that zeros in any of the fields are unsupported.")
+
+    fn contains(&self, order: &Order) -> bool {
+        self.from.is_none_or(|x| order.date_placed >= x) && self.to.is_none_or(|x| order.date_placed <= x)
     }
-    Date {
-        month,
-        day,
-        year
+
+    // Like " placed from 1/1/2026 to 3/31/2026", "" when there's no range
+    fn describe(&self) -> String {
+        match (self.from, self.to) {
+            (Some(x), Some(y)) => format!(" placed from {} to {}", x.to_string(), y.to_string()),
+            (Some(x), None) => format!(" placed since {}", x.to_string()),
+            (None, Some(y)) => format!(" placed until {}", y.to_string()),
+            (None, None) => "".to_string()
+        }
     }
 }
 
-#[derive(Encode, Decode, Debug)]
+// Reads one line from the standard input. Running out of input counts as a failure, since every
+// caller is waiting on an answer to a question.
+fn read_line(stdin: &io::Stdin) -> Result<String, Error> {
+    let mut temp = "".to_string();
+    match stdin.read_line(&mut temp) {
+        Ok(0) => Err(Error::stdin(io::Error::new(io::ErrorKind::UnexpectedEof, "the standard input ended before every question was answered"))),
+        Ok(_) => Ok(temp),
+        Err(x) => Err(Error::stdin(x))
+    }
+}
+
+// Like date_cli, but a blank answer means the date isn't known
+fn optional_date_cli(field: &str) -> Result<Option<Date>, Error> {
+    let stdin = io::stdin();
+    ask(&stdin, |x| match x.trim() {
+        "" => Ok(None),
+        y => parse_date(y, field).map(Some)
+    })
+}
+
+// Typing this in answer to any question of the order creator throws the order away
+const ABORT: &str = "abort";
+
+// Reads the answer to a question of the order creator
+fn read_answer(stdin: &io::Stdin) -> Result<String, Error> {
+    let answer = read_line(stdin)?;
+    if answer.trim() == ABORT {
+        return Err(Error::Aborted);
+    }
+    Ok(answer)
+}
+
+// Asks a "Yes" or "No" question of the order creator (the question itself has to be printed first)
+fn ask_yes_no(stdin: &io::Stdin) -> Result<bool, Error> {
+    loop {
+        let temp = read_answer(stdin)?;
+        if temp.trim() == "Yes" {
+            return Ok(true);
+        }
+        if temp.trim() == "No" {
+            return Ok(false);
+        }
+        println!("Please type \"Yes\" or \"No\"");
+    }
+}
+
+// Keeps asking until parse is happy with the answer, explaining what was wrong each time
+fn ask<T>(stdin: &io::Stdin, parse: impl Fn(&str) -> Result<T, Error>) -> Result<T, Error> {
+    loop {
+        match parse(&read_answer(stdin)?) {
+            Ok(x) => return Ok(x),
+            Err(x) => println!("{}\nPlease try again (or type \"{}\" to throw away this order without saving anything).", x.to_string(), ABORT)
+        }
+    }
+}
+
+fn ask_count(stdin: &io::Stdin, field: &str) -> Result<u64, Error> {
+    ask(stdin, |x| parse_count(x, field))
+}
+
+fn ask_money(stdin: &io::Stdin, currency: Currency, field: &str) -> Result<Money, Error> {
+    ask(stdin, |x| Money::parse(x, currency, field))
+}
+
+// Like ask_money, but a blank answer means nothing
+fn ask_money_or_zero(stdin: &io::Stdin, currency: Currency, field: &str) -> Result<Money, Error> {
+    ask(stdin, |x| match x.trim() {
+        "" => Ok(Money::default()),
+        y => Money::parse(y, currency, field)
+    })
+}
+
+// Parses how many of something there are. field names what the number is for.
+fn parse_count(input: &str, field: &str) -> Result<u64, Error> {
+    match input.trim().parse::<u64>() {
+        Ok(x) => Ok(x),
+        Err(x) => Err(Error::parse(field, input, format!("Your input should be an unsigned integer, no negative sign or decimal, and certainly no nonnumeric characters. Here's the reported error, if it helps: {}", x)))
+    }
+}
+
+#[derive(Encode, Decode, Debug, Clone)]
 struct Product {
     name: String, // Name of the product
     desc: String, // Description of the product
-    base_price: u64, // Price of product excluding additional costs incurred by add-ons
-    sticker_price: u64, // Price of product in cents
+    base_price: Money, // Price of product excluding additional costs incurred by add-ons
+    sticker_price: Money, // Price of product including add-ons
+    category: String, // Like "Hobby > Fencing > Blades", "" to use the one of what it's part of
+    tags: Vec<String>, // Empty to use the ones of what it's part of, see category.rs
     items: Option<Vec<Product>>, // Itemized cost of product (if applicable)
     // NOTE:
     // Generally, the base_price cannot be easily determined by summing up the costs of the
@@ -82,68 +335,35 @@ struct Product {
     add_ons: Option<Vec<AddOn>> // List of add-ons (if applicable)
 }
 
-fn product_cli() -> Product {
+// currency is the one the order is in
+fn product_cli(currency: Currency) -> Result<Product, Error> {
     let stdin = io::stdin();
 
     println!("Product name?");
-    let mut name = "".to_string();
-    match stdin.read_line(&mut name) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-    }
+    let name = read_answer(&stdin)?;
 
     println!("Product description?");
-    let mut desc = "".to_string();
-    match stdin.read_line(&mut desc) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-    }
+    let desc = read_answer(&stdin)?;
 
-    println!("What's the price of the product in cents excluding add-ons and sales?");
-    let mut temp = "".to_string();
-    match stdin.read_line(&mut temp) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-    }
-    let base_price = match temp.trim().parse::<u64>() {
-        Ok(x) => x,
-        Err(x) => panic!("Failed to parse your input as a number. Your input should be an unsigned integer, no negative sign or decimal, and certainly no nonnumeric characters. Here's the reported error, if it helps: {}", x)
-    };
+    let (category, tags) = category_cli(&stdin, &name)?;
 
-    println!("What's the price of the product in cents including add-ons but excluding sales?");
-    let mut temp = "".to_string();
-    match stdin.read_line(&mut temp) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-    }
-    let sticker_price = match temp.trim().parse::<u64>() {
-        Ok(x) => x,
-        Err(x) => panic!("Failed to parse your input as a number. Your input should be an unsigned integer, no negative sign or decimal, and certainly no nonnumeric characters. Here's the reported error, if it helps: {}", x)
-    };
+    println!("What's the price of the product excluding add-ons and sales? (e.g. \"12.05\")");
+    let base_price = ask_money(&stdin, currency, &format!("the base price of {}", name.trim()))?;
+
+    println!("What's the price of the product including add-ons but excluding sales?");
+    let sticker_price = ask_money(&stdin, currency, &format!("the sticker price of {}", name.trim()))?;
 
     println!("Does {} have one or more subitems? (Type \"Yes\" or \"No\")", name);
     let items: Option<Vec<Product>>;
     loop {
-        let mut temp = "".to_string();
-        match stdin.read_line(&mut temp) {
-            Ok(_) => (),
-            Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-        }
+        let temp = read_answer(&stdin)?;
         if temp.trim() == "Yes" {
             println!("How many subitems?");
-            temp = "".to_string();
-            match stdin.read_line(&mut temp) {
-                Ok(_) => (),
-                Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-            }
             let mut tempsubitems:Vec<Product> = vec!();
-            let repetitions = match temp.trim().parse::<u64>() {
-                Ok(x) => x,
-                Err(x) => panic!("Failed to parse your input as a number. Your input should be an unsigned integer, no negative sign or decimal, and certainly no nonnumeric characters. Here's the reported error, if it helps: {}", x)
-            };
+            let repetitions = ask_count(&stdin, &format!("the number of subitems of {}", name.trim()))?;
             for i in 0..repetitions {
                 println!("{}th subitem of {}:\n", i, name);
-                tempsubitems.push(product_cli());
+                tempsubitems.push(product_cli(currency)?);
             }
             items = Some(tempsubitems);
             break;
@@ -158,26 +378,14 @@ fn product_cli() -> Product {
     println!("Does {} have one or more add-ons? (Type \"Yes\" or \"No\")", name);
     let add_ons: Option<Vec<AddOn>>;
     loop {
-        let mut temp = "".to_string();
-        match stdin.read_line(&mut temp) {
-            Ok(_) => (),
-            Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-        }
+        let temp = read_answer(&stdin)?;
         if temp.trim() == "Yes" {
             println!("How many add-ons?");
-            temp = "".to_string();
-            match stdin.read_line(&mut temp) {
-                Ok(_) => (),
-                Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-            }
             let mut tempsubitems:Vec<AddOn> = vec!();
-            let repetitions = match temp.trim().parse::<u64>() {
-                Ok(x) => x,
-                Err(x) => panic!("Failed to parse your input as a number. Your input should be an unsigned integer, no negative sign or decimal, and certainly no nonnumeric characters. Here's the reported error, if it helps: {}", x)
-            };
+            let repetitions = ask_count(&stdin, &format!("the number of add-ons of {}", name.trim()))?;
             for i in 0..repetitions {
                 println!("{}th add-on of {}:\n", i, name);
-                tempsubitems.push(add_on_cli());
+                tempsubitems.push(add_on_cli(currency)?);
             }
             add_ons = Some(tempsubitems);
             break;
@@ -188,31 +396,53 @@ fn product_cli() -> Product {
         }
         println!("Please type \"Yes\" or \"No\"");
     }
-    Product {
+    Ok(Product {
         name,
         desc,
         base_price,
         sticker_price,
+        category,
+        tags,
         items,
         add_ons
+    })
+}
+
+// Asks for the category and tags of a product or add-on called name
+fn category_cli(stdin: &io::Stdin, name: &str) -> Result<(String, Vec<String>), Error> {
+    println!("What category is {} in? (Most general first, like \"Hobby > Fencing > Blades\". Leave it blank if it's in the same category as what it's part of, or has none)", name.trim());
+    let category = category::parse_category(&read_answer(stdin)?);
+    println!("Any tags for {}? (Separated by commas, like \"gift, sale\". Leave it blank if it has the same tags as what it's part of, or none)", name.trim());
+    let tags = category::parse_tags(&read_answer(stdin)?);
+    Ok((category, tags))
+}
+
+// Adds "Category: ..." and "Tags: ..." lines to the description of something, when it has them
+fn push_category(temp: &mut String, category: &str, tags: &[String]) {
+    if !category.is_empty() {
+        temp.push_str("\nCategory: ");
+        temp.push_str(category);
+    }
+    if !tags.is_empty() {
+        temp.push_str("\nTags: ");
+        temp.push_str(&category::tags_to_string(tags));
     }
 }
 
-impl ToString for Product {
-    fn to_string(&self) -> String {
+impl Product {
+    // Product has no ToString of its own, since its prices mean nothing without the currency of
+    // the order it's in
+    fn to_string_in(&self, currency: Currency) -> String {
         let mut temp = "".to_string();
         temp.push_str("Name: ");
         temp.push_str(&self.name);
         temp.push_str("\nDescription: ");
         temp.push_str(&self.desc);
-        temp.push_str("\nBase Price: $");
-        temp.push_str(&(self.base_price/100).to_string());
-        temp.push('.');
-        temp.push_str(&(self.base_price%100).to_string());
-        temp.push_str("\nSticker Price: $");
-        temp.push_str(&(self.sticker_price/100).to_string());
-        temp.push('.');
-        temp.push_str(&(self.sticker_price%100).to_string());
+        push_category(&mut temp, &self.category, &self.tags);
+        temp.push_str("\nBase Price: ");
+        temp.push_str(&self.base_price.to_string_in(currency));
+        temp.push_str("\nSticker Price: ");
+        temp.push_str(&self.sticker_price.to_string_in(currency));
         temp.push('\n');
         match &self.items {
             None => (),
@@ -221,7 +451,7 @@ impl ToString for Product {
                 for (index, item) in x.iter().enumerate() {
                     temp.push_str(&index.to_string());
                     temp.push_str(".\n");
-                    temp.push_str(&item.to_string());
+                    temp.push_str(&item.to_string_in(currency));
                     temp.push_str("\n\n");
                 }
                 temp.push_str("}\n");
@@ -234,7 +464,7 @@ impl ToString for Product {
                 for (index, item) in x.iter().enumerate() {
                     temp.push_str(&index.to_string());
                     temp.push_str(".\n");
-                    temp.push_str(&item.to_string());
+                    temp.push_str(&item.to_string_in(currency));
                     temp.push_str("\n\n");
                 }
                 temp.push_str("}\n");
@@ -244,12 +474,14 @@ impl ToString for Product {
     }
 }
 
-#[derive(Encode, Decode, Debug)]
+#[derive(Encode, Decode, Debug, Clone)]
 struct AddOn {
     name: String, // Name of the add-on
     desc: String, // Description of the add-on
-    sticker_price: u64, // Price of the add-on
-    actual_price: u64, // Price of the add-on after discounts
+    sticker_price: Money, // Price of the add-on
+    actual_price: Money, // Price of the add-on after discounts
+    category: String, // Like Product.category and Product.tags
+    tags: Vec<String>,
     // NOTE:
     // If an add-on has an associated product but the add-on costs less than the associated
     // product, do NOT but the price of the associated product in sticker_price and then price of
@@ -260,56 +492,30 @@ struct AddOn {
     assoc_product: Option<Product> // Product associated with add-on (if applicable)
 }
 
-fn add_on_cli() -> AddOn {
+fn add_on_cli(currency: Currency) -> Result<AddOn, Error> {
     let stdin = io::stdin();
 
     println!("Add-on name?");
-    let mut name = "".to_string();
-    match stdin.read_line(&mut name) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-    }
+    let name = read_answer(&stdin)?;
 
     println!("Add-on description?");
-    let mut desc = "".to_string();
-    match stdin.read_line(&mut desc) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-    }
+    let desc = read_answer(&stdin)?;
 
-    println!("Typical price of the add-on in cents? (This is the nondiscounted price of the add-on, not the typical price of the associated product if one exists)");
-    let mut temp = "".to_string();
-    match stdin.read_line(&mut temp) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-    }
-    let sticker_price = match temp.trim().parse::<u64>() {
-        Ok(x) => x,
-        Err(x) => panic!("Failed to parse your input as a number. Your input should be an unsigned integer, no negative sign or decimal, and certainly no nonnumeric characters. Here's the reported error, if it helps: {}", x)
-    };
+    let (category, tags) = category_cli(&stdin, &name)?;
 
-    println!("What's the price of the add-on after discounts in cents? (Usually, this is the amount that the product that you added this onto increased by)");
-    let mut temp = "".to_string();
-    match stdin.read_line(&mut temp) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-    }
-    let actual_price = match temp.trim().parse::<u64>() {
-        Ok(x) => x,
-        Err(x) => panic!("Failed to parse your input as a number. Your input should be an unsigned integer, no negative sign or decimal, and certainly no nonnumeric characters. Here's the reported error, if it helps: {}", x)
-    };
+    println!("Typical price of the add-on? (This is the nondiscounted price of the add-on, not the typical price of the associated product if one exists)");
+    let sticker_price = ask_money(&stdin, currency, &format!("the typical price of the add-on {}", name.trim()))?;
+
+    println!("What's the price of the add-on after discounts? (Usually, this is the amount that the product that you added this onto increased by)");
+    let actual_price = ask_money(&stdin, currency, &format!("the price after discounts of the add-on {}", name.trim()))?;
 
     let assoc_product:Option<Product>;
     println!("Does {} have an associated product? (Type \"Yes\" or \"No\")", name);
     loop {
-        let mut temp = "".to_string();
-        match stdin.read_line(&mut temp) {
-            Ok(_) => (),
-            Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-        }
+        let temp = read_answer(&stdin)?;
         if temp.trim() == "Yes" {
             println!("Please enter information about the associated product:\n");
-            assoc_product = Some(product_cli());
+            assoc_product = Some(product_cli(currency)?);
             break;
         }
         if temp.trim() == "No" {
@@ -319,36 +525,35 @@ fn add_on_cli() -> AddOn {
         println!("Please type \"Yes\" or \"No\"");
     }
 
-    AddOn {
+    Ok(AddOn {
         name,
         desc,
         sticker_price,
         actual_price,
+        category,
+        tags,
         assoc_product
-    }
+    })
 
 }
 
-impl ToString for AddOn {
-    fn to_string(&self) -> String {
+impl AddOn {
+    fn to_string_in(&self, currency: Currency) -> String {
         let mut temp = "Name: ".to_string();
         temp.push_str(&self.name);
         temp.push_str("\nDescription: ");
         temp.push_str(&self.desc);
-        temp.push_str("\nSticker Price: $");
-        temp.push_str(&(self.sticker_price/100).to_string());
-        temp.push('.');
-        temp.push_str(&(self.sticker_price%100).to_string());
+        push_category(&mut temp, &self.category, &self.tags);
+        temp.push_str("\nSticker Price: ");
+        temp.push_str(&self.sticker_price.to_string_in(currency));
         temp.push('\n');
-        temp.push_str("\nActual Price: $");
-        temp.push_str(&(self.actual_price/100).to_string());
-        temp.push('.');
-        temp.push_str(&(self.actual_price%100).to_string());
+        temp.push_str("\nActual Price: ");
+        temp.push_str(&self.actual_price.to_string_in(currency));
         match &self.assoc_product {
             None => (),
             Some(x) => {
                 temp.push_str("\nAssociated Product: {\n\n");
-                temp.push_str(&x.to_string());
+                temp.push_str(&x.to_string_in(currency));
                 temp.push_str("\n}");
             }
         }
@@ -356,6 +561,97 @@ impl ToString for AddOn {
     }
 }
 
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Placed,
+    Processing,
+    Shipped,
+    Delivered,
+    Cancelled,
+    Returned
+}
+
+const STATUSES: [Status; 6] = [Status::Placed, Status::Processing, Status::Shipped, Status::Delivered, Status::Cancelled, Status::Returned];
+
+impl Status {
+    fn name(self) -> &'static str {
+        match self {
+            Status::Placed => "placed",
+            Status::Processing => "processing",
+            Status::Shipped => "shipped",
+            Status::Delivered => "delivered",
+            Status::Cancelled => "cancelled",
+            Status::Returned => "returned"
+        }
+    }
+
+    // Orders only ever move forward: placed -> processing -> shipped -> delivered, where an order
+    // can be cancelled until it ships and returned once it has. Cancelled and returned are final.
+    fn can_move_to(self, next: Status) -> bool {
+        match next {
+            Status::Placed => false,
+            Status::Processing => self == Status::Placed,
+            Status::Shipped => matches!(self, Status::Placed | Status::Processing),
+            Status::Delivered => matches!(self, Status::Placed | Status::Processing | Status::Shipped),
+            Status::Cancelled => matches!(self, Status::Placed | Status::Processing),
+            Status::Returned => matches!(self, Status::Shipped | Status::Delivered)
+        }
+    }
+
+    // Whether an order with this status has left the vendor at some point
+    fn has_shipped(self) -> bool {
+        matches!(self, Status::Shipped | Status::Delivered | Status::Returned)
+    }
+}
+
+fn parse_status(input: &str, field: &str) -> Result<Status, Error> {
+    match STATUSES.iter().find(|x| x.name().eq_ignore_ascii_case(input.trim())) {
+        Some(x) => Ok(*x),
+        None => Err(Error::parse(field, input, format!("It should be one of {}.", status_names())))
+    }
+}
+
+// "\"placed\", \"processing\", ... or \"returned\"", for telling users what they can type
+fn status_names() -> String {
+    let names: Vec<String> = STATUSES.iter().map(|x| format!("\"{}\"", x.name())).collect();
+    format!("{} or {}", names[..names.len() - 1].join(", "), names[names.len() - 1])
+}
+
+#[derive(Encode, Decode, Debug, Clone)]
+struct StatusChange {
+    status: Status, // What the order became
+    date: Option<Date> // When it did (if known)
+}
+
+impl ToString for StatusChange {
+    fn to_string(&self) -> String {
+        match &self.date {
+            Some(x) => format!("{} on {}", self.status.name(), x.to_string()),
+            None => format!("{} (date unknown)", self.status.name())
+        }
+    }
+}
+
+// The statuses an order went through to end up at status, for when it's entered all at once.
+// Shipped orders are the only ones whose shipping date is asked about separately, since that's the
+// date everything else cares about.
+fn status_history(status: Status, date_shipped: Option<Date>, date: Option<Date>) -> Vec<StatusChange> {
+    let mut history: Vec<StatusChange> = vec!();
+    if status.has_shipped() {
+        history.push(StatusChange {
+            status: Status::Shipped,
+            date: date_shipped
+        });
+    }
+    if status != Status::Placed && status != Status::Shipped {
+        history.push(StatusChange {
+            status,
+            date
+        });
+    }
+    history
+}
+
 #[derive(Encode, Decode, Debug)]
 struct Order {
     // I was originally going to include a sticker_price field, which was the sum of the
@@ -366,262 +662,840 @@ struct Order {
     // So instead I'm just going to include the subtotal (which is the price after discounts before
     // shipping and taxes) and the total, which is the price actually paid.
     date_placed: Date,
-    date_shipped: Date,
-    subtotal: u64,
-    total: u64,
-    products: Vec<(Product, u64)>, // The u64 here represents the actual amount paid for that
-                                   // particular item in the order, different from the stored
-                                   // sticker price in that this tuple element should account for
-                                   // item-specific discounts, while the sticker price is merely
-                                   // the cost after add-ons
+    vendor: Option<usize>, // Where the order was placed, as an index into Ledger.vendors (if known)
+    status_history: Vec<StatusChange>, // Every status the order moved through after being placed,
+                                       // oldest first. Empty means it has only been placed so far
+    currency: Currency, // What subtotal, total and every price in products are in
+    subtotal: Money,
+    breakdown: Option<Breakdown>, // How subtotal turned into total. None for orders that were
+                                  // entered before it was asked for
+    total: Money,
+    products: Vec<OrderLine>,
+    discounts: Vec<Discount>, // Coupons and such that were taken off to get to subtotal
+    refunds: Vec<Refund>, // Money that came back after the order was paid for, oldest first
+    payments: Vec<Payment>, // What total was paid with, see payment.rs. Empty if that isn't known
+    tags: Vec<String>, // See category.rs
+    notes: String
+}
+
+// One of the top level products of an order
+#[derive(Encode, Decode, Debug)]
+struct OrderLine {
+    product: Product,
+    quantity: u64, // How many of product were bought, at least 1
+    unit_price: Money, // The actual price of one of them. Different from the stored sticker price
+                       // in that it accounts for item-specific discounts, while the sticker price
+                       // is merely the cost after add-ons
+    paid: Money, // What was paid for all of them
+    discount_reason: String, // Why it cost less than usual (e.g. "clearance"), "" if it didn't
     notes: String
 }
 /*
  * To put it simply, Product.base_price is the price of the product excluding add-ons,
  * Product.sticker_price is the price of the product including add-ons but excluding item-specific
- * discounts, such as a sale for that particular item, and the u64 stored in the tuple in the Vec
- * in the Order is the actual price for that particular item after sale discounts that affect
- * individual items. Then, Order.subtotal is the cost of your order after coupons and other "full
- * order" discounts (if you have no coupons, then it is more than likely that Order.subtotal will
- * just be the sum of the u64s in the Vec of tuples). Lastly, Order.total is the amount that you
+ * discounts, such as a sale for that particular item, and OrderLine.unit_price is the actual price
+ * for one of that particular item after sale discounts that affect individual items.
+ * OrderLine.paid is what was paid for the whole line, which is usually the unit price times the
+ * quantity, unless something like "3 for $10" made it cheaper. Then, Order.subtotal is the cost of
+ * your order after coupons and other "full order" discounts (if you have no coupons, then it is
+ * more than likely that Order.subtotal will just be the sum of the OrderLine.paid amounts). Those
+ * coupons are kept in Order.discounts, see discount.rs. Lastly, Order.total is the amount that you
  * actually paid for that order, after shipping and taxes and whatnot. The amount of money that was
  * removed from your bank account for that transaction is Order.total :3 makes sense?
+ * Returns and refunds that came later don't change Order.total, they're kept in Order.refunds (see
+ * refund.rs) and taken off by Order::net_total.
+ *
+ * When an order has a Breakdown, Order.subtotal + shipping + tax + fees + tip - credits has to come
+ * out to exactly Order.total. Without one, Order.total can't be less than Order.subtotal. See
+ * Order::check_total.
  */
 
+// Where the difference between Order.subtotal and Order.total went
+#[derive(Encode, Decode, Debug, Default, Clone, Copy)]
+struct Breakdown {
+    shipping: Money,
+    tax: Money, // Sales tax or VAT
+    tax_rate: Option<u32>, // In hundredths of a percent, e.g. 825 for 8.25% (if known)
+    fees: Money, // Handling and any other fees
+    tip: Money,
+    credits: Money // Paid with gift cards or store credit, so it doesn't count towards the total
+}
+
+impl Breakdown {
+    // What the total of an order with this breakdown and subtotal should be
+    fn total(&self, subtotal: Money) -> Result<Money, Error> {
+        let before_credits = Money::sum([subtotal, self.shipping, self.tax, self.fees, self.tip], "the subtotal, shipping, tax, fees and tip")?;
+        match before_credits.checked_sub(self.credits) {
+            Some(x) => Ok(x),
+            None => Err(Error::validation("the credits", "more was paid with gift cards and store credit than the order cost"))
+        }
+    }
+
+    fn to_string_in(self, currency: Currency) -> String {
+        let mut temp = "Shipping: ".to_string();
+        temp.push_str(&self.shipping.to_string_in(currency));
+        temp.push_str("\nTax: ");
+        temp.push_str(&self.tax.to_string_in(currency));
+        if let Some(x) = self.tax_rate {
+            temp.push_str(&format!(" ({})", format_percent(x)));
+        }
+        temp.push_str("\nFees: ");
+        temp.push_str(&self.fees.to_string_in(currency));
+        temp.push_str("\nTip: ");
+        temp.push_str(&self.tip.to_string_in(currency));
+        temp.push_str("\nGift cards and store credit: -");
+        temp.push_str(&self.credits.to_string_in(currency));
+        temp
+    }
+}
+
+// 825 -> "8.25%", 2000 -> "20%"
+fn format_percent(rate: u32) -> String {
+    match rate % 100 {
+        0 => format!("{}%", rate / 100),
+        x => format!("{}.{}%", rate / 100, format!("{:02}", x).trim_end_matches('0'))
+    }
+}
+
+// Parses a percentage like "8.25%" (the "%" is optional) into hundredths of a percent
+fn parse_percent(input: &str, field: &str) -> Result<u32, Error> {
+    let trimmed = input.trim();
+    let number = trimmed.strip_suffix('%').unwrap_or(trimmed).trim_end();
+    let (whole, fraction) = match number.split_once('.') {
+        Some((x, y)) => (x, y),
+        None => (number, "")
+    };
+    let well_formed = !whole.is_empty() && whole.len() <= 3 && whole.bytes().all(|x| x.is_ascii_digit()) && fraction.len() <= 2 && fraction.bytes().all(|x| x.is_ascii_digit()) && !number.ends_with('.');
+    if !well_formed {
+        return Err(Error::parse(field, input, "Write percentages with at most two decimal places, like \"8.25%\" or \"20\"."));
+    }
+    let rate = whole.parse::<u32>().unwrap_or(0) * 100 + format!("{:0<2}", fraction).parse::<u32>().unwrap_or(0);
+    if rate > 10000 {
+        return Err(Error::parse(field, input, "A percentage can't be more than 100%."));
+    }
+    Ok(rate)
+}
+
+// Everything that gets saved to a file
+#[derive(Encode, Decode, Debug)]
+struct Ledger {
+    orders: Vec<Order>,
+    vendors: Vec<Vendor>,
+    catalog: Vec<CatalogEntry>, // Products that can be copied into new orders, see catalog.rs
+    payment_methods: Vec<PaymentMethod>,
+    home_currency: Currency, // What reports convert amounts into, see rates.rs
+    rates: Vec<Rate> // Oldest first
+}
+
+impl Ledger {
+    fn new() -> Ledger {
+        Ledger {
+            orders: vec!(),
+            vendors: vec!(),
+            catalog: vec!(),
+            payment_methods: vec!(),
+            home_currency: money::USD,
+            rates: vec!()
+        }
+    }
+}
+
+impl Order {
+    // Makes sure the breakdown adds up to the total. Without one, nothing says where the
+    // difference went, but only gift cards and store credit could take the total below the subtotal.
+    fn check_total(&self) -> Result<(), Error> {
+        let breakdown = match &self.breakdown {
+            Some(x) => x,
+            None if self.total < self.subtotal => return Err(Error::validation("the total", format!("it's {}, which is less than the subtotal of {}. Only gift cards and store credit can do that, so give the order a breakdown with them in it", self.total.to_string_in(self.currency), self.subtotal.to_string_in(self.currency)))),
+            None => return Ok(())
+        };
+        let expected = breakdown.total(self.subtotal)?;
+        if expected != self.total {
+            return Err(Error::validation("the total", format!("the subtotal, shipping, tax, fees and tip minus gift cards and store credit come out to {}, but the total is {}", expected.to_string_in(self.currency), self.total.to_string_in(self.currency))));
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> Status {
+        match self.status_history.last() {
+            Some(x) => x.status,
+            None => Status::Placed
+        }
+    }
+
+    // What the order ended up costing once refunds came back, which is what reports add up
+    fn net_total(&self) -> Money {
+        refund::refunded(self).ok().and_then(|x| self.total.checked_sub(x)).unwrap_or_default()
+    }
+
+    fn date_shipped(&self) -> Option<Date> {
+        self.status_history.iter().find(|x| x.status == Status::Shipped).and_then(|x| x.date)
+    }
+}
+
 impl ToString for Order {
     fn to_string(&self) -> String {
         let mut temp = "Placed: ".to_string();
         temp.push_str(&self.date_placed.to_string());
+        if self.date_placed.is_valid() {
+            temp.push_str(&format!(" ({})", self.date_placed.weekday()));
+        }
         temp.push_str("\nShipped: ");
-        temp.push_str(&self.date_shipped.to_string());
-        temp.push_str("\nSubtotal: $");
-        temp.push_str(&(self.subtotal/100).to_string());
-        temp.push('.');
-        temp.push_str(&(self.subtotal%100).to_string());
-        temp.push_str("\nTotal: $");
-        temp.push_str(&(self.total/100).to_string());
-        temp.push('.');
-        temp.push_str(&(self.total%100).to_string());
+        match self.date_shipped() {
+            Some(x) => {
+                temp.push_str(&x.to_string());
+                // Orders saved before dates were validated might have dates that aren't on the calendar
+                if self.date_placed.is_valid() && x.is_valid() {
+                    temp.push_str(&format!(" ({} days after it was placed)", self.date_placed.days_until(&x)));
+                }
+            },
+            None if self.status().has_shipped() => temp.push_str("date unknown"),
+            None => temp.push_str("not yet")
+        }
+        temp.push_str("\nStatus: ");
+        match self.status_history.last() {
+            Some(x) => temp.push_str(&x.to_string()),
+            None => temp.push_str(Status::Placed.name())
+        }
+        push_category(&mut temp, "", &self.tags);
+        for x in &self.discounts {
+            temp.push_str("\nDiscount: ");
+            temp.push_str(&x.to_string_in(self.currency, &self.products));
+        }
+        temp.push_str("\nSubtotal: ");
+        temp.push_str(&self.subtotal.to_string_in(self.currency));
+        if let Some(x) = &self.breakdown {
+            temp.push('\n');
+            temp.push_str(&x.to_string_in(self.currency));
+        }
+        temp.push_str("\nTotal: ");
+        temp.push_str(&self.total.to_string_in(self.currency));
+        if let Ok((sales, discounts)) = discount::savings(self)
+            && let Some(x) = sales.checked_add(discounts)
+            && x != Money::default() {
+            temp.push_str(&format!("\nSaved: {} ({} off sticker prices, {} in discounts)", x.to_string_in(self.currency), sales.to_string_in(self.currency), discounts.to_string_in(self.currency)));
+        }
+        for x in &self.refunds {
+            temp.push_str("\nRefund: ");
+            temp.push_str(&x.to_string_in(self.currency, &self.products));
+        }
+        if !self.refunds.is_empty() {
+            temp.push_str("\nAfter refunds: ");
+            temp.push_str(&self.net_total().to_string_in(self.currency));
+        }
         temp.push_str("\nProducts: {\n\n");
-        for (index, (product, price)) in self.products.iter().enumerate() {
+        for (index, line) in self.products.iter().enumerate() {
             temp.push_str(&index.to_string());
-            temp.push_str(". $");
-            temp.push_str(&(price/100).to_string());
-            temp.push('.');
-            temp.push_str(&(price%100).to_string());
+            temp.push_str(". ");
+            temp.push_str(&line.to_string_in(self.currency));
+            temp.push('\n');
+        }
+        temp
+    }
+}
+
+impl OrderLine {
+    // What quantity times unit_price comes to, or as close as Money can count
+    fn full_price(&self) -> Money {
+        self.unit_price.checked_mul(self.quantity).unwrap_or(Money::from_minor(u64::MAX))
+    }
+
+    fn to_string_in(&self, currency: Currency) -> String {
+        let mut temp = "".to_string();
+        if self.quantity != 1 {
+            temp.push_str(&format!("{} x {} = ", self.quantity, self.unit_price.to_string_in(currency)));
+        }
+        temp.push_str(&self.paid.to_string_in(currency));
+        if self.full_price() != self.paid {
+            temp.push_str(&format!(" (instead of {})", self.full_price().to_string_in(currency)));
+        }
+        temp.push('\n');
+        if !self.discount_reason.is_empty() {
+            temp.push_str("Discount: ");
+            temp.push_str(&self.discount_reason);
             temp.push('\n');
-            temp.push_str(&product.to_string());
+        }
+        if !self.notes.is_empty() {
+            temp.push_str("Line notes: ");
+            temp.push_str(&self.notes);
             temp.push('\n');
         }
+        temp.push_str(&self.product.to_string_in(currency));
         temp
     }
 }
 
-fn order_cli() -> Order {
+// Asks where an order is at, for when it's entered all at once
+fn status_cli() -> Result<Vec<StatusChange>, Error> {
     let stdin = io::stdin();
-    println!("Welcome to the order creator!");
-    println!("Please type the date you placed this order.");
-    println!("Use \"MM/DD/YYYY\" format. Note that malformed input may crash the program, but illegal dates will not be rejected.");
-    let date_placed = date_cli();
-    println!("Please type the date that this order was shipped.");
-    println!("Use \"MM/DD/YYYY\" format. Note that malformed input may crash the program, but illegal dates will not be rejected.");
-    let date_shipped = date_cli();
-    println!("Enter the subtotal (this should be the price of the order after coupons and sale discounts but before shipping and taxes) in cents.");
-    let mut temp = "".to_string();
-    match stdin.read_line(&mut temp) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
+    println!("What's the status of this order now? (Type {})", status_names());
+    let status = ask(&stdin, |x| parse_status(x, "the status of the order"))?;
+    let mut date_shipped: Option<Date> = None;
+    if status.has_shipped() {
+        println!("Please type the date that this order was shipped, or leave it blank if you don't know.");
+        println!("Use \"MM/DD/YYYY\" or \"YYYY-MM-DD\" format.");
+        date_shipped = optional_date_cli("the date the order was shipped")?;
     }
-    let subtotal = match temp.trim().parse::<u64>() {
-        Ok(x) => x,
-        Err(x) => panic!("Failed to parse your input as a number. Your input should be an unsigned integer, no negative sign or decimal, and certainly no nonnumeric characters. Here's the reported error, if it helps: {}", x)
-    };
+    let mut date: Option<Date> = None;
+    if status != Status::Placed && status != Status::Shipped {
+        println!("Please type the date that this order became {}, or leave it blank if you don't know.", status.name());
+        println!("Use \"MM/DD/YYYY\" or \"YYYY-MM-DD\" format.");
+        date = optional_date_cli(&format!("the date the order became {}", status.name()))?;
+    }
+    Ok(status_history(status, date_shipped, date))
+}
 
-    println!("Enter the total (this should be the amount of money you actually paid) in cents.");
-    let mut temp = "".to_string();
-    match stdin.read_line(&mut temp) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
+// Quantities have to be at least 1, a product that wasn't bought isn't in the order
+fn parse_quantity(input: &str, field: &str) -> Result<u64, Error> {
+    match parse_count(input, field)? {
+        0 => Err(Error::validation(field, "it has to be at least 1")),
+        x => Ok(x)
     }
-    let total = match temp.trim().parse::<u64>() {
-        Ok(x) => x,
-        Err(x) => panic!("Failed to parse your input as a number. Your input should be an unsigned integer, no negative sign or decimal, and certainly no nonnumeric characters. Here's the reported error, if it helps: {}", x)
+}
+
+// One entry of Order.products
+fn order_line_cli(currency: Currency, catalog: &[CatalogEntry]) -> Result<OrderLine, Error> {
+    let stdin = io::stdin();
+    let temp_product = match catalog::pick_product_cli(&stdin, catalog, currency)? {
+        Some(x) => x,
+        None => product_cli(currency)?
     };
-    println!("How many products did you order?");
-    let mut temp = "".to_string();
-    match stdin.read_line(&mut temp) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
+    let name = temp_product.name.trim().to_string();
+    println!("How many of {} did you buy? (Leave it blank for 1)", name);
+    let quantity = ask(&stdin, |x| match x.trim() {
+        "" => Ok(1),
+        y => parse_quantity(y, &format!("the quantity of {}", name))
+    })?;
+    println!("What was the cost of one {} accounting for all add-ons and sales?", name);
+    let unit_price = ask_money(&stdin, currency, &format!("the cost of {}", name))?;
+    let mut paid = unit_price;
+    if quantity > 1 {
+        let expected = match unit_price.checked_mul(quantity) {
+            Some(x) => x,
+            None => return Err(Error::validation(&format!("the cost of {}", name), "the cost of all of them is more money than purchase_tracker can count"))
+        };
+        println!("What did you pay for all {} of them? (Leave it blank for {}, but it might be less if there was a deal like \"3 for $10\")", quantity, expected.to_string_in(currency));
+        paid = ask(&stdin, |x| match x.trim() {
+            "" => Ok(expected),
+            y => Money::parse(y, currency, &format!("the cost of all of the {}", name))
+        })?;
     }
-    let number_of_products = match temp.trim().parse::<u64>() {
-        Ok(x) => x,
-        Err(x) => panic!("Failed to parse your input as a number. Your input should be an unsigned integer, no negative sign or decimal, and certainly no nonnumeric characters. Here's the reported error, if it helps: {}", x)
-    };
+    let mut discount_reason = "".to_string();
+    if let Some(x) = temp_product.sticker_price.checked_mul(quantity)
+        && paid < x {
+        println!("That's less than the sticker price of {}. Why was it cheaper? (e.g. \"clearance\", or leave it blank to skip this)", x.to_string_in(currency));
+        discount_reason = read_answer(&stdin)?.trim().to_string();
+    }
+    println!("Any notes about {} in this order? (Leave it blank if there aren't any)", name);
+    let notes = read_answer(&stdin)?.trim().to_string();
+    Ok(OrderLine {
+        product: temp_product,
+        quantity,
+        unit_price,
+        paid,
+        discount_reason,
+        notes
+    })
+}
+
+// Asks for everything that went into the total on top of the subtotal
+fn breakdown_cli(currency: Currency) -> Result<Breakdown, Error> {
+    let stdin = io::stdin();
+    println!("For each of the following, leave the answer blank if there wasn't any.");
+    println!("How much was shipping?");
+    let shipping = ask_money_or_zero(&stdin, currency, "the shipping")?;
+    println!("How much was sales tax or VAT?");
+    let tax = ask_money_or_zero(&stdin, currency, "the tax")?;
+    let mut tax_rate: Option<u32> = None;
+    if tax != Money::default() {
+        println!("What was the tax rate? (e.g. \"8.25%\", or leave it blank if you don't know)");
+        tax_rate = ask(&stdin, |x| match x.trim() {
+            "" => Ok(None),
+            y => parse_percent(y, "the tax rate").map(Some)
+        })?;
+    }
+    println!("How much were handling or other fees?");
+    let fees = ask_money_or_zero(&stdin, currency, "the fees")?;
+    println!("How much did you tip?");
+    let tip = ask_money_or_zero(&stdin, currency, "the tip")?;
+    println!("How much of it did you pay with gift cards or store credit?");
+    let credits = ask_money_or_zero(&stdin, currency, "the gift cards and store credit")?;
+    Ok(Breakdown {
+        shipping,
+        tax,
+        tax_rate,
+        fees,
+        tip,
+        credits
+    })
+}
 
-    let mut products: Vec<(Product, u64)> = vec!();
+// ledger is where the order is going to end up. Vendors that are entered for the first time are
+// added to it.
+fn order_cli(ledger: &mut Ledger) -> Result<Order, Error> {
+    let home = ledger.home_currency;
+    let stdin = io::stdin();
+    println!("Welcome to the order creator!");
+    println!("(Type \"{}\" in answer to any question to throw away this order without saving anything.)", ABORT);
+    println!("Please type the date you placed this order.");
+    println!("Use \"MM/DD/YYYY\" or \"YYYY-MM-DD\" format.");
+    let date_placed = date_cli("the date the order was placed")?;
+    let vendor = vendor::pick_vendor_cli(&mut ledger.vendors)?;
+    let status_history = status_cli()?;
+    println!("What currency did you pay in? (Type a code like \"EUR\" or \"JPY\", or leave it blank for {})", home.code());
+    let currency = ask(&stdin, |x| match x.trim() {
+        "" => Ok(home),
+        y => Currency::parse(y, "the currency of the order")
+    })?;
+    println!("Enter the subtotal (this should be the price of the order after coupons and sale discounts but before shipping and taxes). Amounts can be written like \"{}\", \"1,299\" or (in the smallest unit of {}) \"1299c\".", Money::from_minor(1205).to_string_in(currency), currency.code());
+    let subtotal = ask_money(&stdin, currency, "the subtotal")?;
+    let breakdown = breakdown_cli(currency)?;
+    let expected = breakdown.total(subtotal)?;
+
+    println!("Enter the total (this should be the amount of money you actually paid). Leave it blank for {}, which is what everything above adds up to.", expected.to_string_in(currency));
+    let total = ask(&stdin, |x| {
+        let total = match x.trim() {
+            "" => expected,
+            y => Money::parse(y, currency, "the total")?
+        };
+        match total == expected {
+            true => Ok(total),
+            false => Err(Error::validation("the total", format!("everything above adds up to {}, not {}. If one of the amounts above was wrong, type the total anyway as {} and fix it afterwards with 'purchase_tracker edit'", expected.to_string_in(currency), total.to_string_in(currency), expected.to_string_in(currency))))
+        }
+    })?;
+    let payments = payment::split_payment_cli(&stdin, &mut ledger.payment_methods, currency, total)?;
+    println!("How many products did you order?");
+    let number_of_products = ask_count(&stdin, "the number of products")?;
+
+    let mut products: Vec<OrderLine> = vec!();
     for i in 0..number_of_products {
         println!("Product {}", i);
-        let temp_product = product_cli();
-        println!("What was the cost (in cents) of this product accounting for all add-ons and sales?");
-        let mut temp = "".to_string();
-        match stdin.read_line(&mut temp) {
-            Ok(_) => (),
-            Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-        }
-        let temp_price = match temp.trim().parse::<u64>() {
-            Ok(x) => x,
-            Err(x) => panic!("Failed to parse your input as a number. Your input should be an unsigned integer, no negative sign or decimal, and certainly no nonnumeric characters. Here's the reported error, if it helps: {}", x)
-        };
-        products.push((temp_product, temp_price));
+        let line = order_line_cli(currency, &ledger.catalog)?;
+        catalog::offer_to_save_cli(&stdin, &mut ledger.catalog, &line.product, currency)?;
+        products.push(line);
     }
 
+    let discounts = discount::discounts_cli(currency, &products)?;
+
+    println!("Any tags for this order? (Separated by commas, like \"gift, birthday\". Its products get them too, unless they have tags of their own. Leave it blank if there aren't any)");
+    let tags = category::parse_tags(&read_answer(&stdin)?);
+
     println!("Any other notes for your order?");
-    let mut notes = "".to_string();
-    match stdin.read_line(&mut notes) {
-        Ok(_) => (),
-        Err(x) => panic!("Reading from the Standard Input failed. I have no idea why that happened, but here's the error if it's helpful to you: {}", x)
-    }
+    let notes = read_answer(&stdin)?;
 
-    Order {
+    Ok(Order {
         date_placed,
-        date_shipped,
+        vendor,
+        status_history,
+        currency,
         subtotal,
+        breakdown: Some(breakdown),
         total,
         products,
+        discounts,
+        refunds: vec!(),
+        payments,
+        tags,
         notes
+    })
+}
+
+
+// Turns the ORDER argument of a command into an index into orders
+fn parse_order_index(input: &str, orders: &[Order], path: &Path) -> Result<usize, Error> {
+    match input.trim().parse::<usize>() {
+        Ok(x) if x < orders.len() => Ok(x),
+        Ok(_) => Err(Error::validation("ORDER", format!("{} only has {} orders, numbered starting from 0, so there's no order {}", path.display(), orders.len(), input.trim()))),
+        Err(x) => Err(Error::parse("an order number", input, x))
     }
 }
 
+// purchase_tracker update-status [FILE] [ORDER] [STATUS] [--date DATE]
+fn update_status_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = args::Args::parse(raw_args, &["date"], &[])?;
+    let (path, index, status) = match args.positional.as_slice() {
+        [x, y, z] => (Path::new(x), y, z),
+        _ => return Err(Error::usage("purchase_tracker update-status [FILE] [ORDER] [STATUS] [--date DATE]"))
+    };
+    let mut ledger = storage::load(path)?;
+    let index = parse_order_index(index, &ledger.orders, path)?;
+    let status = parse_status(status, "STATUS")?;
+    let date = match args.value("date") {
+        Some(x) => Some(parse_date(x, "--date")?),
+        None => None
+    };
+
+    let order = &mut ledger.orders[index];
+    let before = order.status();
+    if !before.can_move_to(status) {
+        return Err(Error::validation(&format!("order {}", index), format!("it's {}, and orders can't go from {} to {}", before.name(), before.name(), status.name())));
+    }
+    if let Some(x) = date
+        && x < order.date_placed {
+        return Err(Error::validation("--date", format!("{} is before the order was placed on {}", x.to_string(), order.date_placed.to_string())));
+    }
+    // Delivering an order implies it shipped at some point, even if nobody wrote down when
+    if status == Status::Delivered && !before.has_shipped() {
+        order.status_history.push(StatusChange {
+            status: Status::Shipped,
+            date: None
+        });
+    }
+    order.status_history.push(StatusChange {
+        status,
+        date
+    });
+    let after = order.status_history[order.status_history.len() - 1].to_string();
+
+    storage::save(path, &ledger)?;
+    println!("Order {} went from {} to {}.", index, before.name(), after);
+    Ok(())
+}
 
 fn main() {
+    match run() {
+        Ok(_) => (),
+        Err(Error::Aborted) => {
+            println!("{}", Error::Aborted.to_string());
+            process::exit(Error::Aborted.exit_code());
+        },
+        Err(x) => {
+            eprintln!("{}", x.to_string());
+            process::exit(x.exit_code());
+        }
+    }
+}
+
+fn run() -> Result<(), Error> {
     // Get the commandline arguments
     let args: Vec<String> = args().collect();
 
     if args.len() == 1 {
-        panic!("Usage: purchase_tracker [OUTFILE] | purchase_tracker [INFILE] [OUTFILE]\nRun 'purchase_tracker --help' for more information");
+        return Err(Error::usage("purchase_tracker [OUTFILE] [--strict] | purchase_tracker [INFILE] [OUTFILE] [--strict]"));
     }
 
     // Help message
     if args[1] == "--help" {
-        println!("Usage: purchase_tracker [OUTFILE] | purchase_tracker [INFILE] [OUTFILE]");
+        println!("Usage: purchase_tracker [OUTFILE] [--strict]");
+        println!("       purchase_tracker [INFILE] [OUTFILE] [--strict]");
+        println!("       purchase_tracker update [FILE] [--strict]");
+        println!("       purchase_tracker restore [FILE] [SNAPSHOT]");
+        println!("       purchase_tracker retention [FILE] [--keep-last N] [--keep-daily N] [--keep-weekly N]");
+        println!("       purchase_tracker add [FILE] --placed DATE [--shipped DATE] [--status STATUS] [--status-date DATE] [--vendor NAME] --subtotal AMOUNT --total AMOUNT [--paid-with METHOD] [--tags TAGS] [--notes TEXT] [--product-file PRODUCTS.toml] [--strict]");
+        println!("       purchase_tracker update-status [FILE] [ORDER] [STATUS] [--date DATE]");
+        println!("       purchase_tracker refund [FILE] [ORDER] [--date DATE] [--amount AMOUNT] [--product N] [--returned N] [--restocking-fee AMOUNT] [--reason TEXT]");
+        println!("       purchase_tracker rates [FILE]");
+        println!("       purchase_tracker rates import [FILE] [RATES.csv]");
+        println!("       purchase_tracker rates home [FILE] [CURRENCY]");
+        println!("       purchase_tracker list [FILE] [--in CURRENCY] [--by-vendor] [--category CATEGORY] [--tag TAG]");
+        println!("       purchase_tracker show [FILE] [ORDER] [--category CATEGORY] [--tag TAG]");
+        println!("       purchase_tracker search [FILE] [TEXT] [--by-vendor] [--category CATEGORY] [--tag TAG]");
+        println!("       purchase_tracker catalog [FILE] [--all] [--category CATEGORY] [--tag TAG]");
+        println!("       purchase_tracker catalog show [FILE] [ENTRY]");
+        println!("       purchase_tracker catalog add [FILE] [--currency CODE]");
+        println!("       purchase_tracker catalog edit [FILE] [ENTRY]");
+        println!("       purchase_tracker catalog retire [FILE] [ENTRY]");
+        println!("       purchase_tracker catalog unretire [FILE] [ENTRY]");
+        println!("       purchase_tracker vendors [FILE] [--category CATEGORY] [--tag TAG]");
+        println!("       purchase_tracker vendors add [FILE] [NAME] [--website URL] [--contact TEXT] [--notes TEXT]");
+        println!("       purchase_tracker vendors set [FILE] [VENDOR] [--name NAME] [--website URL] [--contact TEXT] [--notes TEXT]");
+        println!("       purchase_tracker payments [FILE]");
+        println!("       purchase_tracker payments add [FILE] [NAME] [--type TYPE] [--last-four DIGITS]");
+        println!("       purchase_tracker payments set [FILE] [METHOD] [--name NAME] [--type TYPE] [--last-four DIGITS]");
+        println!("       purchase_tracker payments report [FILE] [--in CURRENCY] [--category CATEGORY] [--tag TAG]");
+        println!("       purchase_tracker report [FILE] [--by day|week|month|quarter|year] [--split vendor|category|tag] [--in CURRENCY] [--category CATEGORY] [--tag TAG]");
+        println!("       purchase_tracker savings [FILE] [--from DATE] [--to DATE] [--in CURRENCY] [--category CATEGORY] [--tag TAG]");
+        println!("       purchase_tracker check [FILE]");
+        println!("       purchase_tracker export [FILE] [OUT.csv] [--format csv] [--rows orders|lines] [--from DATE] [--to DATE] [--category CATEGORY] [--tag TAG]");
+        println!("       purchase_tracker categories [FILE]");
+        println!("       purchase_tracker categories rename [FILE] [OLD] [NEW]");
+        println!("       purchase_tracker edit [FILE] [ORDER]");
+        println!("       purchase_tracker delete [FILE] [ORDER] [--yes]");
         println!("In the first case (NEW mode), where only one file argument is passed, a new list of orders (initially empty, but populated with contents provided during the usage of that particular session of purchase_tracker) is saved to OUTFILE. If OUTFILE already exists, purchase_tracker will err.");
         println!("In the second case (UPDATE mode), a list of orders is read from INFILE and (after any new orders are added) saved to OUTFILE. INFILE must already exist, and OUTFILE must not; purchase_tracker will err if INFILE doesn't exist or OUTFILE does already.");
-        println!("Saving the updated orders list to the same file as it was read from (i.e. setting INFILE and OUTFILE to be the same file) is unsupported. Doing so may result in the file being truncated, deleting all orders.");
+        println!("Saving the updated orders list to the same file as it was read from (i.e. setting INFILE and OUTFILE to be the same file) is unsupported in UPDATE mode. Doing so may result in the file being truncated, deleting all orders. Use IN-PLACE mode for that instead.");
+        println!("In the third case (IN-PLACE mode), a list of orders is read from FILE and (after a new order is added) saved back to FILE. The updated list is written to a temporary file next to FILE first and only then moved over FILE, so FILE is never left half-written, even if the program crashes or fails to save.");
         println!("Example: purchase_tracker file.txt new_file.txt");
         println!("Will read 'file.txt' and print all orders that were saved to that file (will fail if 'file.txt' does not exist). You will then be brought to a CLI where you can construct a new order and add it to the list. The resulting updated order list is saved to 'new_file.txt' before quitting the program.");
-        println!("NOTE: For all intents and purposes, any usage of UPDATE mode will fail if the input file was not created with this program.\nOnly try to read a file with '-r' if that file was created using '-w' during a past run of purchase_tracker. When using UPDATE mode, ensure that INFILE was created with a previous run of this program.");
         println!("If you have no files that were created with a previous run of the program, run the program in NEW mode to create one.");
-        return;
+        println!("If you mistype an answer in the order creator, it explains what was wrong and asks the question again. Type \"abort\" in answer to any question to throw the order away and quit without saving anything.");
+        println!("Every file written by purchase_tracker is tagged with the version of the file format it uses. Files written by older versions of purchase_tracker (including ones from before the tag existed) are upgraded automatically when they're read, and saved in the current format the next time they're written.");
+        println!("Example: purchase_tracker update file.txt");
+        println!("Will read 'file.txt', print all orders that were saved to it, bring you to the order creator, and save the updated list back to 'file.txt'.");
+        println!("The add command adds an order to FILE (creating FILE if it doesn't exist yet) without asking any questions, so it can be used from scripts. Dates can be written as \"MM/DD/YYYY\" or \"YYYY-MM-DD\", and amounts of money as \"12.05\", \"$12\", \"1,299.99\" or (in cents) \"1299c\". Amounts are always shown as \"$12.05\".");
+        println!("The products of the order, including their sub-products and add-ons, are read from the TOML file given to --product-file. That file may also set placed, shipped, subtotal, total and notes, in which case the matching flags can be left out. See the top of src/spec.rs for what a product file looks like.");
+        println!("Every product in an order has a quantity (1 unless said otherwise), the price of one of them, and what was paid for all of them together, which is normally the price of one times the quantity but can be less for deals like \"3 for $10\". Orders saved before quantities existed bought one of each product. In a product file these are quantity, unit_price and paid.");
+        println!("Products can also say why they were cheaper than their sticker price (the order creator asks when they were) and have notes of their own. In a product file these are discount_reason and notes, next to paid.");
+        println!("Coupons, loyalty rewards, price matches and other discounts are recorded on the order after its products: where each came from, its coupon code, a percentage (\"15%\") or an amount, and whether it was for the whole order or only some products. Product files list them under [[discounts]], see the top of src/spec.rs. Showing an order also says how much it saved altogether, off the sticker prices of its products and through its discounts.");
+        println!("Example: purchase_tracker add file.txt --placed 2026-10-01 --shipped 2026-10-03 --subtotal 19.99 --total 21.49 --product-file items.toml");
+        println!("The list, show and search commands only read FILE, they never change it. list prints one line per order (its number, the date it was placed, its total, how many products it has and its status), show prints everything about order number ORDER, and search prints the orders where TEXT appears (ignoring case) in the name or description of any product, sub-product or associated product, the name of any add-on, or the order's notes.");
+        println!("Every order has a status: placed, processing, shipped, delivered, cancelled or returned. The add command takes it from --status (or assumes \"shipped\" if --shipped is given and \"placed\" otherwise), and --status-date says when the order became delivered, cancelled, returned or processing.");
+        println!("Run 'purchase_tracker update-status FILE ORDER STATUS' to move order number ORDER (as listed when FILE is read) forward to STATUS, optionally saying when that happened with --date. Orders go placed -> processing -> shipped -> delivered, can be cancelled until they ship and can be returned once they have.");
+        println!("Run 'purchase_tracker refund FILE ORDER' to record money that came back for order number ORDER: a return, a partial refund, a refund of the shipping... You'll be asked about it, or it can all be given as flags: --date and --amount (what came back, after any restocking fee), plus --product N if it was for product number N of the order, --returned with how many of that product were sent back, --restocking-fee and --reason. The total of the order stays what was paid at the time, but list, search and every total they print count what the order cost after refunds.");
+        println!("Run 'purchase_tracker edit FILE ORDER' to change order number ORDER. You'll get a numbered menu of its fields: type a number to change that field (leaving an answer blank keeps what was there), and \"done\" to go back. The products, sub-products and add-ons of the order can be changed the same way, and \"add\" or \"remove N\" adds or removes one of them. A product of the order can't be removed while a discount or refund is for it, and the discounts and refunds for the products after it are renumbered along with them. The order is saved when you type \"done\" at the top menu, and \"abort\" throws all the changes away.");
+        println!("Run 'purchase_tracker delete FILE ORDER' to remove order number ORDER from FILE. You'll be asked to confirm first, unless --yes is given. Either way, the orders after it are renumbered.");
+        println!("The order creator also asks how the subtotal turned into the total: shipping, sales tax or VAT (and its rate), handling fees, tip, and how much was paid with gift cards or store credit. Subtotal + shipping + tax + fees + tip - gift cards and store credit has to come out to exactly the total. The add command takes these as --shipping, --tax, --tax-rate, --fees, --tip and --credits, and orders added without any of them (or saved before they were asked for) simply don't have a breakdown. Those can't have a total below their subtotal, since only gift cards and store credit could explain that.");
+        println!("Every order has a currency (asked for by the order creator, or given to add with --currency), and all of its amounts are in it. Orders from before currencies existed are in USD. Each file also has a home currency, USD unless changed with 'purchase_tracker rates home FILE CURRENCY'.");
+        println!("Totals in different currencies are converted into the home currency (or the one given to --in) using exchange rates stored in the file, at the rate from the day each order was placed. Rates are imported from a local CSV file with 'purchase_tracker rates import FILE RATES.csv', where every line looks like \"2026-10-01,EUR,USD,1.0834\" (one EUR was worth 1.0834 USD that day). 'purchase_tracker rates FILE' lists the rates that are already there.");
+        println!("Every file keeps a list of vendors (the shops orders are placed with), each with a name and optionally a website, contact details and notes. The order creator asks where an order was placed: type part of the vendor's name to pick from the closest matches, or add a new vendor. The add command takes --vendor NAME (or \"vendor\" in the product file), adding the vendor if there's none by that name yet.");
+        println!("Run 'purchase_tracker vendors FILE' to list the vendors of FILE, 'purchase_tracker vendors add FILE NAME' to add one, and 'purchase_tracker vendors set FILE VENDOR' (VENDOR being its number or name) to change one. The list and search commands show the vendor of every order, search also looks at vendor names, and --by-vendor groups their output by vendor with a total for each.");
+        println!("Every file also keeps a list of payment methods: a nickname like \"Visa\", what kind it is (credit card, debit card, bank account, gift card, cash or other) and optionally its last four digits. The order creator asks what each order was paid with, and an order can be split across several methods as long as they add up to its total. The add command takes --paid-with NAME for an order paid entirely with one method, or [[payments]] in the product file for a split. A gift card that's a payment method is part of the total, so don't also count it as a credit.");
+        println!("Run 'purchase_tracker payments FILE' to list the payment methods, 'purchase_tracker payments add FILE NAME' and 'purchase_tracker payments set FILE METHOD' to add or change one, and 'purchase_tracker payments report FILE' to see how much went out through each of them every month (converted like list does, --in works here too). Refunds are taken off in the month they came back, split between the methods an order was paid with in proportion to what each paid (or evenly, when the order cost nothing).");
+        println!("Products and add-ons can have a category, written from the most general part to the most specific like \"Hobby > Fencing > Blades\", and products, add-ons and orders can have free-form tags like \"gift\". Sub-products, add-ons and associated products without a category or tags of their own get the ones of what they're part of, and the products of an order get the order's tags unless they have their own. The add command takes the order's tags as --tags \"gift, birthday\", and product files have category and tags on products and add-ons and tags at the top level.");
+        println!("Run 'purchase_tracker report FILE' to see how much was spent every month: how many orders were placed, their subtotals and totals added up, the average total, and how much more or less was spent than the month before. --by day, week (starting on Monday), quarter or year groups the orders that way instead, and --split vendor, category or tag makes a separate table for each one. When splitting by category or tag, the subtotal and total of an order are split between its products in proportion to what was paid for each of them. Weeks, months, quarters and years nothing was spent in still get a row, but days don't. Totals are converted like list does, and --in works here too. When some order can't be converted, every currency gets tables of its own instead.");
+        println!("Run 'purchase_tracker savings FILE' to see how much was saved per vendor, and how: sales (products that cost less than their sticker price), add-ons that cost less than their sticker price, coupons and other discounts on the order (or the subtotal coming out below what the products cost), and bundles (the sticker prices of a bundle's sub-products added up, minus the bundle's base price). --from and --to only count orders placed on or between those dates.");
+        println!("list, search, report, savings, export and 'payments report' take --category CATEGORY (which includes everything under it) and --tag TAG to only look at orders that have something in that category or with that tag. vendors counts only those orders and leaves out vendors that have none, catalog only lists the entries that are in that category or have that tag, and show points out which products of the order are. Run 'purchase_tracker categories FILE' to see the categories in FILE, and 'purchase_tracker categories rename FILE OLD NEW' to rename OLD (and everything under it) throughout the orders and the catalog. Renaming it to a category that already exists merges the two.");
+        println!("Products you buy again and again can be kept in the file's catalog, sub-products and add-ons included. When the catalog isn't empty, the order creator asks whether each product is from it (type part of its name to pick one), copies it into the order and lets you change whatever was different this time before asking what you paid. Products that were typed in from scratch can be saved to the catalog right away. In a product file, write catalog = \"NAME\" (plus paid) instead of the product's fields.");
+        println!("Run 'purchase_tracker catalog FILE' to list the catalog, 'purchase_tracker catalog show FILE ENTRY' to see everything about one entry (ENTRY being its number or name), 'purchase_tracker catalog add FILE' to add one, and 'purchase_tracker catalog edit FILE ENTRY' to change one. Orders keep their own copy, so editing the catalog never changes past orders. 'purchase_tracker catalog retire FILE ENTRY' hides an entry that isn't sold any more (list it again with --all, and bring it back with 'catalog unretire').");
+        println!("Run 'purchase_tracker check FILE' to look for orders that break the rules prices and dates normally follow: a subtotal above what the products cost, a total below the subtotal (unless gift cards or store credit explain it), a sticker price below the base price anywhere in a product, an add-on that cost more than its sticker price, a quantity of 0, or a status change (like shipping) before the order was placed. Each problem is listed with where it is in the order, like \"products[0].items[2]\". check exits with code 8 when it finds anything.");
+        println!("add and the order creator (in NEW, UPDATE and IN-PLACE mode) point out these problems in the order being added, and save it anyway. Give them --strict to refuse to save it instead. edit doesn't look for them, so run check after editing an order.");
+        println!("Run 'purchase_tracker export FILE OUT.csv' to write the orders of FILE to a new CSV file for spreadsheets, one row per order. With --rows lines it has one row per product instead, sub-products, add-ons and associated products included, each with a path saying where it is in its order and the path of what it's part of. Amounts are written as plain numbers in the currency of their order, and only orders placed between --from and --to are exported when those are given. OUT.csv must not exist yet.");
+        println!("Whenever purchase_tracker overwrites a file, it first copies the old contents into a '.purchase_tracker_backups' directory next to that file.");
+        println!("Run 'purchase_tracker restore FILE' to list the backups of FILE, and 'purchase_tracker restore FILE SNAPSHOT' to put one of them back (you'll be shown how many orders the backup holds compared to FILE and asked to confirm first).");
+        println!("Old backups are thinned out after every write. By default, the newest 10 backups are kept, plus the newest backup of each of the last 7 days and of each of the last 4 weeks that have one. Run 'purchase_tracker retention FILE' to see the policy for FILE, and pass any of --keep-last, --keep-daily or --keep-weekly to change it (0 turns that rule off; the newest backup is always kept).");
+        println!("Exit codes:");
+        println!("  0  Success");
+        println!("  2  The command line didn't make sense");
+        println!("  3  A file (or the standard input) couldn't be read or written");
+        println!("  4  Refused to overwrite a file that already exists");
+        println!("  5  A file couldn't be decoded as a list of orders");
+        println!("  6  The orders couldn't be encoded to be saved");
+        println!("  7  Some input couldn't be parsed (a date, an amount, a product file...)");
+        println!("  8  Some input was parsed, but doesn't make sense as part of an order");
+        println!("  9  Everything that was being entered was thrown away by typing \"abort\"");
+        return Ok(());
     }
 
-    // Configuration for bincode
-    let config = config::standard();
+    if args[1] == "restore" {
+        return backup::restore_cli(&args[2..]);
+    }
 
-    match args.len() {
-        1 => {
-            unreachable!("Usage: purchase_tracker [OUTFILE] | purchase_tracker [INFILE] [OUTFILE]\nRun 'purchase_tracker --help' for more information");
-        },
-        2 => { // NEW mode
-            let mut outfile = match File::create_new(&args[1]) { // Panic if the file already exists.
-                Ok(x) => x,
-                Err(x) => panic!("The file {} probably already exists (or you don't have permission to create it) (or the parent directory of the file you entered doesn't exist yet). Run purchase_tracker --help for more information.\nHere's the error that was received upon trying to create the file: {}", args[1], x),
-            };
-            let the_output: Vec<Order> = vec!(order_cli());
-            match bincode::encode_into_std_write(the_output, &mut outfile, config) {
-                Ok(_) => {
-                    println!("Success! The encoded order is saved to {}. In the future, run purchase_tracker in UPDATE mode using that file as the INFILE in order to add new orders.", args[1])
-                },
-                Err(x) => {
-                    panic!("Well, this is awkward...\nDespite the fact that {} was able to be opened successfully, the program failed to save your order to the file. Here's the error that was reported: {}", args[1], x);
-                }
+    if args[1] == "retention" {
+        return backup::retention_cli(&args[2..]);
+    }
+
+    if args[1] == "add" {
+        return spec::add_cli(&args[2..]);
+    }
+
+    if args[1] == "list" {
+        return browse::list_cli(&args[2..]);
+    }
+
+    if args[1] == "show" {
+        return browse::show_cli(&args[2..]);
+    }
+
+    if args[1] == "search" {
+        return browse::search_cli(&args[2..]);
+    }
+
+    if args[1] == "edit" {
+        return edit::edit_cli(&args[2..]);
+    }
+
+    if args[1] == "delete" {
+        return edit::delete_cli(&args[2..]);
+    }
+
+    if args[1] == "rates" {
+        return rates::rates_cli(&args[2..]);
+    }
+
+    if args[1] == "catalog" {
+        return catalog::catalog_cli(&args[2..]);
+    }
+
+    if args[1] == "vendors" {
+        return vendor::vendors_cli(&args[2..]);
+    }
+
+    if args[1] == "report" {
+        return report::report_cli(&args[2..]);
+    }
+
+    if args[1] == "savings" {
+        return savings::savings_cli(&args[2..]);
+    }
+
+    if args[1] == "check" {
+        return fsck::check_cli(&args[2..]);
+    }
+
+    if args[1] == "export" {
+        return export::export_cli(&args[2..]);
+    }
+
+    if args[1] == "categories" {
+        return category::categories_cli(&args[2..]);
+    }
+
+    if args[1] == "payments" {
+        return payment::payments_cli(&args[2..]);
+    }
+
+    if args[1] == "refund" {
+        return refund::refund_cli(&args[2..]);
+    }
+
+    if args[1] == "update-status" {
+        return update_status_cli(&args[2..]);
+    }
+
+    // IN-PLACE mode
+    if args[1] == "update" {
+        let flags = args::Args::parse(&args[2..], &[], &["strict"])?;
+        let path = match flags.positional.as_slice() {
+            [x] => Path::new(x),
+            _ => return Err(Error::usage("purchase_tracker update [FILE] [--strict]"))
+        };
+        let mut the_output: Ledger = storage::load(path)?;
+
+        println!("Your orders:");
+        for (index, order) in the_output.orders.iter().enumerate() {
+            println!("{}.\n{}", index, order.to_string())
+        }
+
+        let order = order_cli(&mut the_output)?;
+        fsck::check_new_order(&order, flags.switch("strict"))?;
+        the_output.orders.push(order);
+        storage::save(path, &the_output)?;
+        println!("Success! The encoded order is saved to {}. In the future, run purchase_tracker update {} again in order to add new orders.", path.display(), path.display());
+        println!("If you're reading this, that hopefully means that purchase_tracker ran successfully :3 please tell Nyl anything about the app that you'd like, he always wants to hear about your experience <3");
+        return Ok(());
+    }
+
+    let flags = args::Args::parse(&args[1..], &[], &["strict"])?;
+    let strict = flags.switch("strict");
+    match flags.positional.as_slice() {
+        [outfile] => { // NEW mode
+            let path = Path::new(outfile);
+            let mut outfile = create_new(path)?;
+            let mut the_output = Ledger::new();
+            match order_cli(&mut the_output).and_then(|x| fsck::check_new_order(&x, strict).map(|_| x)) {
+                Ok(x) => the_output.orders.push(x),
+                Err(x) => return Err(discard_new(path, x))
             };
+            storage::write_new(&mut outfile, path, &the_output)?;
+            println!("Success! The encoded order is saved to {}. In the future, run purchase_tracker in UPDATE mode using that file as the INFILE in order to add new orders.", path.display());
         },
-        3 => { // UPDATE mode
-            let mut outfile = match File::create_new(&args[2]) { // Panic if the file already exists.
+        [infile, outfile] => { // UPDATE mode
+            let path = Path::new(outfile);
+            let mut outfile = create_new(path)?;
+            let mut the_output: Ledger = match storage::load(Path::new(infile)) {
                 Ok(x) => x,
-                Err(x) => panic!("The file {} probably already exists (or you don't have permission to create it) (or the parent directory of the file you entered doesn't exist yet). Run purchase_tracker --help for more information.\nHere's the error that was received upon trying to create the file: {}", args[2], x),
-            };
-            let mut infile = match File::open(&args[1]) {
-                Ok(x) => x,
-                Err(x) => panic!("The file {} probably doesn't exist (or you don't have permission to read it). Run purchase_tracker --help for more information.\nHere's the error that was received upon trying to create the file: {}", args[1], x),
-            };
-            let mut the_output: Vec<Order> = match bincode::decode_from_std_read(&mut infile, config) {
-                Ok(x) => x,
-                Err(x) => panic!("Failed to decode from {}.\nThis probably means that the file wasn't created with a previous run of purchase_tracker.\nHere's the error that was reported: {}", args[1], x)
+                Err(x) => return Err(discard_new(path, x))
             };
 
             println!("Your orders:");
-            for (index, order) in the_output.iter().enumerate() {
+            for (index, order) in the_output.orders.iter().enumerate() {
                 println!("{}.\n{}", index, order.to_string())
             }
 
-            the_output.push(order_cli());
-            match bincode::encode_into_std_write(the_output, &mut outfile, config) {
-                Ok(_) => {
-                    println!("Success! The encoded order is saved to {}. In the future, run purchase_tracker in UPDATE mode using that file as the INFILE in order to add new orders.", args[2])
-                },
-                Err(x) => {
-                    panic!("Well, this is awkward...\nDespite the fact that {} was able to be opened successfully, the program failed to save your order to the file. Here's the error that was reported: {}", args[2], x);
-                }
-            };
+            match order_cli(&mut the_output).and_then(|x| fsck::check_new_order(&x, strict).map(|_| x)) {
+                Ok(x) => the_output.orders.push(x),
+                Err(x) => return Err(discard_new(path, x))
+            }
+            storage::write_new(&mut outfile, path, &the_output)?;
+            println!("Success! The encoded order is saved to {}. In the future, run purchase_tracker in UPDATE mode using that file as the INFILE in order to add new orders.", path.display());
         },
-        _ => panic!("Usage: purchase_tracker [OUTFILE] | purchase_tracker [INFILE] [OUTFILE]\nRun 'purchase_tracker --help' for more information")
+        _ => return Err(Error::usage("purchase_tracker [OUTFILE] [--strict] | purchase_tracker [INFILE] [OUTFILE] [--strict]"))
     };
 
     println!("If you're reading this, that hopefully means that purchase_tracker ran successfully :3 please tell Nyl anything about the app that you'd like, he always wants to hear about your experience <3");
+    Ok(())
+}
 
-//                Ok(_) => {println!("Success! The encoded order is saved to {}. In the future, run purchase_tracker in UPDATE mode using that file as the INFILE in order to add new orders.", args[1])},
-    /*
-    // Check for malformed input
-    if args.len() < 3 {
-        panic!("Usage: purchase_tracker [OUTFILE] | purchase_tracker [INFILE] [OUTFILE]\nRun 'purchase_tracker --help' for more information");
+// Creates path, refusing to touch it if it already exists
+fn create_new(path: &Path) -> Result<File, Error> {
+    match File::create_new(path) {
+        Ok(x) => Ok(x),
+        Err(x) if x.kind() == io::ErrorKind::AlreadyExists => Err(Error::FileExists(path.to_path_buf())),
+        Err(x) => Err(Error::io("creating", path, x))
     }
+}
 
-    // Check that the user provided a valid read/write mode
-    let write_mode:bool = if args[1] == "-r" {
-        false
-    } else if args[1] == "-w" {
-        true 
-    } else {
-        panic!("Usage: purchase_tracker [OUTFILE] | purchase_tracker [INFILE] [OUTFILE]\nRun 'purchase_tracker --help' for more information");
-    };
+// Removes a file made by create_new that never got any orders written to it, so that a failed or
+// aborted run doesn't leave an empty OUTFILE behind
+fn discard_new(path: &Path, error: Error) -> Error {
+    let _ = fs::remove_file(path);
+    error
+}
 
-    // If we're in read mode, the file we're trying to read must exist
-    if !write_mode {
-        match std::fs::exists(&args[2]) {
-            Ok(true) => (),
-            Ok(false) => {
-                panic!("File {} does not exist", args[2]);
-            },
-            Err(x) => {
-                panic!("Failed to verify that file {} exists\nIf it helps, the error in question was: {}", args[2], x);
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: u64, month: u8, day: u8) -> Date {
+        Date::new(year, month, day).unwrap()
     }
-    /*
-    } else if write_mode { // If we're in write mode, the file should be backed up if it exists
-        match std::fs::exists(&args[2]) {
-            Ok(true) => {
-                let unix_timestamp = SystemTime::now().duration_since(UNIX_EPOCH).expect("Failed to fetch system time. You should check your system's clock\nIf you're wondering why I need the system clock, it's for giving the backup file a unique name");
-                let backup_name = format!(".backup_{}_{}", args[2], unix_timestamp.as_secs());
-                match File::create_new(&backup_name) {
-                    Err(x) => {
-                        panic!("Failed to create the backup file.\nThis error should never happen in practice, as it means that a file named {} already exists\nTry rerunning the program to see if that fixes it\nIf it helps, the error in question was: {}", backup_name ,x);
-                    },
-                    _ => todo!()
-                }
-            },
-            Ok(false) => todo!(),
-            Err(x) => todo!()
+
+    #[test]
+    fn leap_years() {
+        assert!(Date::is_leap_year(2000));
+        assert!(!Date::is_leap_year(1900));
+        assert!(!Date::is_leap_year(2100));
+        assert!(Date::is_leap_year(2024));
+        assert!(!Date::is_leap_year(2025));
+        assert!(Date::new(2024, 2, 29).is_some());
+        assert!(Date::new(2000, 2, 29).is_some());
+        assert!(Date::new(1900, 2, 29).is_none());
+        assert!(Date::new(2025, 2, 29).is_none());
+        assert_eq!(date(2000, 2, 28).days_until(&date(2000, 3, 1)), 2);
+        assert_eq!(date(1900, 2, 28).days_until(&date(1900, 3, 1)), 1);
+    }
+
+    #[test]
+    fn days_since_epoch_round_trips() {
+        assert_eq!(date(1970, 1, 1).days_since_epoch(), 0);
+        assert_eq!(date(1969, 12, 31).days_since_epoch(), -1);
+        assert_eq!(date(2000, 3, 1).days_since_epoch(), 11017);
+        // Every day from before 1900 to after 2100, so every kind of year and month boundary
+        let mut days = date(1899, 12, 1).days_since_epoch();
+        let mut previous = Date::from_days_since_epoch(days - 1);
+        while days <= date(2101, 1, 31).days_since_epoch() {
+            let day = Date::from_days_since_epoch(days);
+            assert!(day.is_valid(), "{} isn't on the calendar", day.to_iso_string());
+            assert!(day > previous);
+            assert_eq!(day.days_since_epoch(), days);
+            assert_eq!(Date::from_days_since_epoch(day.days_since_epoch()), day);
+            previous = day;
+            days += 1;
+        }
+        for day in [date(1, 1, 1), date(2024, 12, 31), date(2025, 1, 1), date(9999, 12, 31)] {
+            assert_eq!(Date::from_days_since_epoch(day.days_since_epoch()), day);
         }
     }
-    } else {
-        unreachable!("Program panicked with unknown error\nTell Nyl about this, and include 'URID1' in your error log");
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(date(1970, 1, 1).weekday(), "Thursday");
+        assert_eq!(date(2000, 1, 1).weekday(), "Saturday");
+        assert_eq!(date(2026, 10, 18).weekday(), "Sunday");
     }
-    */
 
-    // Set up the standard bincode config
-    let config = config::standard();
-    */
+    #[test]
+    fn both_ways_of_writing_dates() {
+        assert_eq!(parse_date("10/25/2025", "the date").unwrap(), date(2025, 10, 25));
+        assert_eq!(parse_date("2025-10-25", "the date").unwrap(), date(2025, 10, 25));
+        assert_eq!(parse_date(" 1/2/2026 ", "the date").unwrap(), date(2026, 1, 2));
+        assert_eq!(parse_date("2026-01-02", "the date").unwrap(), date(2026, 1, 2));
+        assert_eq!(parse_date("2/29/2024", "the date").unwrap(), date(2024, 2, 29));
+        assert_eq!(date(2026, 1, 2).to_string(), "1/2/2026");
+        assert_eq!(date(2026, 1, 2).to_iso_string(), "2026-01-02");
+    }
+
+    #[test]
+    fn dates_that_arent_on_the_calendar() {
+        for input in ["2/30/2025", "13/01/2025", "2025-02-30", "2025-13-01", "2/29/2025", "2/29/1900", "4/31/2025", "0/1/2025", "1/0/2025", "1/1/0", "1/1/10000"] {
+            assert!(parse_date(input, "the date").is_err(), "{} was accepted", input);
+        }
+    }
 
+    #[test]
+    fn malformed_dates() {
+        for input in ["", "10/25", "10-25-2025-1", "2025/10", "Oct 25 2025", "10/25/twenty", "-1/1/2025"] {
+            assert!(parse_date(input, "the date").is_err(), "{} was accepted", input);
+        }
+    }
 }
@@ -0,0 +1,312 @@
+use bincode::{Decode, Encode};
+
+use crate::error::Error;
+
+/*
+ * An amount of money, counted in the smallest unit of its currency (cents for USD, EUR and GBP,
+ * whole yen for JPY) so that there's never any rounding. It's saved exactly like the bare u64
+ * prices that came before it. Money doesn't know its own currency, every order has one for all of
+ * its amounts (see Order.currency), so showing or asking for an amount always takes a Currency.
+ *
+ * Amounts are shown like "$12.05", "€12.05", "¥1299" or "12.05 CHF", and anything that asks for
+ * one understands all of these (here for USD):
+ *
+ *   12.05   $12.05   $12   12.5   1,299.99   12.05 USD   1299c
+ *
+ * i.e. whole units (with an optional symbol or code, optional thousands separators and as many
+ * decimal places as the currency has), or a whole number of the smallest unit followed by "c".
+ */
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Money(u64);
+
+impl Money {
+    // units are the smallest unit of whatever currency this is in, e.g. cents
+    pub fn from_minor(units: u64) -> Money {
+        Money(units)
+    }
+
+    pub fn minor(self) -> u64 {
+        self.0
+    }
+
+    // None if the result wouldn't fit
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    // None if the result would be negative
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.0.checked_sub(other.0).map(Money)
+    }
+
+    // None if the result wouldn't fit
+    pub fn checked_mul(self, times: u64) -> Option<Money> {
+        self.0.checked_mul(times).map(Money)
+    }
+
+    // Adds up amounts, refusing to wrap around. field says what's being added up, for the error.
+    pub fn sum(amounts: impl IntoIterator<Item = Money>, field: &str) -> Result<Money, Error> {
+        let mut total = Money(0);
+        for amount in amounts {
+            total = match total.checked_add(amount) {
+                Some(x) => x,
+                None => return Err(Error::validation(field, "it adds up to more money than purchase_tracker can count"))
+            };
+        }
+        Ok(total)
+    }
+
+    // field names what the amount is for, e.g. "--subtotal"
+    pub fn parse(input: &str, currency: Currency, field: &str) -> Result<Money, Error> {
+        let mut trimmed = input.trim();
+        if trimmed.len() > 3 && trimmed.is_char_boundary(trimmed.len() - 3) && trimmed[trimmed.len() - 3..].eq_ignore_ascii_case(currency.code()) {
+            trimmed = trimmed[..trimmed.len() - 3].trim_end();
+        }
+        if let Some(minor) = trimmed.strip_suffix('c') {
+            return match parse_digits(minor) {
+                Ok(x) => Ok(Money(x)),
+                Err(Some(x)) => Err(Error::parse(field, input, x)),
+                Err(None) => Err(Error::parse(field, input, examples(currency)))
+            };
+        }
+        let units = match currency.symbol() {
+            Some(x) => trimmed.strip_prefix(x).unwrap_or(trimmed),
+            None => trimmed
+        };
+        let (whole, fraction) = match units.split_once('.') {
+            Some((x, y)) => (x, y),
+            None => (units, "")
+        };
+        let whole = match parse_digits(whole) {
+            Ok(x) => x,
+            Err(Some(x)) => return Err(Error::parse(field, input, x)),
+            Err(None) => return Err(Error::parse(field, input, examples(currency)))
+        };
+        if !fraction.bytes().all(|x| x.is_ascii_digit()) || units.ends_with('.') {
+            return Err(Error::parse(field, input, examples(currency)));
+        }
+        let exponent = currency.exponent();
+        if fraction.len() > exponent as usize {
+            return Err(Error::parse(field, input, match exponent {
+                0 => format!("{} doesn't have anything smaller than a whole unit, so an amount in it can't have a decimal point.", currency.code()),
+                x => format!("An amount of {} can have at most {} digits after the decimal point.", currency.code(), x)
+            }));
+        }
+        let fraction = match fraction {
+            "" => 0,
+            x => x.parse::<u64>().unwrap_or(0) * 10u64.pow(exponent - x.len() as u32)
+        };
+        match whole.checked_mul(10u64.pow(exponent)).and_then(|x| x.checked_add(fraction)) {
+            Some(x) => Ok(Money(x)),
+            None => Err(Error::parse(field, input, TOO_MUCH))
+        }
+    }
+
+    // Just the number, like "12.05", for files that other programs read
+    pub fn to_number_in(self, currency: Currency) -> String {
+        match currency.exponent() {
+            0 => self.0.to_string(),
+            x => {
+                let unit = 10u64.pow(x);
+                format!("{}.{:0width$}", self.0 / unit, self.0 % unit, width = x as usize)
+            }
+        }
+    }
+
+    // Like "$12.05" or "12.05 CHF"
+    pub fn to_string_in(self, currency: Currency) -> String {
+        let number = self.to_number_in(currency);
+        match currency.symbol() {
+            Some(x) => format!("{}{}", x, number),
+            None => format!("{} {}", number, currency.code())
+        }
+    }
+}
+
+fn examples(currency: Currency) -> String {
+    let number = match currency.exponent() {
+        0 => "1,299".to_string(),
+        x => format!("1,299.{}", "9".repeat(x as usize))
+    };
+    format!("Write amounts of {} like \"{}\" or \"{}\", or as a whole number of its smallest unit followed by \"c\", like \"1299c\".", currency.code(), Money(1205).to_string_in(currency), number)
+}
+
+const TOO_MUCH: &str = "That's more money than purchase_tracker can count.";
+
+// A whole number, optionally with commas between groups of three digits. Fails with Some(reason)
+// if the number is fine but too big, and None if it isn't a number at all.
+fn parse_digits(input: &str) -> Result<u64, Option<&'static str>> {
+    let groups: Vec<&str> = input.split(',').collect();
+    let well_formed = groups.iter().enumerate().all(|(index, group)| {
+        let length_ok = match index {
+            0 => !group.is_empty() && (groups.len() == 1 || group.len() <= 3),
+            _ => group.len() == 3
+        };
+        length_ok && group.bytes().all(|x| x.is_ascii_digit())
+    });
+    if !well_formed {
+        return Err(None);
+    }
+    groups.concat().parse::<u64>().map_err(|_| Some(TOO_MUCH))
+}
+
+// (ISO 4217 code, digits after the decimal point, symbol if it's unambiguous enough to show)
+static CURRENCIES: [(&str, u32, Option<&str>); 24] = [
+    ("USD", 2, Some("$")),
+    ("EUR", 2, Some("€")),
+    ("GBP", 2, Some("£")),
+    ("JPY", 0, Some("¥")),
+    ("AUD", 2, None),
+    ("BRL", 2, None),
+    ("CAD", 2, None),
+    ("CHF", 2, None),
+    ("CNY", 2, None),
+    ("CZK", 2, None),
+    ("DKK", 2, None),
+    ("HKD", 2, None),
+    ("HUF", 2, None),
+    ("INR", 2, Some("₹")),
+    ("ISK", 0, None),
+    ("KRW", 0, Some("₩")),
+    ("KWD", 3, None),
+    ("MXN", 2, None),
+    ("NOK", 2, None),
+    ("NZD", 2, None),
+    ("PLN", 2, None),
+    ("SEK", 2, None),
+    ("SGD", 2, None),
+    ("ZAR", 2, None)
+];
+
+// One of CURRENCIES, saved as its code
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Currency {
+    code: [u8; 3]
+}
+
+// Every order saved before currencies existed was in dollars
+pub const USD: Currency = Currency { code: *b"USD" };
+
+impl Currency {
+    // For format upgrades, which build currencies out of their frozen copies
+    pub fn upgraded(code: [u8; 3]) -> Currency {
+        Currency {
+            code
+        }
+    }
+
+    pub fn parse(input: &str, field: &str) -> Result<Currency, Error> {
+        let code = input.trim().to_ascii_uppercase();
+        match CURRENCIES.iter().find(|(x, _, _)| *x == code) {
+            Some((x, _, _)) => Ok(Currency { code: x.as_bytes().try_into().unwrap_or(USD.code) }),
+            None => Err(Error::parse(field, input, format!("purchase_tracker knows these currencies: {}", CURRENCIES.iter().map(|(x, _, _)| *x).collect::<Vec<&str>>().join(", "))))
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        std::str::from_utf8(&self.code).unwrap_or("???")
+    }
+
+    fn info(&self) -> Option<&'static (&'static str, u32, Option<&'static str>)> {
+        CURRENCIES.iter().find(|(x, _, _)| x.as_bytes() == self.code)
+    }
+
+    // How many digits there are after the decimal point, e.g. 2 for USD and 0 for JPY
+    pub fn exponent(&self) -> u32 {
+        self.info().map_or(2, |(_, x, _)| *x)
+    }
+
+    fn symbol(&self) -> Option<&'static str> {
+        self.info().and_then(|(_, _, x)| *x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn currency(code: &str) -> Currency {
+        Currency::parse(code, "the currency").unwrap()
+    }
+
+    fn usd(input: &str) -> Result<u64, Error> {
+        Money::parse(input, USD, "the amount").map(Money::minor)
+    }
+
+    #[test]
+    fn amounts_in_dollars() {
+        assert_eq!(usd("12.05").unwrap(), 1205);
+        assert_eq!(usd("12.5").unwrap(), 1250);
+        assert_eq!(usd("$12").unwrap(), 1200);
+        assert_eq!(usd("$12.05").unwrap(), 1205);
+        assert_eq!(usd("1,299.99").unwrap(), 129999);
+        assert_eq!(usd("12.05 USD").unwrap(), 1205);
+        assert_eq!(usd(" 12.05 usd ").unwrap(), 1205);
+        assert_eq!(usd("1299c").unwrap(), 1299);
+        assert_eq!(usd("0").unwrap(), 0);
+    }
+
+    #[test]
+    fn malformed_amounts() {
+        for input in ["", "$", "12.", ".5", "1,29", ",299", "1,2999", "12.0.5", "twelve", "1299 c", "€12"] {
+            assert!(usd(input).is_err(), "{:?} was accepted", input);
+        }
+    }
+
+    #[test]
+    fn too_many_decimals() {
+        assert!(usd("12.055").is_err());
+        assert!(usd("0.001").is_err());
+        assert_eq!(Money::parse("1.005", currency("KWD"), "the amount").unwrap().minor(), 1005);
+    }
+
+    #[test]
+    fn negative_amounts() {
+        assert!(usd("-5").is_err());
+        assert!(usd("-$5.00").is_err());
+        assert!(usd("$-5.00").is_err());
+        assert!(usd("-500c").is_err());
+    }
+
+    #[test]
+    fn yen_has_no_decimals() {
+        let jpy = currency("jpy");
+        assert_eq!(jpy.exponent(), 0);
+        assert_eq!(Money::parse("1299", jpy, "the amount").unwrap().minor(), 1299);
+        assert_eq!(Money::parse("¥1,299", jpy, "the amount").unwrap().minor(), 1299);
+        assert_eq!(Money::parse("1299 JPY", jpy, "the amount").unwrap().minor(), 1299);
+        assert!(Money::parse("12.5", jpy, "the amount").is_err());
+        assert!(Money::parse("12.", jpy, "the amount").is_err());
+        assert_eq!(Money::from_minor(1299).to_string_in(jpy), "¥1299");
+    }
+
+    #[test]
+    fn amounts_near_the_limit() {
+        assert_eq!(usd("184467440737095516.15").unwrap(), u64::MAX);
+        assert_eq!(usd("184,467,440,737,095,516.15").unwrap(), u64::MAX);
+        assert_eq!(usd("18446744073709551615c").unwrap(), u64::MAX);
+        assert!(usd("184467440737095516.16").is_err());
+        assert!(usd("184467440737095517").is_err());
+        assert!(usd("18446744073709551616c").is_err());
+        assert!(usd("99999999999999999999999").is_err());
+        assert!(Money::from_minor(u64::MAX).checked_add(Money::from_minor(1)).is_none());
+        assert!(Money::sum([Money::from_minor(u64::MAX), Money::from_minor(1)], "the amounts").is_err());
+    }
+
+    #[test]
+    fn shown_amounts_parse_back() {
+        for code in ["USD", "EUR", "GBP", "JPY", "CHF", "KWD", "INR"] {
+            let currency = currency(code);
+            for minor in [0, 1, 5, 10, 1205, 129999, u64::MAX] {
+                let money = Money::from_minor(minor);
+                let shown = money.to_string_in(currency);
+                assert_eq!(Money::parse(&shown, currency, "the amount").unwrap(), money, "{} didn't parse back", shown);
+                assert_eq!(Money::parse(&money.to_number_in(currency), currency, "the amount").unwrap(), money);
+            }
+        }
+        assert_eq!(Money::from_minor(1205).to_string_in(USD), "$12.05");
+        assert_eq!(Money::from_minor(5).to_string_in(USD), "$0.05");
+        assert_eq!(Money::from_minor(1205).to_string_in(currency("CHF")), "12.05 CHF");
+        assert_eq!(Money::from_minor(1005).to_string_in(currency("KWD")), "1.005 KWD");
+    }
+}
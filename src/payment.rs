@@ -0,0 +1,452 @@
+use bincode::{Decode, Encode};
+use std::io;
+use std::path::Path;
+
+use crate::args::Args;
+use crate::category::Filter;
+use crate::error::Error;
+use crate::money::{Currency, Money};
+use crate::report;
+use crate::{ask, ask_yes_no, rates, storage, Date, Ledger, Order};
+
+/*
+ * The cards, accounts and so on that orders get paid with. Like vendors, every payment method is
+ * stored once in Ledger.payment_methods and never removed from it, since orders point at them by
+ * their index there. An order records what it paid with in Order.payments, which can be split
+ * across several methods (a gift card plus a credit card, say), and when it says anything at all
+ * the amounts have to add up to exactly Order.total.
+ *
+ * Breakdown.credits is for gift cards and store credit that don't count towards the total. A gift
+ * card that's listed as a payment method instead is part of the total, so don't count it twice.
+ */
+#[derive(Encode, Decode, Debug)]
+pub struct PaymentMethod {
+    pub name: String, // A nickname, like "Visa" or "Work Amex"
+    pub last_four: String, // The last four digits of the card or account, "" if not known
+    pub kind: Kind
+}
+
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Credit,
+    Debit,
+    Bank,
+    GiftCard,
+    Cash,
+    Other
+}
+
+const KINDS: [Kind; 6] = [Kind::Credit, Kind::Debit, Kind::Bank, Kind::GiftCard, Kind::Cash, Kind::Other];
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Credit => "credit card",
+            Kind::Debit => "debit card",
+            Kind::Bank => "bank account",
+            Kind::GiftCard => "gift card",
+            Kind::Cash => "cash",
+            Kind::Other => "other"
+        }
+    }
+}
+
+// "credit" works as well as "credit card", and so on
+fn parse_kind(input: &str, field: &str) -> Result<Kind, Error> {
+    let input = input.trim().to_lowercase();
+    match KINDS.iter().find(|x| x.name() == input || x.name().split(' ').next() == Some(input.as_str())) {
+        Some(x) => Ok(*x),
+        None => Err(Error::parse(field, &input, format!("The kinds of payment methods are {}.", KINDS.iter().map(|x| format!("\"{}\"", x.name())).collect::<Vec<String>>().join(", "))))
+    }
+}
+
+// Blank, or exactly four digits
+fn parse_last_four(input: &str, field: &str) -> Result<String, Error> {
+    let input = input.trim();
+    match input.is_empty() || (input.len() == 4 && input.bytes().all(|x| x.is_ascii_digit())) {
+        true => Ok(input.to_string()),
+        false => Err(Error::parse(field, input, "Type the last four digits of the card or account number, like \"1234\"."))
+    }
+}
+
+impl ToString for PaymentMethod {
+    // Like "Visa (credit card ending in 1234)"
+    fn to_string(&self) -> String {
+        match self.last_four.is_empty() {
+            true => format!("{} ({})", self.name, self.kind.name()),
+            false => format!("{} ({} ending in {})", self.name, self.kind.name(), self.last_four)
+        }
+    }
+}
+
+// Part of what an order was paid with
+#[derive(Encode, Decode, Debug, Clone)]
+pub struct Payment {
+    pub method: usize, // Index into Ledger.payment_methods
+    pub amount: Money // In the currency of the order
+}
+
+// The payment method with exactly this name, ignoring case
+pub fn find(methods: &[PaymentMethod], name: &str) -> Option<usize> {
+    methods.iter().position(|x| x.name.eq_ignore_ascii_case(name.trim()))
+}
+
+pub fn method_name(methods: &[PaymentMethod], index: usize) -> &str {
+    match methods.get(index) {
+        Some(x) => &x.name,
+        None => "(unknown payment method)"
+    }
+}
+
+// Like "Visa $30.00, Gift card $10.00", for showing an order
+pub fn payments_to_string(order: &Order, methods: &[PaymentMethod]) -> String {
+    match order.payments.is_empty() {
+        true => "not recorded".to_string(),
+        false => order.payments.iter().map(|x| format!("{} {}", method_name(methods, x.method), x.amount.to_string_in(order.currency))).collect::<Vec<String>>().join(", ")
+    }
+}
+
+// Payments have to be made with methods that exist, and add up to the total
+pub fn check(order: &Order, methods: &[PaymentMethod]) -> Result<(), Error> {
+    if order.payments.is_empty() {
+        return Ok(());
+    }
+    if let Some((index, x)) = order.payments.iter().enumerate().find(|(_, x)| x.method >= methods.len()) {
+        return Err(Error::validation(&format!("payments[{}]", index), format!("it's made with payment method {}, but there are only {}", x.method, methods.len())));
+    }
+    let paid = Money::sum(order.payments.iter().map(|x| x.amount), "the payments")?;
+    match paid == order.total {
+        true => Ok(()),
+        false => Err(Error::validation("the payments", format!("they add up to {}, but the total is {}", paid.to_string_in(order.currency), order.total.to_string_in(order.currency))))
+    }
+}
+
+// How the refunds of an order went back to its payment methods, as (method, date, amount). Refunds
+// are split between the methods in proportion to what each one paid, with whatever doesn't divide
+// evenly going to the one that paid the most. An order that cost nothing can still get a refund
+// (of a deposit, say), and then it's split evenly between the methods, since none of them paid more
+// than the others. Orders without payments give nothing, report counts their refunds on their own.
+fn refunds_by_method(order: &Order) -> Vec<(usize, Date, Money)> {
+    let weights: Vec<u64> = order.payments.iter().map(|x| x.amount.minor()).collect();
+    let mut split: Vec<(usize, Date, Money)> = vec!();
+    for refund in &order.refunds {
+        for (payment, share) in order.payments.iter().zip(report::split(refund.amount, &weights)) {
+            split.push((payment.method, refund.date, share));
+        }
+    }
+    split
+}
+
+// Asks about one payment method called name
+fn new_method_cli(stdin: &io::Stdin, name: &str) -> Result<PaymentMethod, Error> {
+    println!("What kind of payment method is {}? (Type {})", name, KINDS.iter().map(|x| format!("\"{}\"", x.name())).collect::<Vec<String>>().join(", "));
+    let kind = ask(stdin, |x| parse_kind(x, "the kind of payment method"))?;
+    println!("What are the last four digits of {}? (Leave it blank if it doesn't have any)", name);
+    let last_four = ask(stdin, |x| parse_last_four(x, "the last four digits"))?;
+    Ok(PaymentMethod {
+        name: name.to_string(),
+        last_four,
+        kind
+    })
+}
+
+// Asks which payment method something was paid with, offering the ones that are already known.
+// New ones are added to methods. None if the question was skipped.
+fn pick_method_cli(stdin: &io::Stdin, methods: &mut Vec<PaymentMethod>) -> Result<Option<usize>, Error> {
+    for (index, method) in methods.iter().enumerate() {
+        println!("  {}. {}", index, method.to_string());
+    }
+    match methods.is_empty() {
+        true => println!("Type the name of the card or account (like \"Visa\"), or leave it blank to skip this."),
+        false => println!("Type one of the numbers above, the name of a new card or account, or leave it blank to skip this.")
+    }
+    // A number has to be one of the methods above, a card or account can't be called "5"
+    let answer = ask(stdin, |x| match x.trim().parse::<usize>() {
+        Ok(y) if y < methods.len() => Ok(y.to_string()),
+        Ok(y) => Err(Error::validation("the payment method", match methods.is_empty() {
+            true => format!("there aren't any to pick from yet, so there's no method {}. Type the name of the card or account instead", y),
+            false => format!("there's no method {}, pick a number from 0 to {} or type the name of a new card or account", y, methods.len() - 1)
+        })),
+        Err(_) => Ok(x.trim().to_string())
+    })?;
+    if answer.is_empty() {
+        return Ok(None);
+    }
+    if let Ok(x) = answer.parse::<usize>() {
+        return Ok(Some(x));
+    }
+    if let Some(x) = find(methods, &answer) {
+        return Ok(Some(x));
+    }
+    methods.push(new_method_cli(stdin, &answer)?);
+    Ok(Some(methods.len() - 1))
+}
+
+// Asks what an order costing total was paid with, one method at a time until all of total is
+// accounted for. New payment methods are added to methods. Empty if the question was skipped.
+pub fn split_payment_cli(stdin: &io::Stdin, methods: &mut Vec<PaymentMethod>, currency: Currency, total: Money) -> Result<Vec<Payment>, Error> {
+    let mut payments: Vec<Payment> = vec!();
+    let mut left = total;
+    println!("What did you pay with?");
+    loop {
+        let method = match pick_method_cli(stdin, methods)? {
+            Some(x) => x,
+            None if payments.is_empty() => return Ok(payments),
+            None => {
+                println!("The payments have to add up to the total, and {} of it hasn't been paid with anything yet.", left.to_string_in(currency));
+                continue;
+            }
+        };
+        println!("How much did you pay with {}? (Leave it blank for everything that's left, {})", methods[method].name, left.to_string_in(currency));
+        let amount = ask(stdin, |x| {
+            let amount = match x.trim() {
+                "" => left,
+                y => Money::parse(y, currency, "the payment")?
+            };
+            match amount <= left {
+                true => Ok(amount),
+                false => Err(Error::validation("the payment", format!("only {} of the total is left to pay", left.to_string_in(currency))))
+            }
+        })?;
+        payments.push(Payment {
+            method,
+            amount
+        });
+        left = left.checked_sub(amount).unwrap_or_default();
+        if left == Money::default() {
+            return Ok(payments);
+        }
+        println!("{} of the total is left. What else did you pay with?", left.to_string_in(currency));
+    }
+}
+
+// Turns the METHOD argument of a command (its number or its name) into an index into methods
+fn parse_method(input: &str, methods: &[PaymentMethod], path: &Path) -> Result<usize, Error> {
+    if let Ok(x) = input.trim().parse::<usize>() {
+        return match x < methods.len() {
+            true => Ok(x),
+            false => Err(Error::validation("METHOD", format!("{} only has {} payment methods, numbered starting from 0, so there's no payment method {}", path.display(), methods.len(), x)))
+        };
+    }
+    match find(methods, input) {
+        Some(x) => Ok(x),
+        None => Err(Error::validation("METHOD", format!("{} doesn't have a payment method called \"{}\". Run 'purchase_tracker payments {}' to see the ones it has", path.display(), input.trim(), path.display())))
+    }
+}
+
+// paid - refunded, which can come out negative for months where more came back than was spent
+fn net_to_string(paid: Money, refunded: Money, currency: Currency) -> String {
+    match paid.checked_sub(refunded) {
+        Some(x) => x.to_string_in(currency),
+        None => format!("-{}", refunded.checked_sub(paid).unwrap_or_default().to_string_in(currency))
+    }
+}
+
+// (method, (year, month), paid, refunded) for one payment or refund, in the currency of a report
+type Entry = (Option<usize>, (u64, u8), Money, Money);
+
+// How much went out through each payment method every month, and how much came back, in currency.
+// Orders that don't say what they were paid with are grouped together at the end.
+fn report(ledger: &Ledger, currency: Currency, filter: &Filter) -> Result<(), Error> {
+    let mut entries: Vec<Entry> = vec!();
+    for order in ledger.orders.iter().filter(|x| filter.matches(x)) {
+        let month = (order.date_placed.year, order.date_placed.month);
+        if order.payments.is_empty() {
+            entries.push((None, month, rates::convert(ledger, order.total, order.currency, currency, order.date_placed)?, Money::default()));
+            for refund in &order.refunds {
+                entries.push((None, (refund.date.year, refund.date.month), Money::default(), rates::convert(ledger, refund.amount, order.currency, currency, refund.date)?));
+            }
+            continue;
+        }
+        for payment in &order.payments {
+            entries.push((Some(payment.method), month, rates::convert(ledger, payment.amount, order.currency, currency, order.date_placed)?, Money::default()));
+        }
+        for (method, date, amount) in refunds_by_method(order) {
+            entries.push((Some(method), (date.year, date.month), Money::default(), rates::convert(ledger, amount, order.currency, currency, date)?));
+        }
+    }
+    for method in (0..ledger.payment_methods.len()).map(Some).chain([None]) {
+        let mine: Vec<&Entry> = entries.iter().filter(|x| x.0 == method).collect();
+        if mine.is_empty() {
+            continue;
+        }
+        match method {
+            Some(x) => println!("{}:", ledger.payment_methods[x].to_string()),
+            None => println!("(no payment method recorded):")
+        }
+        let mut months: Vec<(u64, u8)> = mine.iter().map(|x| x.1).collect();
+        months.sort();
+        months.dedup();
+        for month in months {
+            let paid = Money::sum(mine.iter().filter(|x| x.1 == month).map(|x| x.2), "the payments")?;
+            let refunded = Money::sum(mine.iter().filter(|x| x.1 == month).map(|x| x.3), "the refunds")?;
+            println!("  {}-{:02}  paid {}  refunded {}  net {}", month.0, month.1, paid.to_string_in(currency), refunded.to_string_in(currency), net_to_string(paid, refunded, currency));
+        }
+        let paid = Money::sum(mine.iter().map(|x| x.2), "the payments")?;
+        let refunded = Money::sum(mine.iter().map(|x| x.3), "the refunds")?;
+        println!("  Altogether: paid {}  refunded {}  net {}", paid.to_string_in(currency), refunded.to_string_in(currency), net_to_string(paid, refunded, currency));
+    }
+    Ok(())
+}
+
+// purchase_tracker payments [FILE] | payments add [FILE] [NAME] [...] | payments set [FILE] [METHOD] [...]
+// | payments report [FILE] [--in CURRENCY] [--category CATEGORY] [--tag TAG]
+pub fn payments_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["name", "last-four", "type", "in", "category", "tag"], &[])?;
+    let usage = "purchase_tracker payments [FILE] | purchase_tracker payments add [FILE] [NAME] [--type TYPE] [--last-four DIGITS] | purchase_tracker payments set [FILE] [METHOD] [--name NAME] [--type TYPE] [--last-four DIGITS] | purchase_tracker payments report [FILE] [--in CURRENCY] [--category CATEGORY] [--tag TAG]";
+    match args.positional.iter().map(|x| x.as_str()).collect::<Vec<&str>>().as_slice() {
+        ["add", path, name] => {
+            let path = Path::new(path);
+            let mut ledger = storage::load(path)?;
+            if let Some(x) = find(&ledger.payment_methods, name) {
+                return Err(Error::validation("NAME", format!("{} already has a payment method called {} (number {})", path.display(), ledger.payment_methods[x].name, x)));
+            }
+            let kind = match args.value("type") {
+                Some(x) => parse_kind(x, "--type")?,
+                None => Kind::Credit
+            };
+            ledger.payment_methods.push(PaymentMethod {
+                name: name.trim().to_string(),
+                last_four: parse_last_four(args.value("last-four").unwrap_or(""), "--last-four")?,
+                kind
+            });
+            storage::save(path, &ledger)?;
+            println!("Added payment method {}: {}", ledger.payment_methods.len() - 1, ledger.payment_methods[ledger.payment_methods.len() - 1].to_string());
+            Ok(())
+        },
+        ["set", path, method] => {
+            let path = Path::new(path);
+            let mut ledger = storage::load(path)?;
+            let index = parse_method(method, &ledger.payment_methods, path)?;
+            if let Some(x) = args.value("name")
+                && let Some(y) = find(&ledger.payment_methods, x)
+                && y != index {
+                return Err(Error::validation("--name", format!("{} already has a payment method called {} (number {})", path.display(), ledger.payment_methods[y].name, y)));
+            }
+            let before = ledger.payment_methods[index].to_string();
+            let method = &mut ledger.payment_methods[index];
+            if let Some(x) = args.value("name") {
+                method.name = x.trim().to_string();
+            }
+            if let Some(x) = args.value("type") {
+                method.kind = parse_kind(x, "--type")?;
+            }
+            if let Some(x) = args.value("last-four") {
+                method.last_four = parse_last_four(x, "--last-four")?;
+            }
+            let after = method.to_string();
+            storage::save(path, &ledger)?;
+            println!("Before: {}", before);
+            println!("After:  {}", after);
+            Ok(())
+        },
+        ["report", path] => {
+            let ledger = storage::load(Path::new(path))?;
+            let currency = match args.value("in") {
+                Some(x) => Currency::parse(x, "--in")?,
+                None => ledger.home_currency
+            };
+            if ledger.orders.is_empty() {
+                println!("{} doesn't have any orders yet.", path);
+                return Ok(());
+            }
+            report(&ledger, currency, &Filter::from_args(&args))
+        },
+        [path] => {
+            let ledger = storage::load(Path::new(path))?;
+            if ledger.payment_methods.is_empty() {
+                println!("{} doesn't have any payment methods yet.", path);
+            }
+            for (index, method) in ledger.payment_methods.iter().enumerate() {
+                let orders = ledger.orders.iter().filter(|x| x.payments.iter().any(|y| y.method == index)).count();
+                println!("{}. {} ({} orders)", index, method.to_string(), orders);
+            }
+            Ok(())
+        },
+        _ => Err(Error::usage(usage))
+    }
+}
+
+// Asks whether to record what an order was paid with, for the editor
+pub fn replace_payments_cli(stdin: &io::Stdin, methods: &mut Vec<PaymentMethod>, order: &mut Order) -> Result<(), Error> {
+    println!("Paid with: {}", payments_to_string(order, methods));
+    println!("Type \"Yes\" to replace this with what you type now, or \"No\" to keep it.");
+    if ask_yes_no(stdin)? {
+        order.payments = split_payment_cli(stdin, methods, order.currency, order.total)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::USD;
+    use crate::refund::Refund;
+
+    fn date(year: u64, month: u8, day: u8) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    // An order paid with (method, amount) for each payment, that got refunds of (date, amount)
+    fn order(payments: &[(usize, u64)], refunds: &[(Date, u64)]) -> Order {
+        let total = payments.iter().map(|x| x.1).sum();
+        Order {
+            date_placed: date(2026, 10, 1),
+            vendor: None,
+            status_history: vec!(),
+            currency: USD,
+            subtotal: Money::from_minor(total),
+            breakdown: None,
+            total: Money::from_minor(total),
+            products: vec!(),
+            discounts: vec!(),
+            refunds: refunds.iter().map(|&(date, amount)| Refund {
+                date,
+                line: None,
+                returned: 0,
+                amount: Money::from_minor(amount),
+                restocking_fee: Money::default(),
+                reason: "".to_string()
+            }).collect(),
+            payments: payments.iter().map(|&(method, amount)| Payment {
+                method,
+                amount: Money::from_minor(amount)
+            }).collect(),
+            tags: vec!(),
+            notes: "".to_string()
+        }
+    }
+
+    fn refunded(order: &Order) -> Vec<(usize, Date, u64)> {
+        refunds_by_method(order).into_iter().map(|(method, date, amount)| (method, date, amount.minor())).collect()
+    }
+
+    #[test]
+    fn refunds_go_back_in_proportion_to_what_was_paid() {
+        let day = date(2026, 10, 9);
+        assert_eq!(refunded(&order(&[(0, 3000), (1, 2000)], &[(day, 1000)])), vec!((0, day, 600), (1, day, 400)));
+        assert_eq!(refunded(&order(&[(2, 4500), (0, 500)], &[(day, 5000)])), vec!((2, day, 4500), (0, day, 500)));
+        // Every refund is split on its own, on its own date
+        let later = date(2026, 11, 2);
+        assert_eq!(refunded(&order(&[(0, 3000), (1, 1000)], &[(day, 400), (later, 800)])), vec!((0, day, 300), (1, day, 100), (0, later, 600), (1, later, 200)));
+    }
+
+    #[test]
+    fn leftover_cents_go_to_the_biggest_payment() {
+        let day = date(2026, 10, 9);
+        assert_eq!(refunded(&order(&[(0, 2000), (1, 3000)], &[(day, 1001)])), vec!((0, day, 400), (1, day, 601)));
+        assert_eq!(refunded(&order(&[(0, 1000), (1, 1000), (2, 1000)], &[(day, 100)])), vec!((0, day, 34), (1, day, 33), (2, day, 33)));
+        // The first of them, when two paid the most
+        assert_eq!(refunded(&order(&[(0, 100), (1, 2500), (2, 2500)], &[(day, 7)])), vec!((0, day, 0), (1, day, 4), (2, day, 3)));
+    }
+
+    #[test]
+    fn refunds_of_orders_that_cost_nothing_are_split_evenly() {
+        let day = date(2026, 10, 9);
+        assert_eq!(refunded(&order(&[(0, 0), (1, 0)], &[(day, 501)])), vec!((0, day, 251), (1, day, 250)));
+        assert_eq!(refunded(&order(&[(1, 0)], &[(day, 500)])), vec!((1, day, 500)));
+        // Orders that don't say what paid for them don't say where refunds went either
+        assert!(refunded(&order(&[], &[(day, 500)])).is_empty());
+        assert!(refunded(&order(&[(0, 1000)], &[])).is_empty());
+    }
+}
@@ -0,0 +1,290 @@
+use bincode::{Decode, Encode};
+use std::fs;
+use std::path::Path;
+
+use crate::args::Args;
+use crate::error::Error;
+use crate::money::{Currency, Money};
+use crate::{parse_date, storage, Date, Ledger};
+
+/*
+ * Exchange rates are kept in the ledger itself (see Ledger.rates), so converting amounts never
+ * needs a network connection. They get there with 'purchase_tracker rates import FILE RATES.csv',
+ * where every line of RATES.csv looks like
+ *
+ *   2026-10-01,EUR,USD,1.0834
+ *
+ * meaning that on that date one EUR was worth 1.0834 USD. A header line starting with "date",
+ * blank lines and lines starting with "#" are skipped. Importing a rate for a date and pair of
+ * currencies that's already known replaces the old one.
+ *
+ * To convert an amount from one currency to another on some date, the newest rate between the
+ * two (in either direction) from on or before that date is used.
+ */
+#[derive(Encode, Decode, Debug, Clone, Copy)]
+pub struct Rate {
+    date: Date,
+    from: Currency,
+    to: Currency,
+    micros: u64 // How much one whole unit of from is worth in to, in millionths of a whole unit
+}
+
+const MICROS: u64 = 1_000_000;
+
+impl Rate {
+    // For format upgrades, which build rates out of their frozen copies
+    pub fn upgraded(date: Date, from: Currency, to: Currency, micros: u64) -> Rate {
+        Rate {
+            date,
+            from,
+            to,
+            micros
+        }
+    }
+}
+
+impl ToString for Rate {
+    fn to_string(&self) -> String {
+        format!("{}  1 {} = {} {}", self.date.to_iso_string(), self.from.code(), format_micros(self.micros), self.to.code())
+    }
+}
+
+// 1083400 -> "1.0834"
+fn format_micros(micros: u64) -> String {
+    let fraction = format!("{:06}", micros % MICROS);
+    let fraction = fraction.trim_end_matches('0');
+    match fraction {
+        "" => (micros / MICROS).to_string(),
+        x => format!("{}.{}", micros / MICROS, x)
+    }
+}
+
+fn parse_micros(input: &str, field: &str) -> Result<u64, Error> {
+    let input = input.trim();
+    let (whole, fraction) = match input.split_once('.') {
+        Some((x, y)) => (x, y),
+        None => (input, "")
+    };
+    let well_formed = !whole.is_empty() && whole.bytes().all(|x| x.is_ascii_digit()) && fraction.bytes().all(|x| x.is_ascii_digit()) && fraction.len() <= 6;
+    if !well_formed {
+        return Err(Error::parse(field, input, "An exchange rate has to be a positive number with at most 6 digits after the decimal point, like \"1.0834\"."));
+    }
+    let fraction = format!("{:0<6}", fraction).parse::<u64>().unwrap_or(0);
+    match whole.parse::<u64>().ok().and_then(|x| x.checked_mul(MICROS)).and_then(|x| x.checked_add(fraction)) {
+        Some(0) => Err(Error::parse(field, input, "An exchange rate can't be 0.")),
+        Some(x) => Ok(x),
+        None => Err(Error::parse(field, input, "That exchange rate is too big."))
+    }
+}
+
+// One line of a rates file, None for lines that are skipped. line_number counts from 1.
+fn parse_line(line: &str, line_number: usize, path: &Path) -> Result<Option<Rate>, Error> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.to_ascii_lowercase().starts_with("date") {
+        return Ok(None);
+    }
+    let field = format!("line {} of {}", line_number, path.display());
+    let fields: Vec<&str> = line.split(',').collect();
+    match fields.as_slice() {
+        [date, from, to, rate] => {
+            let rate = Rate {
+                date: parse_date(date, &format!("the date on {}", field))?,
+                from: Currency::parse(from, &format!("the first currency on {}", field))?,
+                to: Currency::parse(to, &format!("the second currency on {}", field))?,
+                micros: parse_micros(rate, &format!("the rate on {}", field))?
+            };
+            if rate.from == rate.to {
+                return Err(Error::validation(&field, "it converts a currency into itself"));
+            }
+            Ok(Some(rate))
+        },
+        _ => Err(Error::parse(&field, line, "Every line should look like \"DATE,FROM,TO,RATE\", e.g. \"2026-10-01,EUR,USD,1.0834\"."))
+    }
+}
+
+// amount * numerator / denominator, rounded to the nearest unit (halves round up). None if that's
+// too big, even along the way.
+fn scale(amount: Money, numerator: u128, denominator: u128) -> Option<Money> {
+    let scaled = (amount.minor() as u128).checked_mul(numerator)?.checked_add(denominator / 2)? / denominator;
+    u64::try_from(scaled).ok().map(Money::from_minor)
+}
+
+// Converts amount from one currency to another, using the rates that applied on date
+pub fn convert(ledger: &Ledger, amount: Money, from: Currency, to: Currency, date: Date) -> Result<Money, Error> {
+    if from == to {
+        return Ok(amount);
+    }
+    let best = ledger.rates.iter()
+        .filter(|x| x.date <= date && ((x.from == from && x.to == to) || (x.from == to && x.to == from)))
+        .max_by_key(|x| x.date);
+    let rate = match best {
+        Some(x) => x,
+        None => return Err(Error::validation(&format!("converting {} to {}", from.code(), to.code()), format!("there's no exchange rate between them from on or before {}. Import one with 'purchase_tracker rates import'", date.to_string())))
+    };
+    let from_unit = 10u128.pow(from.exponent());
+    let to_unit = 10u128.pow(to.exponent());
+    let converted = match rate.from == from {
+        true => scale(amount, rate.micros as u128 * to_unit, MICROS as u128 * from_unit),
+        false => scale(amount, MICROS as u128 * to_unit, rate.micros as u128 * from_unit)
+    };
+    match converted {
+        Some(x) => Ok(x),
+        None => Err(Error::validation(&format!("converting {} to {}", from.code(), to.code()), "the result is more money than purchase_tracker can count"))
+    }
+}
+
+// purchase_tracker rates [FILE] | rates import [FILE] [RATES.csv] | rates home [FILE] [CURRENCY]
+pub fn rates_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &[], &[])?;
+    let usage = "purchase_tracker rates [FILE] | purchase_tracker rates import [FILE] [RATES.csv] | purchase_tracker rates home [FILE] [CURRENCY]";
+    match args.positional.iter().map(|x| x.as_str()).collect::<Vec<&str>>().as_slice() {
+        ["import", path, csv] => import(Path::new(path), Path::new(csv)),
+        ["home", path, currency] => {
+            let path = Path::new(path);
+            let mut ledger = storage::load(path)?;
+            let currency = Currency::parse(currency, "CURRENCY")?;
+            let before = ledger.home_currency;
+            ledger.home_currency = currency;
+            storage::save(path, &ledger)?;
+            println!("The home currency of {} went from {} to {}.", path.display(), before.code(), currency.code());
+            Ok(())
+        },
+        [path] => {
+            let ledger = storage::load(Path::new(path))?;
+            println!("Home currency: {}", ledger.home_currency.code());
+            if ledger.rates.is_empty() {
+                println!("{} doesn't have any exchange rates yet.", path);
+            }
+            for rate in &ledger.rates {
+                println!("{}", rate.to_string());
+            }
+            Ok(())
+        },
+        _ => Err(Error::usage(usage))
+    }
+}
+
+fn import(path: &Path, csv: &Path) -> Result<(), Error> {
+    let contents = match fs::read_to_string(csv) {
+        Ok(x) => x,
+        Err(x) => return Err(Error::io("reading the rates file", csv, x))
+    };
+    let mut imported: Vec<Rate> = vec!();
+    for (index, line) in contents.lines().enumerate() {
+        if let Some(x) = parse_line(line, index + 1, csv)? {
+            imported.push(x);
+        }
+    }
+
+    let mut ledger = storage::load(path)?;
+    let mut replaced = 0;
+    for rate in &imported {
+        let before = ledger.rates.len();
+        ledger.rates.retain(|x| !(x.date == rate.date && x.from == rate.from && x.to == rate.to));
+        replaced += before - ledger.rates.len();
+        ledger.rates.push(*rate);
+    }
+    ledger.rates.sort_by_key(|x| (x.date, x.from, x.to));
+    storage::save(path, &ledger)?;
+    println!("Imported {} exchange rates into {} ({} of them replaced rates that were already there).", imported.len(), path.display(), replaced);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::USD;
+
+    fn currency(code: &str) -> Currency {
+        Currency::parse(code, "currency").unwrap()
+    }
+
+    fn date(year: u64, month: u8, day: u8) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    fn ledger(lines: &[&str]) -> Ledger {
+        let mut ledger = Ledger::new();
+        for x in lines {
+            ledger.rates.push(parse_line(x, 1, Path::new("rates.csv")).unwrap().unwrap());
+        }
+        ledger
+    }
+
+    fn convert_minor(ledger: &Ledger, amount: u64, from: &str, to: &str) -> u64 {
+        convert(ledger, Money::from_minor(amount), currency(from), currency(to), date(2026, 10, 1)).unwrap().minor()
+    }
+
+    #[test]
+    fn rates_parse() {
+        assert_eq!(parse_micros("1.0834", "rate").unwrap(), 1083400);
+        assert_eq!(parse_micros(" 150 ", "rate").unwrap(), 150000000);
+        assert_eq!(parse_micros("0.000001", "rate").unwrap(), 1);
+        assert_eq!(parse_micros("2.", "rate").unwrap(), 2000000);
+        assert_eq!(format_micros(1083400), "1.0834");
+        assert_eq!(format_micros(150000000), "150");
+        assert_eq!(format_micros(1), "0.000001");
+    }
+
+    #[test]
+    fn bad_rates_are_errors() {
+        for x in ["", "abc", "-1", "+1", ".5", "1,5", "1e3", "1.2.3", "1.0000001", "0", "0.000000", "18446744073709551616", "18446744073710"] {
+            assert!(matches!(parse_micros(x, "rate"), Err(Error::Parse { .. })), "\"{}\" was taken as a rate", x);
+        }
+        let path = Path::new("rates.csv");
+        assert!(matches!(parse_line("2026-10-01,EUR,USD,abc", 3, path), Err(Error::Parse { field, .. }) if field == "the rate on line 3 of rates.csv"));
+        assert!(matches!(parse_line("2026-10-01,EUR,USD", 3, path), Err(Error::Parse { .. })));
+        assert!(matches!(parse_line("2026-10-01,EUR,EUR,1", 3, path), Err(Error::Validation { .. })));
+        assert!(matches!(parse_line("2026-10-01,EUR,XYZ,1", 3, path), Err(Error::Parse { .. })));
+        for x in ["", "# From the ECB", "Date,From,To,Rate"] {
+            assert!(parse_line(x, 1, path).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn conversions_follow_each_currencys_exponent() {
+        let ledger = ledger(&["2026-09-01,USD,JPY,150.25"]);
+        // $10.00 is 1502.5 yen, and there's no such thing as half a yen
+        assert_eq!(convert_minor(&ledger, 1000, "USD", "JPY"), 1503);
+        assert_eq!(convert_minor(&ledger, 1, "USD", "JPY"), 2);
+        // The other way around divides by the rate
+        assert_eq!(convert_minor(&ledger, 1503, "JPY", "USD"), 1000);
+        assert_eq!(convert_minor(&ledger, 1, "JPY", "USD"), 1);
+        assert_eq!(convert_minor(&ledger, 0, "JPY", "USD"), 0);
+        assert_eq!(convert_minor(&ledger, 1000, "USD", "USD"), 1000);
+    }
+
+    #[test]
+    fn conversions_round_halves_up() {
+        let half = ledger(&["2026-09-01,EUR,USD,0.5"]);
+        assert_eq!(convert_minor(&half, 1, "EUR", "USD"), 1);
+        assert_eq!(convert_minor(&half, 3, "EUR", "USD"), 2);
+        assert_eq!(convert_minor(&half, 4, "EUR", "USD"), 2);
+        assert_eq!(convert_minor(&half, 3, "USD", "EUR"), 6);
+        let third = ledger(&["2026-09-01,EUR,USD,0.333333"]);
+        assert_eq!(convert_minor(&third, 1, "EUR", "USD"), 0);
+        assert_eq!(convert_minor(&third, 2, "EUR", "USD"), 1);
+        assert_eq!(convert_minor(&third, 1, "USD", "EUR"), 3);
+    }
+
+    #[test]
+    fn conversions_use_the_newest_rate_up_to_the_date() {
+        let ledger = ledger(&["2026-08-01,EUR,USD,1.1", "2026-09-15,USD,EUR,0.8", "2026-10-02,EUR,USD,2"]);
+        let on = |x: Date| convert(&ledger, Money::from_minor(1000), currency("EUR"), USD, x).map(|y| y.minor());
+        assert_eq!(on(date(2026, 9, 14)).unwrap(), 1100);
+        assert_eq!(on(date(2026, 10, 1)).unwrap(), 1250);
+        assert_eq!(on(date(2026, 10, 2)).unwrap(), 2000);
+        assert!(matches!(on(date(2026, 7, 31)), Err(Error::Validation { .. })));
+        assert!(matches!(convert(&ledger, Money::from_minor(1000), currency("GBP"), USD, date(2026, 10, 2)), Err(Error::Validation { .. })));
+    }
+
+    #[test]
+    fn conversions_too_big_to_count_are_errors() {
+        let ledger = ledger(&["2026-09-01,JPY,KWD,18446744073709.551615"]);
+        let converted = convert(&ledger, Money::from_minor(u64::MAX), currency("JPY"), currency("KWD"), date(2026, 10, 1));
+        assert!(matches!(converted, Err(Error::Validation { .. })));
+        let converted = convert(&ledger, Money::from_minor(u64::MAX), currency("KWD"), currency("JPY"), date(2026, 10, 1));
+        // KWD counts in thousandths
+        assert_eq!(converted.unwrap().minor(), 1000);
+    }
+}
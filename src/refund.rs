@@ -0,0 +1,240 @@
+use bincode::{Decode, Encode};
+use std::io;
+use std::path::Path;
+
+use crate::args::Args;
+use crate::error::Error;
+use crate::money::{Currency, Money};
+use crate::{ask, ask_money, ask_money_or_zero, parse_count, parse_date, parse_order_index, read_answer, storage, Date, Order, OrderLine, Status, ABORT};
+
+/*
+ * Money that came back after an order was paid for: a product sent back (or only some of them, for
+ * lines with a quantity over 1), a partial refund for something that arrived damaged, a refund of
+ * the shipping... Order.total stays what was paid at the time, and everything that reports how
+ * much was spent takes the refunds off of it (see Order::net_total).
+ *
+ * amount is what actually came back. When the vendor kept a restocking fee, that's already been
+ * taken out of amount, and restocking_fee only records how much it was.
+ */
+#[derive(Encode, Decode, Debug, Clone)]
+pub struct Refund {
+    pub date: Date,
+    pub line: Option<usize>, // Index into Order.products it was for, None for the order as a whole
+    pub returned: u64, // How many of that product were sent back, 0 if nothing was
+    pub amount: Money,
+    pub restocking_fee: Money,
+    pub reason: String // "" if none was given
+}
+
+impl Refund {
+    // Like "2026-10-05: $18.00 back for 1 of product 0 (Widget), $2.00 restocking fee: too small"
+    pub fn to_string_in(&self, currency: Currency, products: &[OrderLine]) -> String {
+        let mut temp = format!("{}: {} back", self.date.to_string(), self.amount.to_string_in(currency));
+        match (self.line, self.returned) {
+            (None, _) => temp.push_str(" for the order"),
+            (Some(x), 0) => temp.push_str(&format!(" for product {}", x)),
+            (Some(x), y) => temp.push_str(&format!(" for {} of product {}", y, x))
+        }
+        if let Some(x) = self.line.and_then(|x| products.get(x)) {
+            temp.push_str(&format!(" ({})", x.product.name.trim()));
+        }
+        if self.restocking_fee != Money::default() {
+            temp.push_str(&format!(", {} restocking fee", self.restocking_fee.to_string_in(currency)));
+        }
+        if !self.reason.is_empty() {
+            temp.push_str(": ");
+            temp.push_str(&self.reason);
+        }
+        temp
+    }
+}
+
+// Everything that came back for an order
+pub fn refunded(order: &Order) -> Result<Money, Error> {
+    Money::sum(order.refunds.iter().map(|x| x.amount), "the refunds")
+}
+
+// Product number line of an order can only be removed when none of its refunds are for it, or they'd
+// end up crediting a different product
+pub fn check_line_removal(refunds: &[Refund], line: usize) -> Result<(), Error> {
+    match refunds.iter().position(|x| x.line == Some(line)) {
+        Some(x) => Err(Error::validation(&format!("products[{}]", line), format!("refund {} is for it, so change or remove that refund first", x))),
+        None => Ok(())
+    }
+}
+
+// Once product number line is gone, the products after it move up by one, and so do the numbers of
+// the refunds for them
+pub fn line_removed(refunds: &mut [Refund], line: usize) {
+    for x in refunds.iter_mut().filter_map(|x| x.line.as_mut()).filter(|x| **x > line) {
+        *x -= 1;
+    }
+}
+
+// Refunds have to be for products the order has, can't come before it was placed, and can't give
+// back more than was paid (for the whole order, or for any one product)
+pub fn check(order: &Order) -> Result<(), Error> {
+    for (index, refund) in order.refunds.iter().enumerate() {
+        let field = format!("refunds[{}]", index);
+        if refund.date < order.date_placed {
+            return Err(Error::validation(&field, format!("it's from {}, which is before the order was placed on {}", refund.date.to_string(), order.date_placed.to_string())));
+        }
+        match refund.line {
+            Some(x) if x >= order.products.len() => return Err(Error::validation(&field, format!("it's for product {}, but the order only has {} products", x, order.products.len()))),
+            None if refund.returned > 0 => return Err(Error::validation(&field, "it says products were sent back, but not which ones")),
+            _ => ()
+        }
+    }
+    for (index, line) in order.products.iter().enumerate() {
+        let refunds: Vec<&Refund> = order.refunds.iter().filter(|x| x.line == Some(index)).collect();
+        let returned = refunds.iter().fold(0u64, |total, x| total.saturating_add(x.returned));
+        if returned > line.quantity {
+            return Err(Error::validation(&format!("the refunds for product {}", index), format!("{} of them were sent back, but only {} were bought", returned, line.quantity)));
+        }
+        let amount = Money::sum(refunds.iter().map(|x| x.amount), &format!("the refunds for product {}", index))?;
+        if amount > line.paid {
+            return Err(Error::validation(&format!("the refunds for product {}", index), format!("they gave back {}, but only {} was paid for it", amount.to_string_in(order.currency), line.paid.to_string_in(order.currency))));
+        }
+    }
+    let amount = refunded(order)?;
+    if amount > order.total {
+        return Err(Error::validation("the refunds", format!("they gave back {}, but the total of the order is only {}", amount.to_string_in(order.currency), order.total.to_string_in(order.currency))));
+    }
+    Ok(())
+}
+
+// Asks about one refund on order
+pub fn new_refund_cli(stdin: &io::Stdin, order: &Order) -> Result<Refund, Error> {
+    let currency = order.currency;
+    println!("When was the refund? (Use \"MM/DD/YYYY\" or \"YYYY-MM-DD\" format)");
+    let date = ask(stdin, |x| parse_date(x, "the date of the refund"))?;
+    let mut line: Option<usize> = None;
+    let mut returned = 0;
+    if !order.products.is_empty() {
+        println!("Which product was it for? (Type its number, or leave it blank if it was for the order as a whole, like a refund of the shipping)");
+        for (index, x) in order.products.iter().enumerate() {
+            println!("  {}. {} x {}", index, x.quantity, x.product.name.trim());
+        }
+        line = ask(stdin, |x| match x.trim() {
+            "" => Ok(None),
+            y => match y.parse::<usize>() {
+                Ok(z) if z < order.products.len() => Ok(Some(z)),
+                _ => Err(Error::validation("the product the refund was for", format!("pick a number from 0 to {}", order.products.len() - 1)))
+            }
+        })?;
+    }
+    if let Some(x) = line {
+        println!("How many of {} did you send back? (Leave it blank if you didn't send any back)", order.products[x].product.name.trim());
+        returned = ask(stdin, |y| match y.trim() {
+            "" => Ok(0),
+            z => parse_count(z, "the number of products sent back")
+        })?;
+    }
+    println!("How much did you get back? (After any restocking fee)");
+    let amount = ask_money(stdin, currency, "the refund")?;
+    println!("How much of a restocking fee did the vendor keep? (Leave it blank if there wasn't one)");
+    let restocking_fee = ask_money_or_zero(stdin, currency, "the restocking fee")?;
+    println!("Why was it refunded? (Leave it blank to skip this)");
+    let reason = read_answer(stdin)?.trim().to_string();
+    Ok(Refund {
+        date,
+        line,
+        returned,
+        amount,
+        restocking_fee,
+        reason
+    })
+}
+
+// purchase_tracker refund [FILE] [ORDER] [--date DATE] [--amount AMOUNT] [--product N] [--returned N] [--restocking-fee AMOUNT] [--reason TEXT]
+// Without --amount, asks about the refund instead.
+pub fn refund_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["date", "amount", "product", "returned", "restocking-fee", "reason"], &[])?;
+    let usage = "purchase_tracker refund [FILE] [ORDER] [--date DATE] [--amount AMOUNT] [--product N] [--returned N] [--restocking-fee AMOUNT] [--reason TEXT]";
+    let (path, index) = match args.positional.as_slice() {
+        [x, y] => (Path::new(x), y),
+        _ => return Err(Error::usage(usage))
+    };
+    let mut ledger = storage::load(path)?;
+    let index = parse_order_index(index, &ledger.orders, path)?;
+    let order = &ledger.orders[index];
+    let currency = order.currency;
+
+    let refund = match args.value("amount") {
+        None => {
+            println!("{}.\n{}", index, order.to_string());
+            println!("(Type \"{}\" in answer to any question to stop without saving anything.)", ABORT);
+            new_refund_cli(&io::stdin(), order)?
+        },
+        Some(x) => Refund {
+            date: match args.value("date") {
+                Some(y) => parse_date(y, "--date")?,
+                None => return Err(Error::validation("--date", "a refund given with --amount needs a date too"))
+            },
+            line: match args.value("product") {
+                Some(y) => Some(match y.trim().parse::<usize>() {
+                    Ok(z) if z < order.products.len() => z,
+                    Ok(z) => return Err(Error::validation("--product", format!("order {} only has {} products, numbered starting from 0, so there's no product {}", index, order.products.len(), z))),
+                    Err(z) => return Err(Error::parse("--product", y, z))
+                }),
+                None => None
+            },
+            returned: match args.value("returned") {
+                Some(y) => parse_count(y, "--returned")?,
+                None => 0
+            },
+            amount: Money::parse(x, currency, "--amount")?,
+            restocking_fee: match args.value("restocking-fee") {
+                Some(y) => Money::parse(y, currency, "--restocking-fee")?,
+                None => Money::default()
+            },
+            reason: args.value("reason").unwrap_or("").trim().to_string()
+        }
+    };
+
+    let order = &mut ledger.orders[index];
+    order.refunds.push(refund);
+    check(order)?;
+    let added = order.refunds[order.refunds.len() - 1].to_string_in(currency, &order.products);
+    let net = order.net_total();
+    let fully_refunded = net == Money::default() && order.status().can_move_to(Status::Returned);
+    storage::save(path, &ledger)?;
+    println!("Refund: {}", added);
+    println!("Order {} cost {} after refunds.", index, net.to_string_in(currency));
+    if fully_refunded {
+        println!("Everything was refunded. If the order was sent back, run 'purchase_tracker update-status {} {} returned' to mark it as returned.", path.display(), index);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refund(line: Option<usize>) -> Refund {
+        Refund {
+            date: Date::new(2026, 1, 2).unwrap(),
+            line,
+            returned: 0,
+            amount: Money::from_minor(100),
+            restocking_fee: Money::default(),
+            reason: "".to_string()
+        }
+    }
+
+    #[test]
+    fn later_lines_move_up_when_a_line_is_removed() {
+        let mut refunds = vec!(refund(Some(3)), refund(None), refund(Some(0)), refund(Some(1)));
+        assert!(check_line_removal(&refunds, 2).is_ok());
+        line_removed(&mut refunds, 2);
+        assert_eq!(refunds.iter().map(|x| x.line).collect::<Vec<Option<usize>>>(), vec!(Some(2), None, Some(0), Some(1)));
+    }
+
+    #[test]
+    fn lines_with_a_refund_cant_be_removed() {
+        let refunds = vec!(refund(None), refund(Some(1)));
+        assert!(check_line_removal(&refunds, 1).is_err());
+        assert!(check_line_removal(&refunds, 0).is_ok());
+        assert!(check_line_removal(&refunds, 2).is_ok());
+    }
+}
@@ -0,0 +1,469 @@
+use std::path::Path;
+
+use crate::args::Args;
+use crate::category::{line_category, Filter};
+use crate::error::Error;
+use crate::money::{Currency, Money};
+use crate::vendor::vendor_name;
+use crate::{rates, storage, Date, Ledger, Order};
+
+/*
+ * How much was spent per day, week, month, quarter or year, going by when orders were placed. Every
+ * amount is converted into one currency at the rate from the day the order was placed, like list
+ * does. When some order can't be (because there's no rate for it), the orders of each currency get
+ * tables of their own instead, like list adds them up per currency.
+ *
+ * The report can also be split up by vendor, category or tag. Orders only have one vendor, but
+ * they can have products in several categories, so for those the subtotal and total of an order
+ * are split between its products in proportion to what was paid for each one. Only the category
+ * and tags of the top level products count (a bundle is counted in its own category, not in the
+ * ones of its sub-products).
+ */
+
+#[derive(Clone, Copy, PartialEq)]
+enum Period {
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year
+}
+
+const PERIODS: [Period; 5] = [Period::Day, Period::Week, Period::Month, Period::Quarter, Period::Year];
+
+impl Period {
+    fn name(self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+            Period::Quarter => "quarter",
+            Period::Year => "year"
+        }
+    }
+
+    // The first day of the period date is in. Weeks start on Monday.
+    fn start(self, date: Date) -> Date {
+        match self {
+            Period::Day => date,
+            Period::Week => {
+                let days = date.days_since_epoch();
+                // 1970-01-01 was a Thursday, 3 days after a Monday
+                Date::from_days_since_epoch(days - (days + 3).rem_euclid(7))
+            },
+            Period::Month => Date { month: date.month, day: 1, year: date.year },
+            Period::Quarter => Date { month: (date.month - 1) / 3 * 3 + 1, day: 1, year: date.year },
+            Period::Year => Date { month: 1, day: 1, year: date.year }
+        }
+    }
+
+    // The first day of the period after the one starting on start
+    fn next(self, start: Date) -> Date {
+        let months = match self {
+            Period::Day => return Date::from_days_since_epoch(start.days_since_epoch() + 1),
+            Period::Week => return Date::from_days_since_epoch(start.days_since_epoch() + 7),
+            Period::Month => 1,
+            Period::Quarter => 3,
+            Period::Year => 12
+        };
+        match start.month + months > 12 {
+            true => Date { month: start.month + months - 12, day: 1, year: start.year + 1 },
+            false => Date { month: start.month + months, day: 1, year: start.year }
+        }
+    }
+
+    // Like "2026-10-05", "week of 2026-10-05", "2026-10", "2026 Q4" or "2026"
+    fn label(self, start: Date) -> String {
+        match self {
+            Period::Day => start.to_iso_string(),
+            Period::Week => format!("week of {}", start.to_iso_string()),
+            Period::Month => format!("{}-{:02}", start.year, start.month),
+            Period::Quarter => format!("{} Q{}", start.year, (start.month - 1) / 3 + 1),
+            Period::Year => start.year.to_string()
+        }
+    }
+}
+
+fn parse_period(input: &str, field: &str) -> Result<Period, Error> {
+    let input = input.trim().to_lowercase();
+    match PERIODS.iter().find(|x| x.name() == input || format!("{}ly", x.name()) == input || (**x == Period::Day && input == "daily")) {
+        Some(x) => Ok(*x),
+        None => Err(Error::parse(field, &input, format!("It can be {}.", PERIODS.iter().map(|x| format!("\"{}\"", x.name())).collect::<Vec<String>>().join(", "))))
+    }
+}
+
+// Prints rows as a table under header, with every column as wide as its widest cell. The first
+// column is lined up on the left and the rest on the right, since they're numbers.
+pub fn print_table(indent: &str, header: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = header.iter().map(|x| x.chars().count()).collect();
+    for row in rows {
+        for (index, cell) in row.iter().enumerate() {
+            widths[index] = widths[index].max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells.iter().enumerate().map(|(index, x)| match index {
+            0 => format!("{:<width$}", x, width = widths[0]),
+            _ => format!("{:>width$}", x, width = widths[index])
+        }).collect();
+        println!("{}{}", indent, padded.join("  ").trim_end());
+    };
+    line(header.to_vec());
+    line(widths.iter().map(|x| "-".repeat(*x)).collect::<Vec<String>>().iter().map(|x| x.as_str()).collect());
+    for row in rows {
+        line(row.iter().map(|x| x.as_str()).collect());
+    }
+}
+
+// Splits amount between weights in proportion to them, with whatever doesn't divide evenly going to
+// the biggest one. When every weight is 0 they all count the same.
+pub fn split(amount: Money, weights: &[u64]) -> Vec<Money> {
+    let weights: Vec<u128> = match weights.iter().all(|x| *x == 0) {
+        true => weights.iter().map(|_| 1).collect(),
+        false => weights.iter().map(|x| *x as u128).collect()
+    };
+    let sum: u128 = weights.iter().sum();
+    let mut shares: Vec<u64> = weights.iter()
+        .map(|x| u64::try_from(amount.minor() as u128 * x / sum).unwrap_or(u64::MAX))
+        .collect();
+    let leftover = amount.minor().saturating_sub(shares.iter().sum());
+    if let Some((biggest, _)) = weights.iter().enumerate().max_by_key(|(index, x)| (**x, std::cmp::Reverse(*index))) {
+        shares[biggest] += leftover;
+    }
+    shares.into_iter().map(Money::from_minor).collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Split {
+    Vendor,
+    Category,
+    Tag
+}
+
+// How much of order goes to each group of a split, as (group, share of the subtotal, share of the
+// total, share of what it cost after refunds). The shares of a split by tag can add up to more
+// than the order, since a product with two tags counts for both.
+fn shares(ledger: &Ledger, order: &Order, by: Option<Split>) -> Vec<(String, Money, Money, Money)> {
+    let whole = |group: &str| vec!((group.to_string(), order.subtotal, order.total, order.net_total()));
+    let by = match by {
+        None => return whole(""),
+        Some(Split::Vendor) => return whole(vendor_name(order, &ledger.vendors)),
+        Some(x) => x
+    };
+    if order.products.is_empty() {
+        return match by {
+            Split::Tag if !order.tags.is_empty() => order.tags.iter().flat_map(|x| whole(x)).collect(),
+            Split::Tag => whole("(no tags)"),
+            _ => whole("(no category)")
+        };
+    }
+    let weights: Vec<u64> = order.products.iter().map(|x| x.paid.minor()).collect();
+    let subtotals = split(order.subtotal, &weights);
+    let totals = split(order.total, &weights);
+    let nets = split(order.net_total(), &weights);
+    let mut found: Vec<(String, Money, Money, Money)> = vec!();
+    for line in 0..order.products.len() {
+        let (category, tags) = line_category(order, line);
+        let groups: Vec<&str> = match by {
+            Split::Tag if tags.is_empty() => vec!("(no tags)"),
+            Split::Tag => tags.iter().map(|x| x.as_str()).collect(),
+            _ if category.is_empty() => vec!("(no category)"),
+            _ => vec!(category)
+        };
+        for group in groups {
+            found.push((group.to_string(), subtotals[line], totals[line], nets[line]));
+        }
+    }
+    found
+}
+
+// (group, start of the period, index of the order, subtotal, total, after refunds), in the currency
+// of the report
+type Entry = (String, Date, usize, Money, Money, Money);
+
+// "+12.5%", "-3.0%", or "-" when there's nothing to compare with
+fn change_to_string(before: Option<Money>, now: Money) -> String {
+    let before = match before {
+        Some(x) if x != Money::default() => x.minor() as i128,
+        _ => return "-".to_string()
+    };
+    let tenths = (now.minor() as i128 - before) * 1000;
+    let tenths = (tenths + tenths.signum() * before / 2) / before;
+    format!("{}{}.{}%", if tenths < 0 { "-" } else { "+" }, tenths.abs() / 10, tenths.abs() % 10)
+}
+
+fn average(amount: Money, count: usize) -> Money {
+    match count {
+        0 => Money::default(),
+        x => Money::from_minor(((amount.minor() as u128 + x as u128 / 2) / x as u128) as u64)
+    }
+}
+
+// One table for the entries of one group, with every period from the first to the last one that
+// has orders. Empty weeks, months, quarters and years are kept so that gaps in spending show, but
+// a table with a row for every day nothing was bought on would mostly be empty rows, so those are
+// left out. Change is always against the period right before, so it's "-" after a left out day.
+fn print_group(entries: &[&Entry], period: Period, currency: Currency, indent: &str, show_net: bool) -> Result<(), Error> {
+    let (first, last) = match (entries.iter().map(|x| x.1).min(), entries.iter().map(|x| x.1).max()) {
+        (Some(x), Some(y)) => (x, y),
+        _ => return Ok(())
+    };
+    let mut header = vec!("Period", "Orders", "Subtotal", "Total", "Average");
+    if show_net {
+        header.push("After refunds");
+    }
+    header.push("Change");
+    let mut rows: Vec<Vec<String>> = vec!();
+    let mut before: Option<Money> = None;
+    let mut start = first;
+    let row = |label: String, mine: &[&&Entry], before: Option<Money>| -> Result<(Vec<String>, Money), Error> {
+        let mut orders: Vec<usize> = mine.iter().map(|x| x.2).collect();
+        orders.sort();
+        orders.dedup();
+        let subtotal = Money::sum(mine.iter().map(|x| x.3), "the subtotals")?;
+        let total = Money::sum(mine.iter().map(|x| x.4), "the totals")?;
+        let mut cells = vec!(label, orders.len().to_string(), subtotal.to_string_in(currency), total.to_string_in(currency), average(total, orders.len()).to_string_in(currency));
+        if show_net {
+            cells.push(Money::sum(mine.iter().map(|x| x.5), "the totals after refunds")?.to_string_in(currency));
+        }
+        cells.push(change_to_string(before, total));
+        Ok((cells, total))
+    };
+    while start <= last {
+        let mine: Vec<&&Entry> = entries.iter().filter(|x| x.1 == start).collect();
+        if mine.is_empty() && period == Period::Day {
+            before = None;
+            start = period.next(start);
+            continue;
+        }
+        let (cells, total) = row(period.label(start), &mine, before)?;
+        rows.push(cells);
+        before = Some(total);
+        start = period.next(start);
+    }
+    let all: Vec<&&Entry> = entries.iter().collect();
+    let (mut cells, _) = row("Altogether".to_string(), &all, None)?;
+    cells.pop();
+    cells.push("".to_string());
+    rows.push(cells);
+    print_table(indent, &header, &rows);
+    Ok(())
+}
+
+// The entries of orders, converted into currency, or left in their own when it's None. The
+// orders have to have a date that's on the calendar.
+fn collect_entries(ledger: &Ledger, orders: &[(usize, &Order)], period: Period, by: Option<Split>, currency: Option<Currency>) -> Result<Vec<Entry>, Error> {
+    let mut entries: Vec<Entry> = vec!();
+    for (index, order) in orders {
+        let start = period.start(order.date_placed);
+        let convert = |x: Money| match currency {
+            Some(y) => rates::convert(ledger, x, order.currency, y, order.date_placed),
+            None => Ok(x)
+        };
+        for (group, subtotal, total, net) in shares(ledger, order, by) {
+            entries.push((group, start, *index, convert(subtotal)?, convert(total)?, convert(net)?));
+        }
+    }
+    Ok(entries)
+}
+
+// A table for entries, or one for each group of the split with its name above it
+fn print_entries(entries: &[Entry], period: Period, currency: Currency, by: Option<Split>, indent: &str, show_net: bool) -> Result<(), Error> {
+    if by.is_none() {
+        return print_group(&entries.iter().collect::<Vec<&Entry>>(), period, currency, indent, show_net);
+    }
+    let mut groups: Vec<&str> = entries.iter().map(|x| x.0.as_str()).collect();
+    // Alphabetically, with "(no vendor)" and the like last
+    groups.sort_by_key(|x| (x.starts_with('('), x.to_lowercase()));
+    groups.dedup_by(|x, y| x.eq_ignore_ascii_case(y));
+    for group in groups {
+        println!();
+        println!("{}{}:", indent, group);
+        let mine: Vec<&Entry> = entries.iter().filter(|x| x.0.eq_ignore_ascii_case(group)).collect();
+        print_group(&mine, period, currency, &format!("{}  ", indent), show_net)?;
+    }
+    Ok(())
+}
+
+// purchase_tracker report [FILE] [--by PERIOD] [--split vendor|category|tag] [--in CURRENCY] [--category CATEGORY] [--tag TAG]
+pub fn report_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["by", "split", "in", "category", "tag"], &[])?;
+    let path = match args.positional.as_slice() {
+        [x] => Path::new(x),
+        _ => return Err(Error::usage("purchase_tracker report [FILE] [--by day|week|month|quarter|year] [--split vendor|category|tag] [--in CURRENCY] [--category CATEGORY] [--tag TAG]"))
+    };
+    let period = match args.value("by") {
+        Some(x) => parse_period(x, "--by")?,
+        None => Period::Month
+    };
+    let by = match args.value("split").map(|x| x.trim().to_lowercase()).as_deref() {
+        None => None,
+        Some("vendor") => Some(Split::Vendor),
+        Some("category") => Some(Split::Category),
+        Some("tag") => Some(Split::Tag),
+        Some(x) => return Err(Error::parse("--split", x, "It can be \"vendor\", \"category\" or \"tag\"."))
+    };
+    let filter = Filter::from_args(&args);
+    let ledger = storage::load(path)?;
+    let currency = match args.value("in") {
+        Some(x) => Currency::parse(x, "--in")?,
+        None => ledger.home_currency
+    };
+
+    let mut orders: Vec<(usize, &Order)> = vec!();
+    let mut skipped = 0;
+    for (index, order) in ledger.orders.iter().enumerate().filter(|(_, x)| filter.matches(x)) {
+        // Dates from old files might not be on the calendar, so there's no telling what period
+        // they're in
+        match order.date_placed.is_valid() {
+            true => orders.push((index, order)),
+            false => skipped += 1
+        }
+    }
+    if orders.is_empty() {
+        println!("{} doesn't have any orders{}.", path.display(), filter.describe());
+        return Ok(());
+    }
+    let show_net = orders.iter().any(|(_, x)| !x.refunds.is_empty());
+
+    match collect_entries(&ledger, &orders, period, by, Some(currency)) {
+        Ok(x) => {
+            println!("Spending per {}{} in {}:", period.name(), filter.describe(), currency.code());
+            print_entries(&x, period, currency, by, "", show_net)?;
+        },
+        Err(x) => {
+            println!("Spending per {}{}, in the currency of each order:", period.name(), filter.describe());
+            let mut currencies: Vec<Currency> = orders.iter().map(|(_, y)| y.currency).collect();
+            currencies.sort();
+            currencies.dedup();
+            for order_currency in currencies {
+                let mine: Vec<(usize, &Order)> = orders.iter().copied().filter(|(_, y)| y.currency == order_currency).collect();
+                println!();
+                println!("In {}:", order_currency.code());
+                let entries = collect_entries(&ledger, &mine, period, by, None)?;
+                print_entries(&entries, period, order_currency, by, "  ", show_net)?;
+            }
+            println!();
+            println!("(Couldn't add that up in {}. {})", currency.code(), x.to_string());
+        }
+    }
+    if by == Some(Split::Tag) {
+        println!();
+        println!("(Products with more than one tag count towards each of them, so the tags add up to more than was spent.)");
+    }
+    if skipped > 0 {
+        println!();
+        println!("{} orders were left out because the date they were placed isn't on the calendar. Fix it with 'purchase_tracker edit'.", skipped);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::USD;
+
+    fn date(year: u64, month: u8, day: u8) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    fn order(placed: Date, currency: Currency, total: u64) -> Order {
+        Order {
+            date_placed: placed,
+            vendor: None,
+            status_history: vec!(),
+            currency,
+            subtotal: Money::from_minor(total),
+            breakdown: None,
+            total: Money::from_minor(total),
+            products: vec!(),
+            discounts: vec!(),
+            refunds: vec!(),
+            payments: vec!(),
+            tags: vec!(),
+            notes: "".to_string()
+        }
+    }
+
+    // (start, label, start of the period after it) for the period date is in
+    fn period_of(period: Period, date: Date) -> (String, String, String) {
+        let start = period.start(date);
+        (start.to_iso_string(), period.label(start), period.next(start).to_iso_string())
+    }
+
+    fn strings(start: &str, label: &str, next: &str) -> (String, String, String) {
+        (start.to_string(), label.to_string(), next.to_string())
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // A Sunday belongs to the week that started 6 days before it
+        assert_eq!(period_of(Period::Week, date(2026, 10, 18)), strings("2026-10-12", "week of 2026-10-12", "2026-10-19"));
+        assert_eq!(period_of(Period::Week, date(2026, 10, 19)), strings("2026-10-19", "week of 2026-10-19", "2026-10-26"));
+        // Weeks run across the end of the year
+        assert_eq!(period_of(Period::Week, date(2026, 1, 1)), strings("2025-12-29", "week of 2025-12-29", "2026-01-05"));
+        assert_eq!(period_of(Period::Week, date(2025, 1, 5)), strings("2024-12-30", "week of 2024-12-30", "2025-01-06"));
+    }
+
+    #[test]
+    fn periods_roll_over_at_the_end_of_the_year() {
+        assert_eq!(period_of(Period::Day, date(2026, 12, 31)), strings("2026-12-31", "2026-12-31", "2027-01-01"));
+        assert_eq!(period_of(Period::Day, date(2024, 2, 28)), strings("2024-02-28", "2024-02-28", "2024-02-29"));
+        assert_eq!(period_of(Period::Month, date(2026, 12, 31)), strings("2026-12-01", "2026-12", "2027-01-01"));
+        assert_eq!(period_of(Period::Month, date(2026, 1, 31)), strings("2026-01-01", "2026-01", "2026-02-01"));
+        assert_eq!(period_of(Period::Quarter, date(2026, 3, 31)), strings("2026-01-01", "2026 Q1", "2026-04-01"));
+        assert_eq!(period_of(Period::Quarter, date(2026, 7, 1)), strings("2026-07-01", "2026 Q3", "2026-10-01"));
+        assert_eq!(period_of(Period::Quarter, date(2026, 11, 30)), strings("2026-10-01", "2026 Q4", "2027-01-01"));
+        assert_eq!(period_of(Period::Year, date(2026, 7, 4)), strings("2026-01-01", "2026", "2027-01-01"));
+    }
+
+    #[test]
+    fn every_day_is_in_exactly_one_period() {
+        let mut day = date(2023, 12, 1);
+        while day < date(2027, 2, 1) {
+            for period in PERIODS {
+                let start = period.start(day);
+                let next = period.next(start);
+                assert!(start <= day && day < next, "{} isn't in the {} starting on {}", day.to_iso_string(), period.name(), start.to_iso_string());
+                assert_eq!(period.start(next), next);
+                assert_eq!(period.start(start), start);
+            }
+            day = Period::Day.next(day);
+        }
+    }
+
+    #[test]
+    fn periods_parse() {
+        assert!(parse_period("month", "--by").unwrap() == Period::Month);
+        assert!(parse_period(" Weekly ", "--by").unwrap() == Period::Week);
+        assert!(parse_period("daily", "--by").unwrap() == Period::Day);
+        assert!(parse_period("quarterly", "--by").unwrap() == Period::Quarter);
+        assert!(parse_period("fortnight", "--by").is_err());
+    }
+
+    #[test]
+    fn splits_add_up_to_the_amount() {
+        let minor = |amount: u64, weights: &[u64]| split(Money::from_minor(amount), weights).iter().map(|x| x.minor()).collect::<Vec<u64>>();
+        assert_eq!(minor(100, &[1, 2]), vec!(33, 67));
+        assert_eq!(minor(1000, &[300, 100]), vec!(750, 250));
+        // What doesn't divide evenly goes to the biggest, or the first of them when they tie
+        assert_eq!(minor(1000, &[1, 1, 1]), vec!(334, 333, 333));
+        assert_eq!(minor(1000, &[1, 5, 5]), vec!(90, 456, 454));
+        assert_eq!(minor(1000, &[0, 0]), vec!(500, 500));
+        assert_eq!(minor(7, &[0, 4]), vec!(0, 7));
+        assert_eq!(minor(0, &[5, 5]), vec!(0, 0));
+        assert_eq!(minor(u64::MAX, &[u64::MAX, u64::MAX]), vec!(u64::MAX / 2 + 1, u64::MAX / 2));
+    }
+
+    #[test]
+    fn orders_without_a_rate_stay_in_their_own_currency() {
+        let eur = Currency::parse("EUR", "currency").unwrap();
+        let mut ledger = Ledger::new();
+        ledger.orders.push(order(date(2026, 10, 1), USD, 1000));
+        ledger.orders.push(order(date(2026, 10, 2), eur, 2000));
+        let orders: Vec<(usize, &Order)> = ledger.orders.iter().enumerate().collect();
+        assert!(matches!(collect_entries(&ledger, &orders, Period::Month, None, Some(USD)), Err(Error::Validation { .. })));
+        let entries = collect_entries(&ledger, &orders, Period::Month, None, None).unwrap();
+        assert_eq!(entries.iter().map(|x| (x.1, x.2, x.4.minor())).collect::<Vec<_>>(), vec!((date(2026, 10, 1), 0, 1000), (date(2026, 10, 1), 1, 2000)));
+    }
+}
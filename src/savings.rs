@@ -0,0 +1,291 @@
+use std::path::Path;
+
+use crate::args::Args;
+use crate::category::Filter;
+use crate::discount;
+use crate::error::Error;
+use crate::money::{Currency, Money};
+use crate::report::print_table;
+use crate::vendor::vendor_name;
+use crate::{rates, storage, DateRange, Ledger, Order, Product};
+
+/*
+ * Where the money an order saved came from:
+ *
+ * - Sales: what the top level products cost less than their sticker prices, not counting what
+ *   their add-ons were discounted by (the sticker price of a product includes its add-ons).
+ * - Add-ons: what add-ons cost less than their sticker prices, wherever they are in the order.
+ * - Coupons: the order's discounts (see discount.rs). Orders with no discounts recorded can still
+ *   have a subtotal below what their products were paid, and that difference counts here too.
+ * - Bundles: how much more the sub-products of a product would have cost on their own (their
+ *   sticker prices added up) than the bundle's base price.
+ *
+ * Bundle value isn't money that was paid less, so it's shown separately from the rest, but it's
+ * part of what was saved altogether.
+ */
+
+// What add-ons were discounted by in product and everything in it, for one of product
+fn add_on_savings(product: &Product) -> Result<Money, Error> {
+    let mut saved: Vec<Money> = vec!();
+    for item in product.items.iter().flatten() {
+        saved.push(add_on_savings(item)?);
+    }
+    for add_on in product.add_ons.iter().flatten() {
+        saved.push(add_on.sticker_price.checked_sub(add_on.actual_price).unwrap_or_default());
+        if let Some(x) = &add_on.assoc_product {
+            saved.push(add_on_savings(x)?);
+        }
+    }
+    Money::sum(saved, &format!("the add-on discounts of {}", product.name.trim()))
+}
+
+// How much more the sub-products of every bundle in product cost on their own than the bundle, for
+// one of product
+fn bundle_value(product: &Product) -> Result<Money, Error> {
+    let mut value: Vec<Money> = vec!();
+    if let Some(x) = &product.items {
+        let separately = Money::sum(x.iter().map(|y| y.sticker_price), &format!("the sticker prices of the sub-products of {}", product.name.trim()))?;
+        value.push(separately.checked_sub(product.base_price).unwrap_or_default());
+    }
+    for item in product.items.iter().flatten() {
+        value.push(bundle_value(item)?);
+    }
+    for add_on in product.add_ons.iter().flatten() {
+        if let Some(x) = &add_on.assoc_product {
+            value.push(bundle_value(x)?);
+        }
+    }
+    Money::sum(value, &format!("the bundle value of {}", product.name.trim()))
+}
+
+fn times(amount: Money, quantity: u64, field: &str) -> Result<Money, Error> {
+    match amount.checked_mul(quantity) {
+        Some(x) => Ok(x),
+        None => Err(Error::validation(field, "times its quantity, it's more money than purchase_tracker can count"))
+    }
+}
+
+// What order saved, as [sales, add-ons, coupons, bundles], in its own currency
+fn attribute(order: &Order) -> Result<[Money; 4], Error> {
+    let (sales, coupons) = discount::savings(order)?;
+    let mut add_ons: Vec<Money> = vec!();
+    let mut bundles: Vec<Money> = vec!();
+    for line in &order.products {
+        let name = line.product.name.trim();
+        add_ons.push(times(add_on_savings(&line.product)?, line.quantity, &format!("the add-on discounts of {}", name))?);
+        bundles.push(times(bundle_value(&line.product)?, line.quantity, &format!("the bundle value of {}", name))?);
+    }
+    let add_ons = Money::sum(add_ons, "the add-on discounts")?;
+    let bundles = Money::sum(bundles, "the bundle value")?;
+    let paid = Money::sum(order.products.iter().map(|x| x.paid), "what was paid for the products")?;
+    let coupons = coupons.max(paid.checked_sub(order.subtotal).unwrap_or_default());
+    Ok([sales.checked_sub(add_ons).unwrap_or_default(), add_ons, coupons, bundles])
+}
+
+// One row of the table: how many orders there were, what they saved in each way and altogether
+fn row(label: &str, orders: usize, saved: [Money; 4], currency: Currency) -> Result<Vec<String>, Error> {
+    let mut cells = vec!(label.to_string(), orders.to_string());
+    cells.extend(saved.iter().map(|x| x.to_string_in(currency)));
+    cells.push(Money::sum(saved, "the savings")?.to_string_in(currency));
+    Ok(cells)
+}
+
+fn add(sum: [Money; 4], more: [Money; 4]) -> Result<[Money; 4], Error> {
+    let mut sum = sum;
+    for (x, y) in sum.iter_mut().zip(more) {
+        *x = Money::sum([*x, y], "the savings")?;
+    }
+    Ok(sum)
+}
+
+// The savings of orders added up per vendor, converted into currency at the rate from the day each
+// order was placed
+fn print_savings(ledger: &Ledger, orders: &[&Order], currency: Currency) -> Result<(), Error> {
+    let mut per_vendor: Vec<(&str, usize, [Money; 4])> = vec!();
+    let mut altogether = [Money::default(); 4];
+    for order in orders {
+        let mut saved = attribute(order)?;
+        for x in saved.iter_mut() {
+            *x = rates::convert(ledger, *x, order.currency, currency, order.date_placed)?;
+        }
+        let vendor = vendor_name(order, &ledger.vendors);
+        match per_vendor.iter_mut().find(|x| x.0 == vendor) {
+            Some(x) => {
+                x.1 += 1;
+                x.2 = add(x.2, saved)?;
+            },
+            None => per_vendor.push((vendor, 1, saved))
+        }
+        altogether = add(altogether, saved)?;
+    }
+    per_vendor.sort_by_key(|x| (x.0.starts_with('('), x.0.to_lowercase()));
+    let mut rows: Vec<Vec<String>> = vec!();
+    for (vendor, count, saved) in per_vendor {
+        rows.push(row(vendor, count, saved, currency)?);
+    }
+    rows.push(row("Altogether", orders.len(), altogether, currency)?);
+    print_table("", &["Vendor", "Orders", "Sales", "Add-ons", "Coupons", "Bundles", "Saved"], &rows);
+    Ok(())
+}
+
+// purchase_tracker savings [FILE] [--from DATE] [--to DATE] [--in CURRENCY] [--category CATEGORY] [--tag TAG]
+pub fn savings_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["from", "to", "in", "category", "tag"], &[])?;
+    let path = match args.positional.as_slice() {
+        [x] => Path::new(x),
+        _ => return Err(Error::usage("purchase_tracker savings [FILE] [--from DATE] [--to DATE] [--in CURRENCY] [--category CATEGORY] [--tag TAG]"))
+    };
+    let range = DateRange::from_args(&args)?;
+    let filter = Filter::from_args(&args);
+    let ledger = storage::load(path)?;
+    let currency = match args.value("in") {
+        Some(x) => Currency::parse(x, "--in")?,
+        None => ledger.home_currency
+    };
+
+    let orders: Vec<&Order> = ledger.orders.iter().filter(|x| range.contains(x) && filter.matches(x)).collect();
+    if orders.is_empty() {
+        println!("{} doesn't have any orders{}{}.", path.display(), range.describe(), filter.describe());
+        return Ok(());
+    }
+    println!("Savings on orders{}{} in {}:", range.describe(), filter.describe(), currency.code());
+    print_savings(&ledger, &orders, currency)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discount::{Amount, Discount, Source};
+    use crate::money::USD;
+    use crate::{AddOn, Date, OrderLine};
+
+    fn product(name: &str, base_price: u64, sticker_price: u64) -> Product {
+        Product {
+            name: name.to_string(),
+            desc: "".to_string(),
+            base_price: Money::from_minor(base_price),
+            sticker_price: Money::from_minor(sticker_price),
+            category: "".to_string(),
+            tags: vec!(),
+            items: None,
+            add_ons: None
+        }
+    }
+
+    fn add_on(sticker_price: u64, actual_price: u64) -> AddOn {
+        AddOn {
+            name: "Grip".to_string(),
+            desc: "".to_string(),
+            sticker_price: Money::from_minor(sticker_price),
+            actual_price: Money::from_minor(actual_price),
+            category: "".to_string(),
+            tags: vec!(),
+            assoc_product: None
+        }
+    }
+
+    fn line(product: Product, quantity: u64, paid: u64) -> OrderLine {
+        OrderLine {
+            product,
+            quantity,
+            unit_price: Money::from_minor(paid / quantity),
+            paid: Money::from_minor(paid),
+            discount_reason: "".to_string(),
+            notes: "".to_string()
+        }
+    }
+
+    fn discount(amount: Amount, lines: Option<Vec<usize>>) -> Discount {
+        Discount {
+            source: Source::Promo,
+            code: "".to_string(),
+            amount,
+            lines
+        }
+    }
+
+    // An order of products for their subtotal, which is what they were paid less the discounts
+    fn order(products: Vec<OrderLine>, discounts: Vec<Discount>, subtotal: u64) -> Order {
+        Order {
+            date_placed: Date::new(2026, 10, 1).unwrap(),
+            vendor: None,
+            status_history: vec!(),
+            currency: USD,
+            subtotal: Money::from_minor(subtotal),
+            breakdown: None,
+            total: Money::from_minor(subtotal),
+            products,
+            discounts,
+            refunds: vec!(),
+            payments: vec!(),
+            tags: vec!(),
+            notes: "".to_string()
+        }
+    }
+
+    fn saved(order: &Order) -> [u64; 4] {
+        attribute(order).unwrap().map(|x| x.minor())
+    }
+
+    #[test]
+    fn add_on_discounts_arent_counted_as_sales() {
+        // $50 on the sticker with a $6 grip in it, the grip went for $4 and the whole thing for $45
+        let mut sword = product("Sword", 4400, 5000);
+        sword.add_ons = Some(vec!(add_on(600, 400)));
+        assert_eq!(saved(&order(vec!(line(sword, 1, 4500)), vec!(), 4500)), [300, 200, 0, 0]);
+
+        let mut sword = product("Sword", 4400, 5000);
+        sword.add_ons = Some(vec!(add_on(600, 400)));
+        assert_eq!(saved(&order(vec!(line(sword, 2, 9000)), vec!(), 9000)), [600, 400, 0, 0]);
+
+        // Add-ons of sub-products and of the products that came with add-ons count too, and sales
+        // never go below nothing
+        let mut blade = product("Blade", 1000, 1000);
+        blade.add_ons = Some(vec!(add_on(300, 100)));
+        let mut grip = add_on(600, 500);
+        let mut wrap = product("Wrap", 100, 100);
+        wrap.add_ons = Some(vec!(add_on(50, 0)));
+        grip.assoc_product = Some(wrap);
+        let mut sword = product("Sword", 5000, 5000);
+        sword.items = Some(vec!(blade));
+        sword.add_ons = Some(vec!(grip));
+        assert_eq!(saved(&order(vec!(line(sword, 1, 4800)), vec!(), 4800)), [0, 350, 0, 0]);
+    }
+
+    #[test]
+    fn discounts_can_cover_several_products() {
+        let products = vec!(line(product("Mask", 2000, 2000), 1, 2000), line(product("Glove", 1000, 1000), 1, 1000), line(product("Jacket", 3000, 3000), 1, 3000));
+        // 10% of the $50 paid for the mask and the jacket, and $2 off everything
+        let discounts = vec!(discount(Amount::Percent(1000), Some(vec!(0, 2))), discount(Amount::Fixed(Money::from_minor(200)), None));
+        assert_eq!(saved(&order(products, discounts, 5300)), [0, 0, 700, 0]);
+
+        let products = vec!(line(product("Mask", 2000, 2500), 1, 2000), line(product("Glove", 1000, 1000), 2, 1500));
+        let discounts = vec!(discount(Amount::Percent(2000), Some(vec!(0, 1))));
+        assert_eq!(saved(&order(products, discounts, 2800)), [1000, 0, 700, 0]);
+    }
+
+    #[test]
+    fn unrecorded_coupons_come_from_the_subtotal() {
+        let products = || vec!(line(product("Mask", 2000, 2000), 1, 2000), line(product("Glove", 1000, 1000), 1, 1000));
+        assert_eq!(saved(&order(products(), vec!(), 2500)), [0, 0, 500, 0]);
+        // Whichever says more was taken off counts, so nothing is counted twice
+        let discounts = vec!(discount(Amount::Fixed(Money::from_minor(300)), None));
+        assert_eq!(saved(&order(products(), discounts, 2500)), [0, 0, 500, 0]);
+        let discounts = vec!(discount(Amount::Fixed(Money::from_minor(300)), None));
+        assert_eq!(saved(&order(products(), discounts, 3000)), [0, 0, 300, 0]);
+    }
+
+    #[test]
+    fn bundles_are_worth_what_their_parts_would_have_cost() {
+        // A $50 kit of a $30 blade and a $25 mask, and the blade is a $10 bundle of $6 parts
+        let mut blade = product("Blade", 1000, 3000);
+        blade.items = Some(vec!(product("Tip", 600, 600), product("Tang", 600, 600)));
+        let mut kit = product("Kit", 5000, 5000);
+        kit.items = Some(vec!(blade, product("Mask", 2500, 2500)));
+        assert_eq!(saved(&order(vec!(line(kit, 2, 10000)), vec!(), 10000)), [0, 0, 0, 1400]);
+        // Sub-products that cost less on their own than the bundle don't count against it
+        let mut kit = product("Kit", 5000, 5000);
+        kit.items = Some(vec!(product("Mask", 2000, 2000)));
+        assert_eq!(saved(&order(vec!(line(kit, 1, 5000)), vec!(), 5000)), [0, 0, 0, 0]);
+    }
+}
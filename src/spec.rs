@@ -0,0 +1,717 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::args::Args;
+use crate::catalog::{self, CatalogEntry};
+use crate::category::{parse_category, parse_tags};
+use crate::discount::{self, parse_amount, parse_source, Amount, Discount};
+use crate::error::Error;
+use crate::fsck;
+use crate::money::{Currency, Money};
+use crate::payment::{self, Payment};
+use crate::vendor::{self, Vendor};
+use crate::{parse_date, parse_status, parse_percent, status_history, storage, AddOn, Breakdown, Date, Ledger, Order, OrderLine, Product, Status};
+
+/*
+ * A product file describes the products of an order in TOML, so that orders can be added by
+ * scripts instead of by typing answers into order_cli. For example:
+ *
+ *   [[products]]
+ *   name = "Economy Rapier"
+ *   desc = "Darkwood Armory Economy Rapier"
+ *   base_price = "350.00"
+ *   sticker_price = "355.00"
+ *   paid = "$355"                 # Only on top level products, see OrderLine
+ *   quantity = 1                  # Optional, also only on top level products. With more than
+ *   unit_price = "$355"           # one, paid is for all of them and unit_price for one, and
+ *                                 # either can be left out if it's unit_price times quantity.
+ *   discount_reason = ""          # Optional and only on top level products, like notes
+ *   notes = "Gift for Sam"
+ *   category = "Hobby > Fencing"  # Optional, see category.rs. Sub-products and add-ons can have
+ *   tags = ["gift"]               # these too, and inherit them when they don't
+ *
+ *     [[products.items]]          # Sub-products, same fields as a product minus the last five
+ *     name = "DA1 Practice Rapier Blade"
+ *     base_price = "175.00"
+ *     sticker_price = "175.00"
+ *
+ *     [[products.add_ons]]
+ *     name = "Leather Grip"
+ *     sticker_price = "5.00"
+ *     actual_price = "5.00"
+ *
+ *       [products.add_ons.assoc_product]   # Optional, same fields as a sub-product
+ *       name = "Leather Grip Wrap"
+ *       base_price = "5.00"
+ *       sticker_price = "5.00"
+ *
+ * A product that's in the catalog (see catalog.rs) can be copied from there by its name instead:
+ *
+ *   [[products]]
+ *   catalog = "Economy Rapier"
+ *   paid = "340.00"               # Any other field given here replaces the one from the catalog
+ *
+ * Coupons and other discounts on the order (see discount.rs) go in their own list:
+ *
+ *   [[discounts]]
+ *   source = "promo"              # Or "loyalty", "price match" or "other"
+ *   code = "SAVE10"               # Optional
+ *   amount = "10%"                # A percentage, or an amount like the prices below
+ *   products = [0]                # Optional, leave it out for the whole order
+ *
+ * And so does what the order was paid with (see payment.rs), when it was split:
+ *
+ *   [[payments]]
+ *   method = "Gift card"          # The name of a payment method the file already has
+ *   amount = "10.00"
+ *
+ *   [[payments]]
+ *   method = "Visa"               # amount can be left out of the last one, for whatever's left
+ *
+ * Prices are strings in any form Money::parse understands, or bare integers for a number of cents
+ * (or whatever the smallest unit of the order's currency is, which is how product files were
+ * written before Money existed). The file may also set any of placed, shipped, status,
+ * status_date, vendor, currency, subtotal, shipping, tax, tax_rate, fees, tip, credits, total,
+ * tags (a list, like on products) and notes at the top level, for when the whole order comes from
+ * the file. Flags given to the add command take precedence over those.
+ */
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProductFile {
+    placed: Option<String>,
+    shipped: Option<String>,
+    status: Option<String>,
+    status_date: Option<String>,
+    vendor: Option<String>,
+    currency: Option<String>,
+    subtotal: Option<MoneySpec>,
+    shipping: Option<MoneySpec>,
+    tax: Option<MoneySpec>,
+    tax_rate: Option<String>,
+    fees: Option<MoneySpec>,
+    tip: Option<MoneySpec>,
+    credits: Option<MoneySpec>,
+    total: Option<MoneySpec>,
+    tags: Option<Vec<String>>,
+    notes: Option<String>,
+    #[serde(default)]
+    products: Vec<ProductSpec>,
+    #[serde(default)]
+    discounts: Vec<DiscountSpec>,
+    #[serde(default)]
+    payments: Vec<PaymentSpec>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProductSpec {
+    catalog: Option<String>,
+    name: Option<String>, // Only optional for products from the catalog, like the prices
+    desc: Option<String>,
+    base_price: Option<MoneySpec>,
+    sticker_price: Option<MoneySpec>,
+    paid: Option<MoneySpec>,
+    quantity: Option<u64>,
+    unit_price: Option<MoneySpec>,
+    discount_reason: Option<String>,
+    notes: Option<String>,
+    category: Option<String>,
+    tags: Option<Vec<String>>,
+    items: Option<Vec<ProductSpec>>,
+    add_ons: Option<Vec<AddOnSpec>>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AddOnSpec {
+    name: String,
+    #[serde(default)]
+    desc: String,
+    sticker_price: MoneySpec,
+    actual_price: MoneySpec,
+    category: Option<String>,
+    tags: Option<Vec<String>>,
+    assoc_product: Option<ProductSpec>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiscountSpec {
+    source: String,
+    code: Option<String>,
+    amount: MoneySpec,
+    products: Option<Vec<usize>>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PaymentSpec {
+    method: String,
+    amount: Option<MoneySpec>
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MoneySpec {
+    Cents(u64),
+    Text(String)
+}
+
+impl MoneySpec {
+    // field is where the amount sits in the file, for error messages
+    fn into_money(self, currency: Currency, field: &str) -> Result<Money, Error> {
+        match self {
+            MoneySpec::Cents(x) => Ok(Money::from_minor(x)),
+            MoneySpec::Text(x) => Money::parse(&x, currency, field)
+        }
+    }
+}
+
+impl ProductSpec {
+    // location is where this product sits in the file, for error messages
+    fn into_product(self, currency: Currency, location: &str, catalog: &[CatalogEntry]) -> Result<Product, Error> {
+        if self.paid.is_some() || self.quantity.is_some() || self.unit_price.is_some() || self.discount_reason.is_some() || self.notes.is_some() {
+            return Err(Error::validation(location, "it has a \"paid\" amount, a quantity, a unit price, a discount reason or notes, but only top level products can have those"));
+        }
+        let from_catalog = match &self.catalog {
+            None => None,
+            Some(x) => {
+                let (product, from) = catalog::lookup(catalog, x, &format!("{}.catalog", location))?;
+                if from != currency {
+                    return Err(Error::validation(location, format!("the catalog has the prices of {} in {}, but the order is in {}", product.name.trim(), from.code(), currency.code())));
+                }
+                Some(product)
+            }
+        };
+        let missing = |field: &str| Error::validation(&format!("{}.{}", location, field), "it's missing. Only products copied from the catalog can leave it out");
+        let items = match self.items {
+            None => from_catalog.as_ref().and_then(|x| x.items.clone()),
+            Some(x) => {
+                let mut items: Vec<Product> = vec!();
+                for (index, item) in x.into_iter().enumerate() {
+                    items.push(item.into_product(currency, &format!("{}.items[{}]", location, index), catalog)?);
+                }
+                Some(items)
+            }
+        };
+        let add_ons = match self.add_ons {
+            None => from_catalog.as_ref().and_then(|x| x.add_ons.clone()),
+            Some(x) => {
+                let mut add_ons: Vec<AddOn> = vec!();
+                for (index, add_on) in x.into_iter().enumerate() {
+                    add_ons.push(add_on.into_add_on(currency, &format!("{}.add_ons[{}]", location, index), catalog)?);
+                }
+                Some(add_ons)
+            }
+        };
+        Ok(Product {
+            name: match (self.name, &from_catalog) {
+                (Some(x), _) => x,
+                (None, Some(y)) => y.name.clone(),
+                (None, None) => return Err(missing("name"))
+            },
+            desc: match (self.desc, &from_catalog) {
+                (Some(x), _) => x,
+                (None, Some(y)) => y.desc.clone(),
+                (None, None) => "".to_string()
+            },
+            base_price: match (self.base_price, &from_catalog) {
+                (Some(x), _) => x.into_money(currency, &format!("{}.base_price", location))?,
+                (None, Some(y)) => y.base_price,
+                (None, None) => return Err(missing("base_price"))
+            },
+            sticker_price: match (self.sticker_price, &from_catalog) {
+                (Some(x), _) => x.into_money(currency, &format!("{}.sticker_price", location))?,
+                (None, Some(y)) => y.sticker_price,
+                (None, None) => return Err(missing("sticker_price"))
+            },
+            category: match (self.category, &from_catalog) {
+                (Some(x), _) => parse_category(&x),
+                (None, Some(y)) => y.category.clone(),
+                (None, None) => "".to_string()
+            },
+            tags: match (self.tags, &from_catalog) {
+                (Some(x), _) => parse_tags(&x.join(",")),
+                (None, Some(y)) => y.tags.clone(),
+                (None, None) => vec!()
+            },
+            items,
+            add_ons
+        })
+    }
+}
+
+impl AddOnSpec {
+    fn into_add_on(self, currency: Currency, location: &str, catalog: &[CatalogEntry]) -> Result<AddOn, Error> {
+        let assoc_product = match self.assoc_product {
+            None => None,
+            Some(x) => Some(x.into_product(currency, &format!("{}.assoc_product", location), catalog)?)
+        };
+        Ok(AddOn {
+            name: self.name,
+            desc: self.desc,
+            sticker_price: self.sticker_price.into_money(currency, &format!("{}.sticker_price", location))?,
+            actual_price: self.actual_price.into_money(currency, &format!("{}.actual_price", location))?,
+            category: parse_category(&self.category.unwrap_or_default()),
+            tags: parse_tags(&self.tags.unwrap_or_default().join(",")),
+            assoc_product
+        })
+    }
+}
+
+// purchase_tracker add [FILE] --placed DATE [--shipped DATE] [--status STATUS] [--status-date DATE] [--vendor NAME] [--currency CODE] --subtotal AMOUNT [--shipping AMOUNT] [--tax AMOUNT] [--tax-rate PERCENT] [--fees AMOUNT] [--tip AMOUNT] [--credits AMOUNT] --total AMOUNT [--paid-with METHOD] [--tags TAGS] [--notes TEXT] [--product-file PRODUCTS.toml] [--strict]
+pub fn add_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["placed", "shipped", "status", "status-date", "vendor", "currency", "subtotal", "shipping", "tax", "tax-rate", "fees", "tip", "credits", "total", "notes", "paid-with", "tags", "product-file"], &["strict"])?;
+    let path = match args.positional.as_slice() {
+        [x] => Path::new(x),
+        _ => return Err(Error::usage("purchase_tracker add [FILE] --placed DATE [--shipped DATE] [--status STATUS] [--status-date DATE] [--vendor NAME] [--currency CODE] --subtotal AMOUNT [--shipping AMOUNT] [--tax AMOUNT] [--tax-rate PERCENT] [--fees AMOUNT] [--tip AMOUNT] [--credits AMOUNT] --total AMOUNT [--paid-with METHOD] [--tags TAGS] [--notes TEXT] [--product-file PRODUCTS.toml] [--strict]"))
+    };
+
+    let file = match args.value("product-file") {
+        None => ProductFile {
+            placed: None,
+            shipped: None,
+            status: None,
+            status_date: None,
+            vendor: None,
+            currency: None,
+            subtotal: None,
+            shipping: None,
+            tax: None,
+            tax_rate: None,
+            fees: None,
+            tip: None,
+            credits: None,
+            total: None,
+            tags: None,
+            notes: None,
+            products: vec!(),
+            discounts: vec!(),
+            payments: vec!()
+        },
+        Some(x) => {
+            let contents = match fs::read_to_string(x) {
+                Ok(y) => y,
+                Err(y) => return Err(Error::io("reading the product file", Path::new(x), y))
+            };
+            match toml::from_str(&contents) {
+                Ok(y) => y,
+                Err(y) => return Err(Error::parse("a product file", x, y))
+            }
+        }
+    };
+
+    let date_placed = match (args.value("placed"), &file.placed) {
+        (Some(x), _) => parse_date(x, "--placed")?,
+        (None, Some(x)) => parse_date(x, "\"placed\" in the product file")?,
+        (None, None) => return Err(Error::validation("--placed", "the date the order was placed is missing. Pass it with --placed (or set \"placed\" in the product file)."))
+    };
+    let date_shipped: Option<Date> = match (args.value("shipped"), &file.shipped) {
+        (Some(x), _) => Some(parse_date(x, "--shipped")?),
+        (None, Some(x)) => Some(parse_date(x, "\"shipped\" in the product file")?),
+        (None, None) => None
+    };
+    // Giving a shipping date without a status means the order has shipped
+    let status = match (args.value("status"), &file.status, date_shipped) {
+        (Some(x), _, _) => parse_status(x, "--status")?,
+        (None, Some(x), _) => parse_status(x, "\"status\" in the product file")?,
+        (None, None, Some(_)) => Status::Shipped,
+        (None, None, None) => Status::Placed
+    };
+    if date_shipped.is_some() && !status.has_shipped() {
+        return Err(Error::validation("--shipped", format!("an order that's {} hasn't shipped, so it can't have a shipping date", status.name())));
+    }
+    let status_date: Option<Date> = match (args.value("status-date"), &file.status_date) {
+        (Some(x), _) => Some(parse_date(x, "--status-date")?),
+        (None, Some(x)) => Some(parse_date(x, "\"status_date\" in the product file")?),
+        (None, None) => None
+    };
+    if status_date.is_some() && (status == Status::Placed || status == Status::Shipped) {
+        return Err(Error::validation("--status-date", format!("it's only for orders that went past being shipped or placed. Use --placed or --shipped for the date the order became {}", status.name())));
+    }
+    let mut ledger: Ledger = match fs::exists(path) {
+        Ok(true) => storage::load(path)?,
+        Ok(false) => Ledger::new(),
+        Err(x) => return Err(Error::io("checking whether this file exists:", path, x))
+    };
+    // A vendor that isn't in the ledger yet gets added with just its name
+    let known_vendors = ledger.vendors.len();
+    let vendor = match args.value("vendor").or(file.vendor.as_deref()).map(|x| x.trim()).filter(|x| !x.is_empty()) {
+        None => None,
+        Some(x) => match vendor::find(&ledger.vendors, x) {
+            Some(y) => Some(y),
+            None => {
+                ledger.vendors.push(Vendor {
+                    name: x.to_string(),
+                    website: "".to_string(),
+                    contact: "".to_string(),
+                    notes: "".to_string()
+                });
+                Some(ledger.vendors.len() - 1)
+            }
+        }
+    };
+    let currency = match (args.value("currency"), &file.currency) {
+        (Some(x), _) => Currency::parse(x, "--currency")?,
+        (None, Some(x)) => Currency::parse(x, "\"currency\" in the product file")?,
+        (None, None) => ledger.home_currency
+    };
+    let subtotal = match (args.value("subtotal"), file.subtotal) {
+        (Some(x), _) => Money::parse(x, currency, "--subtotal")?,
+        (None, Some(x)) => x.into_money(currency, "\"subtotal\" in the product file")?,
+        (None, None) => return Err(Error::validation("--subtotal", "the subtotal is missing. Pass it with --subtotal (or set \"subtotal\" in the product file)."))
+    };
+    // Only orders that say something about where the total came from get a breakdown, and
+    // anything that isn't mentioned is 0
+    let component = |name: &str, from_file: Option<MoneySpec>| -> Result<Option<Money>, Error> {
+        match (args.value(name), from_file) {
+            (Some(x), _) => Money::parse(x, currency, &format!("--{}", name)).map(Some),
+            (None, Some(x)) => x.into_money(currency, &format!("\"{}\" in the product file", name)).map(Some),
+            (None, None) => Ok(None)
+        }
+    };
+    let shipping = component("shipping", file.shipping)?;
+    let tax = component("tax", file.tax)?;
+    let fees = component("fees", file.fees)?;
+    let tip = component("tip", file.tip)?;
+    let credits = component("credits", file.credits)?;
+    let tax_rate = match (args.value("tax-rate"), &file.tax_rate) {
+        (Some(x), _) => Some(parse_percent(x, "--tax-rate")?),
+        (None, Some(x)) => Some(parse_percent(x, "\"tax_rate\" in the product file")?),
+        (None, None) => None
+    };
+    let mentioned = [shipping, tax, fees, tip, credits].iter().any(|x| x.is_some()) || tax_rate.is_some();
+    let breakdown = match mentioned {
+        true => Some(Breakdown {
+            shipping: shipping.unwrap_or_default(),
+            tax: tax.unwrap_or_default(),
+            tax_rate,
+            fees: fees.unwrap_or_default(),
+            tip: tip.unwrap_or_default(),
+            credits: credits.unwrap_or_default()
+        }),
+        false => None
+    };
+    let total = match (args.value("total"), file.total) {
+        (Some(x), _) => Money::parse(x, currency, "--total")?,
+        (None, Some(x)) => x.into_money(currency, "\"total\" in the product file")?,
+        (None, None) => return Err(Error::validation("--total", "the total is missing. Pass it with --total (or set \"total\" in the product file)."))
+    };
+    // --tags takes them separated by commas, like the order creator
+    let tags = match (args.value("tags"), file.tags) {
+        (Some(x), _) => parse_tags(x),
+        (None, Some(x)) => parse_tags(&x.join(",")),
+        (None, None) => vec!()
+    };
+    let notes = match args.value("notes") {
+        Some(x) => x.to_string(),
+        None => file.notes.unwrap_or_default()
+    };
+
+    let mut products: Vec<OrderLine> = vec!();
+    for (index, mut spec) in file.products.into_iter().enumerate() {
+        let location = format!("products[{}]", index);
+        let quantity = match spec.quantity.take() {
+            Some(0) => return Err(Error::validation(&format!("{}.quantity", location), "it has to be at least 1")),
+            Some(x) => x,
+            None => 1
+        };
+        let unit_price = match spec.unit_price.take() {
+            Some(x) => Some(x.into_money(currency, &format!("{}.unit_price", location))?),
+            None => None
+        };
+        let paid = match spec.paid.take() {
+            Some(x) => Some(x.into_money(currency, &format!("{}.paid", location))?),
+            None => None
+        };
+        let (unit_price, paid) = match (unit_price, paid) {
+            (Some(x), Some(y)) => (x, y),
+            (Some(x), None) => match x.checked_mul(quantity) {
+                Some(y) => (x, y),
+                None => return Err(Error::validation(&location, "its unit price times its quantity is more money than purchase_tracker can count"))
+            },
+            (None, Some(y)) if quantity == 1 => (y, y),
+            (None, Some(_)) => return Err(Error::validation(&location, format!("it has a quantity of {}, so it needs a \"unit_price\" (the cost of one of them) as well as what was \"paid\" for all of them", quantity))),
+            (None, None) => return Err(Error::validation(&location, "it's missing its \"paid\" amount (the cost of the product accounting for all add-ons and sales)"))
+        };
+        let discount_reason = spec.discount_reason.take().unwrap_or_default().trim().to_string();
+        let notes = spec.notes.take().unwrap_or_default().trim().to_string();
+        products.push(OrderLine {
+            product: spec.into_product(currency, &location, &ledger.catalog)?,
+            quantity,
+            unit_price,
+            paid,
+            discount_reason,
+            notes
+        });
+    }
+
+    let mut discounts: Vec<Discount> = vec!();
+    for (index, spec) in file.discounts.into_iter().enumerate() {
+        let location = format!("discounts[{}]", index);
+        discounts.push(Discount {
+            source: parse_source(&spec.source, &format!("{}.source", location))?,
+            code: spec.code.unwrap_or_default().trim().to_string(),
+            amount: match spec.amount {
+                MoneySpec::Cents(x) => Amount::Fixed(Money::from_minor(x)),
+                MoneySpec::Text(x) => parse_amount(&x, currency, &format!("{}.amount", location))?
+            },
+            lines: spec.products
+        });
+    }
+
+    // --paid-with is for orders paid entirely with one method, the file can split them up
+    let method = |name: &str, field: &str| match payment::find(&ledger.payment_methods, name) {
+        Some(x) => Ok(x),
+        None => Err(Error::validation(field, format!("{} doesn't have a payment method called \"{}\". Add it first with 'purchase_tracker payments add'", path.display(), name.trim())))
+    };
+    let mut payments: Vec<Payment> = vec!();
+    match args.value("paid-with") {
+        Some(x) => payments.push(Payment {
+            method: method(x, "--paid-with")?,
+            amount: total
+        }),
+        None => {
+            let count = file.payments.len();
+            for (index, spec) in file.payments.into_iter().enumerate() {
+                let location = format!("payments[{}]", index);
+                let amount = match spec.amount {
+                    Some(x) => x.into_money(currency, &format!("{}.amount", location))?,
+                    None if index + 1 == count => match total.checked_sub(Money::sum(payments.iter().map(|x| x.amount), "the payments")?) {
+                        Some(x) => x,
+                        None => return Err(Error::validation("the payments", "the ones before the last already add up to more than the total"))
+                    },
+                    None => return Err(Error::validation(&location, "it's missing its \"amount\", which only the last payment can leave out"))
+                };
+                payments.push(Payment {
+                    method: method(&spec.method, &format!("{}.method", location))?,
+                    amount
+                });
+            }
+        }
+    }
+
+    let order = Order {
+        date_placed,
+        vendor,
+        status_history: status_history(status, date_shipped, status_date),
+        currency,
+        subtotal,
+        breakdown,
+        total,
+        products,
+        discounts,
+        refunds: vec!(),
+        payments,
+        tags,
+        notes
+    };
+
+    discount::check(&order)?;
+    payment::check(&order, &ledger.payment_methods)?;
+    order.check_total()?;
+    fsck::check_new_order(&order, args.switch("strict"))?;
+    println!("Adding order:\n{}", order.to_string());
+    if let Some(x) = order.vendor {
+        println!("Vendor: {}", ledger.vendors[x].name);
+    }
+    ledger.orders.push(order);
+    storage::save(path, &ledger)?;
+    println!("Success! The order was saved to {} as order {}.", path.display(), ledger.orders.len() - 1);
+    if ledger.vendors.len() > known_vendors {
+        println!("{} was a new vendor, so it was added too. Use 'purchase_tracker vendors set' to fill in its details.", ledger.vendors[ledger.vendors.len() - 1].name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::USD;
+    use std::env;
+    use std::path::PathBuf;
+    use std::process;
+
+    const FULL: &str = r#"
+        placed = "2025-10-01"
+        subtotal = "380.00"
+        total = 38500
+        tags = ["gift"]
+
+        [[products]]
+        name = "Economy Rapier"
+        base_price = "350.00"
+        sticker_price = 35500
+        quantity = 2
+        unit_price = "$170"
+        paid = "340.00"
+        category = "Hobby > Fencing"
+
+          [[products.items]]
+          name = "Practice Blade"
+          base_price = "175.00"
+          sticker_price = "175.00"
+
+          [[products.add_ons]]
+          name = "Leather Grip"
+          sticker_price = "5.00"
+          actual_price = 300
+
+            [products.add_ons.assoc_product]
+            name = "Grip Wrap"
+            base_price = "5.00"
+            sticker_price = "5.00"
+
+        [[discounts]]
+        source = "promo"
+        code = "SAVE10"
+        amount = "10%"
+        products = [0]
+
+        [[payments]]
+        method = "Visa"
+    "#;
+
+    fn parse(contents: &str) -> Result<ProductFile, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    fn entry(name: &str, currency: Currency, retired: bool) -> CatalogEntry {
+        CatalogEntry::upgraded(Product {
+            name: name.to_string(),
+            desc: "From the catalog".to_string(),
+            base_price: Money::from_minor(4000),
+            sticker_price: Money::from_minor(4500),
+            category: "Hobby".to_string(),
+            tags: vec!("fencing".to_string()),
+            items: None,
+            add_ons: None
+        }, currency, retired)
+    }
+
+    fn is_invalid<T>(result: Result<T, Error>, location: &str) -> bool {
+        matches!(result, Err(Error::Validation { field, .. }) if field == location)
+    }
+
+    // A directory of its own for tests that run add, which is gone again once they're done
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Scratch {
+            let path = env::temp_dir().join(format!("purchase_tracker_spec_{}_{}", process::id(), name));
+            fs::create_dir_all(&path).unwrap();
+            Scratch(path)
+        }
+
+        fn add(&self, product_file: &str, flags: &[&str]) -> Result<(), Error> {
+            let products = self.0.join("products.toml");
+            fs::write(&products, product_file).unwrap();
+            let mut args = vec!(self.0.join("orders.ptl").display().to_string(), "--product-file".to_string(), products.display().to_string());
+            args.extend(flags.iter().map(|x| x.to_string()));
+            add_cli(&args)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn product_files_parse() {
+        let file = parse(FULL).unwrap();
+        assert_eq!(file.placed.as_deref(), Some("2025-10-01"));
+        assert!(matches!(file.total, Some(MoneySpec::Cents(38500))));
+        assert!(matches!(&file.subtotal, Some(MoneySpec::Text(x)) if x == "380.00"));
+        assert_eq!(file.tags, Some(vec!("gift".to_string())));
+        assert_eq!(file.products.len(), 1);
+        assert_eq!(file.products[0].quantity, Some(2));
+        assert_eq!(file.discounts[0].products, Some(vec!(0)));
+        assert_eq!(file.payments[0].method, "Visa");
+        assert!(file.payments[0].amount.is_none());
+
+        let mut spec = parse(FULL).unwrap().products.remove(0);
+        spec.quantity = None;
+        spec.unit_price = None;
+        spec.paid = None;
+        let product = spec.into_product(USD, "products[0]", &[]).unwrap();
+        assert_eq!(product.name, "Economy Rapier");
+        assert_eq!(product.desc, "");
+        // Bare integers are a number of cents, strings go through Money::parse
+        assert_eq!(product.base_price, Money::from_minor(35000));
+        assert_eq!(product.sticker_price, Money::from_minor(35500));
+        assert_eq!(product.category, "Hobby > Fencing");
+        assert_eq!(product.items.as_ref().map(|x| x[0].name.as_str()), Some("Practice Blade"));
+        let add_ons = product.add_ons.unwrap();
+        assert_eq!(add_ons[0].actual_price, Money::from_minor(300));
+        assert_eq!(add_ons[0].assoc_product.as_ref().map(|x| x.base_price), Some(Money::from_minor(500)));
+    }
+
+    #[test]
+    fn bad_product_files_are_rejected() {
+        assert!(parse("placed = \"2025-10-01\"\nnmae = \"Mask\"").is_err());
+        assert!(parse("[[products]]\nname = \"Mask\"\ncolour = \"white\"").is_err());
+        assert!(parse("[[products]]\nname = \"Mask\"\nbase_price = -5").is_err());
+        assert!(parse("[[discounts]]\ncode = \"SAVE10\"\namount = \"10%\"").is_err());
+        assert!(parse("").unwrap().products.is_empty());
+    }
+
+    #[test]
+    fn missing_fields_are_reported_where_they_are() {
+        let file = parse("[[products]]\nname = \"Mask\"\nsticker_price = \"45.00\"\npaid = \"45.00\"").unwrap();
+        let mut spec = file.products.into_iter().next().unwrap();
+        spec.paid = None;
+        assert!(is_invalid(spec.into_product(USD, "products[0]", &[]), "products[0].base_price"));
+
+        let file = parse("[[products]]\nbase_price = 1\nsticker_price = 1\n[[products.items]]\nbase_price = 1\nsticker_price = 1").unwrap();
+        let mut spec = file.products.into_iter().next().unwrap();
+        spec.name = Some("Mask".to_string());
+        assert!(is_invalid(spec.into_product(USD, "products[0]", &[]), "products[0].items[0].name"));
+
+        // What was paid belongs to the order line, so sub-products can't have it
+        let file = parse("[[products]]\nname = \"Mask\"\nbase_price = 1\nsticker_price = 1\n[[products.items]]\nname = \"Bib\"\nbase_price = 1\nsticker_price = 1\npaid = 1").unwrap();
+        let spec = file.products.into_iter().next().unwrap();
+        assert!(is_invalid(spec.into_product(USD, "products[0]", &[]), "products[0].items[0]"));
+    }
+
+    #[test]
+    fn products_can_come_from_the_catalog() {
+        let catalog = vec!(entry("Mask", USD, false), entry("Glove", USD, true), entry("Jacket", Currency::parse("EUR", "").unwrap(), false));
+        let file = parse("[[products]]\ncatalog = \"mask\"\nsticker_price = \"42.00\"\n[[products]]\ncatalog = \"Glove\"\n[[products]]\ncatalog = \"Jacket\"\n[[products]]\ncatalog = \"Sabre\"").unwrap();
+        let mut specs = file.products.into_iter();
+
+        // Anything the file gives replaces what the catalog has
+        let mask = specs.next().unwrap().into_product(USD, "products[0]", &catalog).unwrap();
+        assert_eq!(mask.name, "Mask");
+        assert_eq!(mask.desc, "From the catalog");
+        assert_eq!(mask.base_price, Money::from_minor(4000));
+        assert_eq!(mask.sticker_price, Money::from_minor(4200));
+        assert_eq!(mask.category, "Hobby");
+        assert_eq!(mask.tags, vec!("fencing".to_string()));
+
+        assert!(is_invalid(specs.next().unwrap().into_product(USD, "products[1]", &catalog), "products[1].catalog"));
+        assert!(is_invalid(specs.next().unwrap().into_product(USD, "products[2]", &catalog), "products[2]"));
+        assert!(is_invalid(specs.next().unwrap().into_product(USD, "products[3]", &catalog), "products[3].catalog"));
+    }
+
+    #[test]
+    fn totals_are_checked_with_and_without_a_breakdown() {
+        let scratch = Scratch::new("totals");
+        // Top level keys have to come before the products in TOML
+        let order = |top: &str| format!("placed = \"2025-10-01\"\nsubtotal = \"40.00\"\n{}\n[[products]]\nname = \"Mask\"\nbase_price = \"40.00\"\nsticker_price = \"40.00\"\npaid = \"40.00\"", top);
+        assert!(is_invalid(scratch.add(&order("total = \"35.00\""), &[]), "the total"));
+        assert!(is_invalid(scratch.add(&order("total = \"45.00\"\nshipping = \"4.00\""), &[]), "the total"));
+        assert!(is_invalid(scratch.add(&order(""), &["--total", "35", "--shipping", "4"]), "the total"));
+        assert!(!scratch.0.join("orders.ptl").exists());
+
+        scratch.add(&order("total = \"45.00\""), &[]).unwrap();
+        scratch.add(&order("total = \"35.00\"\ncredits = \"5.00\""), &[]).unwrap();
+        scratch.add(&order(""), &["--total", "44", "--shipping", "4"]).unwrap();
+        let ledger = storage::load(&scratch.0.join("orders.ptl")).unwrap();
+        assert_eq!(ledger.orders.len(), 3);
+        assert!(ledger.orders[0].breakdown.is_none());
+        assert_eq!(ledger.orders[1].breakdown.map(|x| x.credits), Some(Money::from_minor(500)));
+    }
+}
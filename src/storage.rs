@@ -0,0 +1,124 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::Ledger;
+use crate::backup;
+use crate::error::Error;
+use crate::format;
+
+// Reads the ledger (the orders and everything else) that was saved to path during a previous run
+// of purchase_tracker. Files written in an older format are upgraded to the current one on the way
+// in.
+pub fn load(path: &Path) -> Result<Ledger, Error> {
+    let bytes = match fs::read(path) {
+        Ok(x) => x,
+        Err(x) => return Err(Error::io("reading", path, x))
+    };
+    let (ledger, version) = match format::decode(&bytes) {
+        Ok(x) => x,
+        Err(x) => return Err(Error::Decode(path.to_path_buf(), x))
+    };
+    if version < format::CURRENT_VERSION {
+        println!("Note: {} was written in file format version {}. It will be saved in version {} the next time it's written.", path.display(), version, format::CURRENT_VERSION);
+    }
+    Ok(ledger)
+}
+
+// Like load, but for files that are only being peeked at (such as backups), where a file that
+// can't be read shouldn't stop the program
+pub fn try_load(path: &Path) -> Option<Ledger> {
+    let bytes = fs::read(path).ok()?;
+    format::decode(&bytes).ok().map(|(ledger, _)| ledger)
+}
+
+// Writes a ledger to a file that was freshly created by the caller. path is where the ledger is
+// headed in the end, which is what gets named if encoding it fails.
+pub fn write_new(file: &mut File, path: &Path, ledger: &Ledger) -> Result<(), Error> {
+    let bytes = match format::encode(ledger) {
+        Ok(x) => x,
+        Err(x) => return Err(Error::Encode(path.to_path_buf(), x))
+    };
+    match file.write_all(&bytes).and_then(|_| file.sync_all()) {
+        Ok(_) => Ok(()),
+        Err(x) => Err(Error::io("writing to", path, x))
+    }
+}
+
+// Writes a ledger to path without ever leaving a half-written file behind.
+// The encoded orders go to a temporary file in the same directory as path, which is flushed to
+// disk and then renamed over path. Renaming within a directory is atomic, so anyone reading path
+// (including a future run of purchase_tracker after a crash) sees either the old list of orders or
+// the new one, never a mix of the two.
+// Whatever was in path before gets backed up first, see backup.rs.
+pub fn save(path: &Path, ledger: &Ledger) -> Result<(), Error> {
+    if let Some(x) = backup::snapshot(path)? {
+        println!("Backed up the previous contents of {} to {}", path.display(), x.display());
+    }
+
+    let temp_path = temp_path_for(path);
+    let result = write_then_rename(path, &temp_path, ledger);
+    if result.is_err() {
+        // The temporary file is useless if anything went wrong, and path is still untouched
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // The new orders are safely saved at this point, so old backups failing to get cleaned up
+    // isn't worth reporting as a failure
+    if let Err(x) = backup::prune(path) {
+        println!("Warning: failed to clean up old backups of {}. {}", path.display(), x.to_string());
+    }
+    result
+}
+
+fn write_then_rename(path: &Path, temp_path: &Path, ledger: &Ledger) -> Result<(), Error> {
+    let mut temp_file = match File::create_new(temp_path) {
+        Ok(x) => x,
+        Err(x) => return Err(Error::io("creating the temporary file", temp_path, x))
+    };
+
+    // Keep the permissions of the file we're replacing, if there is one
+    if let Ok(metadata) = fs::metadata(path)
+        && let Err(x) = temp_file.set_permissions(metadata.permissions()) {
+        return Err(Error::io("setting the permissions of", temp_path, x));
+    }
+
+    write_new(&mut temp_file, path, ledger)?;
+    drop(temp_file);
+
+    if let Err(x) = fs::rename(temp_path, path) {
+        return Err(Error::io(&format!("moving {} over", temp_path.display()), path, x));
+    }
+    match sync_dir(path) {
+        Ok(_) => Ok(()),
+        Err(x) => Err(Error::io("flushing the directory entry of", path, x))
+    }
+}
+
+// The rename itself only becomes durable once the directory entry is flushed too
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    File::open(parent_dir(path))?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(x) if !x.as_os_str().is_empty() => x,
+        _ => Path::new(".")
+    }
+}
+
+// The temporary file has to live next to path, since rename is only atomic within one filesystem
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = match path.file_name() {
+        Some(x) => x.to_string_lossy().to_string(),
+        None => "ledger".to_string()
+    };
+    parent_dir(path).join(format!(".{}.tmp-{}", file_name, std::process::id()))
+}
@@ -0,0 +1,303 @@
+use bincode::{Decode, Encode};
+use std::io;
+use std::path::Path;
+
+use crate::args::Args;
+use crate::category::Filter;
+use crate::error::Error;
+use crate::{ask, ask_yes_no, read_answer, storage, Ledger, Order};
+
+// Somewhere orders are placed. Every vendor is stored once in Ledger.vendors, and orders point at
+// one by its index there (see Order.vendor), so vendors are never removed from that list.
+#[derive(Encode, Decode, Debug)]
+pub struct Vendor {
+    pub name: String,
+    pub website: String, // These three are "" when they aren't known
+    pub contact: String,
+    pub notes: String
+}
+
+impl ToString for Vendor {
+    fn to_string(&self) -> String {
+        let mut temp = self.name.clone();
+        for extra in [&self.website, &self.contact, &self.notes] {
+            if !extra.is_empty() {
+                temp.push_str("  ");
+                temp.push_str(extra);
+            }
+        }
+        temp
+    }
+}
+
+// The name of the vendor of an order, for listings
+pub fn vendor_name<'a>(order: &Order, vendors: &'a [Vendor]) -> &'a str {
+    match order.vendor.and_then(|x| vendors.get(x)) {
+        Some(x) => &x.name,
+        None => "(no vendor)"
+    }
+}
+
+// The vendor with exactly this name, ignoring case
+pub fn find(vendors: &[Vendor], name: &str) -> Option<usize> {
+    vendors.iter().position(|x| x.name.eq_ignore_ascii_case(name.trim()))
+}
+
+// A vendor's name, trimmed. Orders and commands pick vendors by name, so it can't be blank.
+fn parse_name(input: &str, field: &str) -> Result<String, Error> {
+    match input.trim() {
+        "" => Err(Error::validation(field, "a vendor needs a name")),
+        x => Ok(x.to_string())
+    }
+}
+
+// How well query matches name, lower is better. None if it doesn't match at all.
+// Exact matches beat prefixes, which beat matches anywhere in the name, which beat names that
+// merely have all the letters of query in the same order ("drkwd" matches "Darkwood Armory").
+fn fuzzy_score(query: &str, name: &str) -> Option<u32> {
+    let query = query.trim().to_lowercase();
+    let name = name.to_lowercase();
+    if name == query {
+        return Some(0);
+    }
+    if name.starts_with(&query) {
+        return Some(1);
+    }
+    if name.contains(&query) {
+        return Some(2);
+    }
+    let mut letters = name.chars();
+    match query.chars().filter(|x| !x.is_whitespace()).all(|x| letters.any(|y| y == x)) {
+        true => Some(3),
+        false => None
+    }
+}
+
+// The best matches for query among names (given as (index, name)), best first. The catalog uses
+// this too.
+pub fn fuzzy_matches<'a>(names: impl IntoIterator<Item = (usize, &'a str)>, query: &str) -> Vec<usize> {
+    let mut scored: Vec<(u32, usize)> = names.into_iter()
+        .filter_map(|(index, name)| fuzzy_score(query, name).map(|x| (x, index)))
+        .collect();
+    scored.sort();
+    scored.into_iter().take(5).map(|(_, index)| index).collect()
+}
+
+// Asks for a new vendor called name
+fn new_vendor_cli(stdin: &io::Stdin, name: &str) -> Result<Vendor, Error> {
+    println!("What's the website of {}? (Leave it blank if you don't know)", name);
+    let website = read_answer(stdin)?.trim().to_string();
+    println!("How do you contact {}? (An email address or phone number, or leave it blank)", name);
+    let contact = read_answer(stdin)?.trim().to_string();
+    println!("Any notes about {}? (Leave it blank if there aren't any)", name);
+    let notes = read_answer(stdin)?.trim().to_string();
+    Ok(Vendor {
+        name: name.to_string(),
+        website,
+        contact,
+        notes
+    })
+}
+
+// Asks where an order was placed, offering the vendors that are already known. New vendors are
+// added to vendors. None if the question was skipped.
+pub fn pick_vendor_cli(vendors: &mut Vec<Vendor>) -> Result<Option<usize>, Error> {
+    let stdin = io::stdin();
+    loop {
+        println!("Where did you buy this? (Type part of the name of the vendor, or leave it blank to skip this)");
+        let query = read_answer(&stdin)?.trim().to_string();
+        if query.is_empty() {
+            return Ok(None);
+        }
+        let matches = fuzzy_matches(vendors.iter().map(|x| x.name.as_str()).enumerate(), &query);
+        if matches.is_empty() {
+            println!("None of the vendors you've bought from before match \"{}\". Add it as a new vendor? (Type \"Yes\" or \"No\")", query);
+            if ask_yes_no(&stdin)? {
+                vendors.push(new_vendor_cli(&stdin, &query)?);
+                return Ok(Some(vendors.len() - 1));
+            }
+            continue;
+        }
+        for (number, index) in matches.iter().enumerate() {
+            println!("  {}. {}", number, vendors[*index].to_string());
+        }
+        println!("Type one of the numbers above, \"new\" to add \"{}\" as a new vendor, or leave it blank to search again.", query);
+        let picked = ask(&stdin, |x| match x.trim() {
+            "" => Ok(None),
+            "new" => Ok(Some(None)),
+            y => match y.parse::<usize>() {
+                Ok(z) if z < matches.len() => Ok(Some(Some(matches[z]))),
+                _ => Err(Error::validation("the vendor", format!("pick a number from 0 to {}, or type \"new\"", matches.len() - 1)))
+            }
+        })?;
+        match picked {
+            None => continue,
+            Some(Some(x)) => return Ok(Some(x)),
+            Some(None) => {
+                if let Some(x) = find(vendors, &query) {
+                    println!("There's already a vendor called {}, so that one was picked.", vendors[x].name);
+                    return Ok(Some(x));
+                }
+                vendors.push(new_vendor_cli(&stdin, &query)?);
+                return Ok(Some(vendors.len() - 1));
+            }
+        }
+    }
+}
+
+// Turns the VENDOR argument of a command (its number or its name) into an index into vendors
+fn parse_vendor(input: &str, vendors: &[Vendor], path: &Path) -> Result<usize, Error> {
+    if let Ok(x) = input.trim().parse::<usize>() {
+        return match x < vendors.len() {
+            true => Ok(x),
+            false => Err(Error::validation("VENDOR", format!("{} only has {} vendors, numbered starting from 0, so there's no vendor {}", path.display(), vendors.len(), x)))
+        };
+    }
+    match find(vendors, input) {
+        Some(x) => Ok(x),
+        None => Err(Error::validation("VENDOR", format!("{} doesn't have a vendor called \"{}\". Run 'purchase_tracker vendors {}' to see the vendors it has", path.display(), input.trim(), path.display())))
+    }
+}
+
+// purchase_tracker vendors [FILE] [--category CATEGORY] [--tag TAG] | vendors add [FILE] [NAME] [...] | vendors set [FILE] [VENDOR] [...]
+pub fn vendors_cli(raw_args: &[String]) -> Result<(), Error> {
+    let args = Args::parse(raw_args, &["name", "website", "contact", "notes", "category", "tag"], &[])?;
+    let usage = "purchase_tracker vendors [FILE] [--category CATEGORY] [--tag TAG] | purchase_tracker vendors add [FILE] [NAME] [--website URL] [--contact TEXT] [--notes TEXT] | purchase_tracker vendors set [FILE] [VENDOR] [--name NAME] [--website URL] [--contact TEXT] [--notes TEXT]";
+    match args.positional.iter().map(|x| x.as_str()).collect::<Vec<&str>>().as_slice() {
+        ["add", path, name] => {
+            let path = Path::new(path);
+            let name = parse_name(name, "NAME")?;
+            let mut ledger = storage::load(path)?;
+            if let Some(x) = find(&ledger.vendors, &name) {
+                return Err(Error::validation("NAME", format!("{} already has a vendor called {} (number {})", path.display(), ledger.vendors[x].name, x)));
+            }
+            ledger.vendors.push(Vendor {
+                name,
+                website: args.value("website").unwrap_or("").trim().to_string(),
+                contact: args.value("contact").unwrap_or("").trim().to_string(),
+                notes: args.value("notes").unwrap_or("").trim().to_string()
+            });
+            storage::save(path, &ledger)?;
+            println!("Added vendor {}: {}", ledger.vendors.len() - 1, ledger.vendors[ledger.vendors.len() - 1].to_string());
+            Ok(())
+        },
+        ["set", path, vendor] => {
+            let path = Path::new(path);
+            let name = match args.value("name") {
+                Some(x) => Some(parse_name(x, "--name")?),
+                None => None
+            };
+            let mut ledger = storage::load(path)?;
+            let index = parse_vendor(vendor, &ledger.vendors, path)?;
+            if let Some(x) = &name
+                && let Some(y) = find(&ledger.vendors, x)
+                && y != index {
+                return Err(Error::validation("--name", format!("{} already has a vendor called {} (number {})", path.display(), ledger.vendors[y].name, y)));
+            }
+            let before = ledger.vendors[index].to_string();
+            let vendor = &mut ledger.vendors[index];
+            if let Some(x) = name {
+                vendor.name = x;
+            }
+            for (flag, field) in [("website", &mut vendor.website), ("contact", &mut vendor.contact), ("notes", &mut vendor.notes)] {
+                if let Some(x) = args.value(flag) {
+                    *field = x.trim().to_string();
+                }
+            }
+            let after = vendor.to_string();
+            storage::save(path, &ledger)?;
+            println!("Before: {}", before);
+            println!("After:  {}", after);
+            Ok(())
+        },
+        [path] => {
+            let ledger: Ledger = storage::load(Path::new(path))?;
+            if ledger.vendors.is_empty() {
+                println!("{} doesn't have any vendors yet.", path);
+                return Ok(());
+            }
+            // With a filter, only vendors that had orders like that are shown, and only those
+            // orders are counted
+            let filter = Filter::from_args(&args);
+            let mut shown = 0;
+            for (index, vendor) in ledger.vendors.iter().enumerate() {
+                let orders = ledger.orders.iter().filter(|x| x.vendor == Some(index) && filter.matches(x)).count();
+                if orders == 0 && !filter.is_empty() {
+                    continue;
+                }
+                println!("{}. {} ({} orders{})", index, vendor.to_string(), orders, filter.describe());
+                shown += 1;
+            }
+            if shown == 0 {
+                println!("{} doesn't have any orders{} from a vendor.", path, filter.describe());
+            }
+            Ok(())
+        },
+        _ => Err(Error::usage(usage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    fn matches(names: &[&str], query: &str) -> Vec<usize> {
+        fuzzy_matches(names.iter().copied().enumerate(), query)
+    }
+
+    #[test]
+    fn fuzzy_scores_rank_how_well_names_match() {
+        assert_eq!(fuzzy_score("darkwood armory", "Darkwood Armory"), Some(0));
+        assert_eq!(fuzzy_score(" DARKWOOD ", "Darkwood Armory"), Some(1));
+        assert_eq!(fuzzy_score("armory", "Darkwood Armory"), Some(2));
+        assert_eq!(fuzzy_score("drkwd", "Darkwood Armory"), Some(3));
+        assert_eq!(fuzzy_score("dw arm", "Darkwood Armory"), Some(3));
+        // The letters have to come in the same order
+        assert_eq!(fuzzy_score("wdk", "Darkwood Armory"), None);
+        assert_eq!(fuzzy_score("darkwood armoury", "Darkwood Armory"), None);
+    }
+
+    #[test]
+    fn better_matches_come_first() {
+        let names = ["Fencing Post", "Absolute Fencing", "Fence", "fencing", "Leon Paul"];
+        assert_eq!(matches(&names, "Fencing"), vec!(3, 0, 1));
+        assert_eq!(matches(&names, "fenc"), vec!(0, 2, 3, 1));
+        assert_eq!(matches(&names, "fp"), vec!(0));
+        assert_eq!(matches(&names, "lp"), vec!(4));
+        assert!(matches(&names, "zz").is_empty());
+    }
+
+    #[test]
+    fn only_the_five_best_matches_are_kept() {
+        let names = ["Shop 1", "Shop 2", "shop", "Shop 3", "The Shop", "Shop 4", "Shop 5", "Sh-op"];
+        // Names that match equally well stay in the order they were added
+        assert_eq!(matches(&names, "shop"), vec!(2, 0, 1, 3, 5));
+        assert_eq!(matches(&names, "sop"), vec!(0, 1, 2, 3, 4));
+        assert_eq!(matches(&names[5..], "shop"), vec!(0, 1, 2));
+    }
+
+    #[test]
+    fn vendors_need_a_name() {
+        assert_eq!(parse_name("  Fencing Post ", "NAME").unwrap(), "Fencing Post");
+        assert!(matches!(parse_name("   ", "NAME"), Err(Error::Validation { field, .. }) if field == "NAME"));
+
+        let dir = env::temp_dir().join(format!("purchase_tracker_vendor_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("orders.ptl").display().to_string();
+        storage::save(Path::new(&path), &Ledger::new()).unwrap();
+        let run = |x: &[&str]| vendors_cli(&x.iter().map(|y| y.to_string()).collect::<Vec<String>>());
+        let added = run(&["add", &path, "Fencing Post"]);
+        let blank = run(&["add", &path, " "]);
+        let renamed = run(&["set", &path, "0", "--name", ""]);
+        let vendors = storage::load(Path::new(&path)).map(|x| x.vendors.into_iter().map(|y| y.name).collect::<Vec<String>>());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(added.is_ok());
+        assert!(matches!(blank, Err(Error::Validation { field, .. }) if field == "NAME"));
+        assert!(matches!(renamed, Err(Error::Validation { field, .. }) if field == "--name"));
+        assert_eq!(vendors.unwrap(), vec!("Fencing Post"));
+    }
+}